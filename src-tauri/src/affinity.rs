@@ -0,0 +1,67 @@
+//! Pinning the backend to a set of CPU cores, for steadier control-loop latency.
+//! Linux uses sched_setaffinity on every backend thread, Windows SetProcessAffinityMask.
+//! macOS has no way to pin another process (affinity tags are per-thread hints), so there
+//! it is reported as unsupported.
+
+use std::process::Child;
+
+/// Number of cores indices are validated against: those available to the viewer, which
+/// the backend inherits
+pub fn core_count() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Checks that every index is below core_count (and below 64 on Windows, the width of an
+/// affinity mask)
+pub fn validate(cores: &[usize]) -> Result<(), String> {
+    let limit = if cfg!(windows) { core_count().min(64) } else { core_count() };
+    match cores.iter().find(|&&c| c >= limit) {
+        Some(core) => Err(format!("CPU core {} does not exist; valid cores are 0..{}", core, limit)),
+        None => Ok(()),
+    }
+}
+
+/// Restricts `child` to `cores`; an empty list allows all cores again
+#[cfg(target_os = "linux")]
+pub fn apply(child: &Child, cores: &[usize]) -> Result<(), String> {
+    let all: Vec<usize> = (0..core_count()).collect();
+    let cores = if cores.is_empty() { &all[..] } else { cores };
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &core in cores {
+        unsafe { libc::CPU_SET(core, &mut set) };
+    }
+    // Affinity is per thread; threads the backend already started keep theirs otherwise
+    let pid = child.id() as libc::pid_t;
+    let mut tids: Vec<libc::pid_t> = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|entries| entries.filter_map(Result::ok).filter_map(|e| e.file_name().to_str()?.parse().ok()).collect())
+        .unwrap_or_default();
+    if tids.is_empty() {
+        tids.push(pid);
+    }
+    for tid in tids {
+        if unsafe { libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+            return Err(format!("sched_setaffinity failed: {}", std::io::Error::last_os_error()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn apply(child: &Child, cores: &[usize]) -> Result<(), String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::SetProcessAffinityMask;
+    let mask = if cores.is_empty() {
+        usize::MAX >> (usize::BITS as usize - core_count().min(usize::BITS as usize))
+    } else {
+        cores.iter().fold(0usize, |mask, &core| mask | (1 << core))
+    };
+    if unsafe { SetProcessAffinityMask(child.as_raw_handle() as _, mask) } == 0 {
+        return Err(format!("SetProcessAffinityMask failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn apply(_child: &Child, _cores: &[usize]) -> Result<(), String> {
+    Err("CPU affinity is not supported on this platform".to_string())
+}
@@ -0,0 +1,757 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tauri::Manager; // for app.path()
+
+use crate::config;
+use crate::error::PythonError;
+use crate::logs::{self, Stream};
+use crate::pip_errors::{self, BootstrapErrorKind};
+use crate::{quarantine, requirements, update};
+
+/// Duration of a single bootstrap phase
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct PhaseTiming {
+    /// One of "copy", "venv_create", "pip_upgrade", "requirements_install", "package_install"
+    pub phase: String,
+    pub duration_ms: u64,
+}
+
+/// Per-phase timings of a runtime bootstrap, emitted as `python-bootstrap-timings`
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BootstrapTimings {
+    pub phases: Vec<PhaseTiming>,
+    pub total_ms: u64,
+    /// Unix timestamp (seconds) of when the bootstrap finished
+    pub finished_at: u64,
+}
+
+/// Coarse bootstrap state for the UI, readable via bootstrap_phase() and emitted as
+/// `python-bootstrap-phase` on every change
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+pub enum BootstrapPhase {
+    Idle,
+    Copying,
+    CreatingVenv,
+    InstallingDeps,
+    InstallingPackage,
+    Done,
+}
+
+static PHASE: Mutex<BootstrapPhase> = Mutex::new(BootstrapPhase::Idle);
+
+pub fn current_phase() -> BootstrapPhase {
+    *PHASE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn set_phase<R: tauri::Runtime>(app: &tauri::AppHandle<R>, phase: BootstrapPhase) {
+    *PHASE.lock().unwrap_or_else(|e| e.into_inner()) = phase;
+    let _ = app.emit("python-bootstrap-phase", phase);
+}
+
+struct PhaseTimer {
+    started: Instant,
+    phases: Vec<PhaseTiming>,
+}
+
+impl PhaseTimer {
+    fn new() -> Self {
+        Self { started: Instant::now(), phases: Vec::new() }
+    }
+
+    fn time<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.phases.push(PhaseTiming {
+            phase: phase.to_string(),
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+        result
+    }
+
+    fn finish(self) -> BootstrapTimings {
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        BootstrapTimings {
+            phases: self.phases,
+            total_ms: self.started.elapsed().as_millis() as u64,
+            finished_at,
+        }
+    }
+}
+
+// simple recursive copy helper for bootstrapping runtime python from resources
+pub fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if !src.exists() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let ty = entry.file_type()?;
+        if ty.is_dir() {
+            copy_dir_all(&entry.path(), &dst.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the bundled televoodoo package to `dst`: only the top-level entries in the
+/// `source_include` config (package source and packaging metadata) unless
+/// `copy_full_source` is set. Listed entries that don't exist are skipped.
+pub fn copy_televoodoo_source<R: tauri::Runtime>(app: &tauri::AppHandle<R>, src: &Path, dst: &Path) -> std::io::Result<()> {
+    let cfg = config::get(app);
+    if cfg.copy_full_source {
+        return copy_dir_all(src, dst);
+    }
+    std::fs::create_dir_all(dst)?;
+    for name in &cfg.source_include {
+        // Entries are plain names relative to the package root
+        if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+            continue;
+        }
+        let entry = src.join(name);
+        if entry.is_dir() {
+            copy_dir_all(&entry, &dst.join(name))?;
+        } else if entry.is_file() {
+            std::fs::copy(&entry, dst.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn find_bundled_python_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    if let Ok(res_dir) = app.path().resource_dir() {
+        let candidate1 = res_dir.join("python");
+        if candidate1.exists() {
+            return Some(candidate1);
+        }
+        let candidate2 = res_dir.join("resources").join("python");
+        if candidate2.exists() {
+            return Some(candidate2);
+        }
+    }
+    None
+}
+
+/// Interpreter of a venv shipped inside the bundled resources (`python/.venv`), if it runs
+pub fn bundled_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    let python = find_bundled_python_dir(app)?.join(".venv").join("bin").join("python");
+    interpreter_works(&python).then_some(python)
+}
+
+/// Overrides the app data dir, for machines where the default location is read-only
+pub const DATA_DIR_ENV: &str = "TELEVOODOO_VIEWER_DATA_DIR";
+
+/// App data dir, honoring the TELEVOODOO_VIEWER_DATA_DIR override
+pub fn data_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    match std::env::var_os(DATA_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => app.path().app_data_dir().ok(),
+    }
+}
+
+/// Probes that `dir` can be created and written to by creating and removing a temp file
+pub fn ensure_writable(dir: &Path) -> Result<(), PythonError> {
+    let read_only = || PythonError::DataDirReadOnly { path: dir.to_path_buf() };
+    std::fs::create_dir_all(dir).map_err(|_| read_only())?;
+    let probe = dir.join(format!(".write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"").map_err(|_| read_only())?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Free bytes available to this user on the volume holding `dir` (or its nearest existing
+/// ancestor), or None if it can't be determined
+pub fn available_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|d| d.exists())?;
+    platform_available_space(existing)
+}
+
+#[cfg(unix)]
+fn platform_available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn platform_available_space(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Fails with InsufficientDiskSpace when the volume of `dir` has less free space than the
+/// `bootstrap_required_bytes` config. Unknown free space is not treated as a failure.
+pub fn ensure_free_space<R: tauri::Runtime>(app: &tauri::AppHandle<R>, dir: &Path) -> Result<(), PythonError> {
+    let required = config::get(app).bootstrap_required_bytes;
+    match available_space(dir) {
+        Some(available) if available < required => Err(PythonError::InsufficientDiskSpace { available, required }),
+        _ => Ok(()),
+    }
+}
+
+/// Runtime python dir under the app data dir, named per config (default `python`)
+pub fn runtime_python_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    let app_data_dir = data_dir(app)?;
+    let mut name = config::get(app).python_dir_name;
+    // A hand-edited config could bypass set_python_dir_name; never escape app_data
+    if config::validate_python_dir_name(&name).is_err() {
+        name = "python".to_string();
+    }
+    Some(app_data_dir.join(name))
+}
+
+/// Ensures a runtime venv exists under App Support, bootstrapping it from bundled
+/// Resources if missing. Returns the runtime interpreter when available, or an error
+/// if the start was cancelled mid-bootstrap.
+pub fn ensure_runtime_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<Option<PathBuf>, PythonError> {
+    let Some(runtime_py_dir) = runtime_python_dir(app) else { return Ok(None) };
+    let runtime_python = runtime_py_dir.join(".venv").join("bin").join("python");
+
+    let venv_root = runtime_py_dir.join(".venv");
+    if venv_root.exists() {
+        let health = validate_venv(&venv_root);
+        if health != VenvHealth::Healthy {
+            report_stale_venv(app, &venv_root, &health);
+            quarantine::discard_venv(app, &venv_root, &health.describe());
+        }
+    }
+    if runtime_python.exists() && !interpreter_works(&runtime_python) {
+        report_degraded(app, "Runtime venv python is broken; rebuilding the venv");
+        quarantine::discard_venv(app, &venv_root, "runtime venv python does not start");
+    }
+
+    if !runtime_python.exists() {
+        if let Some(bundled) = find_bundled_python_dir(app) {
+            let televoodoo_dir = bundled.join("televoodoo");
+            if televoodoo_dir.join("pyproject.toml").exists() {
+                // Fail loudly instead of letting every bootstrap step silently fail
+                ensure_writable(&runtime_py_dir)?;
+                ensure_free_space(app, &runtime_py_dir)?;
+                let mut timer = PhaseTimer::new();
+                let result = bootstrap_from_bundle(app, &mut timer, &televoodoo_dir, &runtime_py_dir);
+                if result.is_err() {
+                    // Don't leave a half-built venv that would be mistaken for a working one
+                    let _ = std::fs::remove_dir_all(runtime_py_dir.join(".venv"));
+                } else {
+                    set_phase(app, BootstrapPhase::Done);
+                }
+                set_phase(app, BootstrapPhase::Idle);
+                result?;
+
+                let timings = timer.finish();
+                let _ = app.emit("python-bootstrap-timings", &timings);
+                // Keep the last run around so slow first-runs can be compared
+                let _ = config::update(app, |c| c.last_bootstrap_timings = Some(timings));
+                update::record_bundle_fingerprint(app);
+            }
+        }
+    }
+    if runtime_python.exists() && !interpreter_works(&runtime_python) {
+        report_degraded(app, "Runtime venv python is still broken after rebuild; falling back to system python");
+        return Ok(None);
+    }
+    Ok(runtime_python.exists().then_some(runtime_python))
+}
+
+/// What ensure_runtime_python would do, without doing it: whether it would (re)build the
+/// runtime venv from the bundle, and the interpreter it would then return
+pub fn runtime_python_plan<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> (bool, Option<PathBuf>) {
+    let Some(runtime_py_dir) = runtime_python_dir(app) else { return (false, None) };
+    let venv_root = runtime_py_dir.join(".venv");
+    let runtime_python = venv_root.join("bin").join("python");
+    let healthy = runtime_python.exists() && validate_venv(&venv_root) == VenvHealth::Healthy && interpreter_works(&runtime_python);
+    if healthy {
+        return (false, Some(runtime_python));
+    }
+    let bundled = find_bundled_python_dir(app).filter(|dir| dir.join("televoodoo").join("pyproject.toml").exists());
+    match bundled {
+        Some(_) => (true, Some(runtime_python)),
+        None => (false, None),
+    }
+}
+
+/// Oldest system Python accepted by discover_system_python
+pub const MIN_PYTHON_VERSION: (u32, u32) = (3, 10);
+
+/// A system interpreter picked by discover_system_python
+#[derive(Clone, serde::Serialize)]
+pub struct ResolvedPython {
+    /// Candidate name as configured (resolved through PATH when spawning)
+    pub command: String,
+    pub version: String,
+}
+
+// Result of the most recent discovery, reported by python_status
+static RESOLVED_PYTHON: Mutex<Option<ResolvedPython>> = Mutex::new(None);
+
+pub fn last_resolved_python() -> Option<ResolvedPython> {
+    RESOLVED_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Tries the configured `python_candidates` in order and returns the first that runs and
+/// is at least MIN_PYTHON_VERSION. Each probe is logged as a debug line.
+pub fn discover_system_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<ResolvedPython> {
+    let candidates = config::get(app).python_candidates;
+    let debug = |msg: String| logs::forward_tool_line(app, "discovery", Stream::Stdout, &format!("DEBUG: {}", msg));
+    debug(format!("python resolution order: {}", candidates.join(", ")));
+    let mut resolved = None;
+    for candidate in candidates {
+        match probe_version(&candidate) {
+            Some((major, minor, version)) if (major, minor) >= MIN_PYTHON_VERSION => {
+                debug(format!("using {} ({})", candidate, version));
+                resolved = Some(ResolvedPython { command: candidate, version });
+                break;
+            }
+            Some((_, _, version)) => debug(format!("skipping {}: version {} is too old", candidate, version)),
+            None => debug(format!("skipping {}: not found or not runnable", candidate)),
+        }
+    }
+    if resolved.is_none() {
+        let (major, minor) = MIN_PYTHON_VERSION;
+        debug(format!("no candidate satisfies python >= {}.{}", major, minor));
+    }
+    *RESOLVED_PYTHON.lock().unwrap_or_else(|e| e.into_inner()) = resolved.clone();
+    resolved
+}
+
+/// Command to use for a system interpreter: the discovered one, else plain `python3`
+pub fn system_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> String {
+    discover_system_python(app).map(|p| p.command).unwrap_or_else(|| "python3".to_string())
+}
+
+// Returns (major, minor, "major.minor.micro") of the interpreter, if it runs
+fn probe_version(python: &str) -> Option<(u32, u32, String)> {
+    let output = Command::new(python)
+        .args(["-c", "import sys; print('%d.%d.%d' % sys.version_info[:3])"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    let (major, minor) = (parts.next()??, parts.next()??);
+    Some((major, minor, version))
+}
+
+/// Runs `<python> --version` to catch venvs whose interpreter exists but can't start
+pub fn interpreter_works(python: &Path) -> bool {
+    Command::new(python)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Result of validate_venv
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VenvHealth {
+    Healthy,
+    /// No `pyvenv.cfg` in the venv root
+    MissingConfig,
+    /// `pyvenv.cfg` could not be read or lacks a `home` entry
+    Malformed { reason: String },
+    /// The base interpreter recorded in `pyvenv.cfg` no longer exists (e.g. the app or
+    /// Python installation was moved)
+    BaseMissing { path: PathBuf },
+}
+
+impl VenvHealth {
+    pub fn describe(&self) -> String {
+        match self {
+            VenvHealth::Healthy => "healthy".to_string(),
+            VenvHealth::MissingConfig => "pyvenv.cfg is missing".to_string(),
+            VenvHealth::Malformed { reason } => format!("pyvenv.cfg is malformed: {}", reason),
+            VenvHealth::BaseMissing { path } => format!("base interpreter no longer exists: {}", path.display()),
+        }
+    }
+}
+
+/// Parses `<venv_root>/pyvenv.cfg` and checks that the base interpreter it points at
+/// (`home`, plus `executable` when present) still exists
+pub fn validate_venv(venv_root: &Path) -> VenvHealth {
+    let cfg_path = venv_root.join("pyvenv.cfg");
+    if !cfg_path.exists() {
+        return VenvHealth::MissingConfig;
+    }
+    let text = match std::fs::read_to_string(&cfg_path) {
+        Ok(text) => text,
+        Err(e) => return VenvHealth::Malformed { reason: e.to_string() },
+    };
+    let mut home = None;
+    let mut executable = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return VenvHealth::Malformed { reason: format!("line {} is not `key = value`", n + 1) };
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "home" => home = Some(PathBuf::from(value.trim())),
+            "executable" => executable = Some(PathBuf::from(value.trim())),
+            _ => {}
+        }
+    }
+    let Some(home) = home.filter(|h| !h.as_os_str().is_empty()) else {
+        return VenvHealth::Malformed { reason: "no home entry".to_string() };
+    };
+    if !home.is_dir() {
+        return VenvHealth::BaseMissing { path: home };
+    }
+    match executable {
+        Some(exe) if !exe.exists() => VenvHealth::BaseMissing { path: exe },
+        _ => VenvHealth::Healthy,
+    }
+}
+
+/// Payload of `python-venv-stale`
+#[derive(Clone, serde::Serialize)]
+struct VenvStale {
+    path: PathBuf,
+    health: VenvHealth,
+    message: String,
+}
+
+fn report_stale_venv<R: tauri::Runtime>(app: &tauri::AppHandle<R>, venv_root: &Path, health: &VenvHealth) {
+    let message = format!("Runtime venv at {} is stale ({}); rebuilding it", venv_root.display(), health.describe());
+    logs::forward_tool_line(app, "bootstrap", Stream::Stderr, &format!("WARNING: {}", message));
+    let _ = app.emit("python-venv-stale", VenvStale { path: venv_root.to_path_buf(), health: health.clone(), message });
+}
+
+/// Mirrors `source activate` for a command whose program lives in a venv (bin/ or Scripts/
+/// next to `pyvenv.cfg`): sets VIRTUAL_ENV and puts the venv's bin dir first on PATH, so
+/// packages that shell out to sibling tools find the venv's copies. Programs outside a
+/// venv are left untouched.
+pub fn activate_venv(cmd: &mut Command) {
+    let program = PathBuf::from(cmd.get_program());
+    let Some(bin_dir) = program.parent().filter(|d| !d.as_os_str().is_empty()).map(Path::to_path_buf) else { return };
+    let Some(venv_root) = bin_dir.parent().filter(|r| r.join("pyvenv.cfg").is_file()).map(Path::to_path_buf) else { return };
+    let current = cmd
+        .get_envs()
+        .find(|(key, _)| *key == "PATH")
+        .map(|(_, value)| value.map(|v| v.to_os_string()))
+        .unwrap_or_else(|| std::env::var_os("PATH"));
+    let mut paths = vec![bin_dir];
+    paths.extend(current.iter().flat_map(std::env::split_paths));
+    if let Ok(path) = std::env::join_paths(paths) {
+        cmd.env("PATH", path);
+    }
+    cmd.env("VIRTUAL_ENV", venv_root);
+}
+
+/// Payload of `python-runtime-degraded`
+#[derive(Clone, serde::Serialize)]
+struct Degraded {
+    message: String,
+}
+
+fn report_degraded<R: tauri::Runtime>(app: &tauri::AppHandle<R>, message: &str) {
+    logs::forward_tool_line(app, "bootstrap", Stream::Stderr, &format!("WARNING: {}", message));
+    let _ = app.emit("python-runtime-degraded", Degraded { message: message.to_string() });
+}
+
+fn bootstrap_from_bundle<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    timer: &mut PhaseTimer,
+    televoodoo_dir: &Path,
+    runtime_py_dir: &Path,
+) -> Result<(), PythonError> {
+    // A malformed requirements file fails here, with its line, rather than midway through pip
+    let req = televoodoo_dir.join("requirements.txt");
+    let requirement_count = if req.exists() { requirements::parse_file(&req)?.len() } else { 0 };
+    let runtime_venv_bin = runtime_py_dir.join(".venv").join("bin");
+    let runtime_python = runtime_venv_bin.join("python");
+    let runtime_pip = runtime_venv_bin.join("pip");
+    let runtime_televoodoo = runtime_py_dir.join("televoodoo");
+
+    let cfg = config::get(app);
+    let net = cfg.pip_network_args();
+    logs::forward_tool_line(
+        app,
+        "bootstrap",
+        Stream::Stdout,
+        &format!("pip network options: timeout={}s retries={}", cfg.pip_timeout_secs, cfg.pip_retries),
+    );
+
+    let _ = std::fs::create_dir_all(runtime_py_dir);
+    check_cancelled()?;
+    set_phase(app, BootstrapPhase::Copying);
+    emit_progress(app, 0, None);
+    timer.time("copy", || {
+        let _ = copy_televoodoo_source(app, televoodoo_dir, &runtime_televoodoo);
+    });
+    check_cancelled()?;
+    set_phase(app, BootstrapPhase::CreatingVenv);
+    emit_progress(app, 5, None);
+    let base_python = system_python(app);
+    timer.time("venv_create", || run_step(app, "venv_create", Command::new(&base_python).arg("-m").arg("venv").arg(runtime_py_dir.join(".venv")), &mut |_| {}))?;
+    if runtime_pip.exists() {
+        set_phase(app, BootstrapPhase::InstallingDeps);
+        emit_progress(app, 15, None);
+        timer.time("pip_upgrade", || run_step(app, "pip_upgrade", Command::new(&runtime_python).arg("-m").arg("pip").arg("install").args(&net).arg("-U").arg("pip"), &mut |_| {}))?;
+        if req.exists() {
+            emit_progress(app, 25, None);
+            let mut on_line = requirements_progress(app, requirement_count, 25, 85);
+            timer.time("requirements_install", || run_step(app, "requirements_install", Command::new(&runtime_python).arg("-m").arg("pip").arg("install").args(&net).arg("-r").arg(&req), &mut on_line))?;
+        }
+        set_phase(app, BootstrapPhase::InstallingPackage);
+        emit_progress(app, 90, None);
+        timer.time("package_install", || run_step(app, "package_install", Command::new(&runtime_python).arg("-m").arg("pip").arg("install").args(&net).arg(&runtime_televoodoo), &mut |_| {}))?;
+    }
+    emit_progress(app, 100, None);
+    Ok(())
+}
+
+/// Payload of `python-bootstrap-progress`
+#[derive(Clone, serde::Serialize)]
+struct BootstrapProgress {
+    /// Coarse overall progress, 0-100
+    pct: u8,
+    /// Package pip is currently collecting, when its output could be parsed
+    current_package: Option<String>,
+}
+
+fn emit_progress<R: tauri::Runtime>(app: &tauri::AppHandle<R>, pct: u8, current_package: Option<String>) {
+    let _ = app.emit("python-bootstrap-progress", BootstrapProgress { pct, current_package });
+}
+
+/// Line handler for `pip install -r` that maps packages seen so far onto `start..end` percent.
+/// Progress stays at `start` when the requirement count or pip's output can't be parsed.
+fn requirements_progress<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    total: usize,
+    start: u8,
+    end: u8,
+) -> impl FnMut(&str) + '_ {
+    let mut seen = 0;
+    move |line| match parse_pip_line(line) {
+        PipLine::Collecting(name) | PipLine::AlreadySatisfied(name) => {
+            seen += 1;
+            // Transitive dependencies push `seen` past the direct requirement count
+            let done = ((end - start) as usize * seen.min(total)).checked_div(total).unwrap_or(0);
+            emit_progress(app, start + done as u8, Some(name));
+        }
+        PipLine::Installing => emit_progress(app, end, None),
+        PipLine::Other => {}
+    }
+}
+
+/// What a pip output line says about install progress
+#[derive(Debug, PartialEq, Eq)]
+enum PipLine {
+    Collecting(String),
+    AlreadySatisfied(String),
+    /// `Installing collected packages: ...`, i.e. downloads are done
+    Installing,
+    Other,
+}
+
+fn parse_pip_line(line: &str) -> PipLine {
+    let line = line.trim();
+    let name = |spec: &str| {
+        let name: String = spec.chars().take_while(|c| !"<>=!~;[ (@,".contains(*c)).collect();
+        (!name.is_empty()).then_some(name)
+    };
+    if let Some(name) = line.strip_prefix("Collecting ").and_then(name) {
+        PipLine::Collecting(name)
+    } else if let Some(name) = line.strip_prefix("Requirement already satisfied: ").and_then(name) {
+        PipLine::AlreadySatisfied(name)
+    } else if line.starts_with("Installing collected packages") {
+        PipLine::Installing
+    } else {
+        PipLine::Other
+    }
+}
+
+// Set by cancel_start; checked between bootstrap steps
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Bootstrap helper currently running, so a cancel can kill it
+static ACTIVE_STEP: Mutex<Option<Child>> = Mutex::new(None);
+
+pub fn reset_cancel() {
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_cancelled() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+fn check_cancelled() -> Result<(), PythonError> {
+    if is_cancelled() {
+        Err(PythonError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Flags the in-progress start as cancelled and kills the running bootstrap helper, if any
+pub fn request_cancel() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+    if let Ok(mut guard) = ACTIVE_STEP.lock() {
+        if let Some(child) = guard.as_mut() {
+            let _ = child.kill();
+        }
+    }
+}
+
+/// Output lines of a failed step attached to `python-bootstrap-failed`
+const FAILED_OUTPUT_LINES: usize = 200;
+
+/// Payload of `python-bootstrap-failed`
+#[derive(Clone, serde::Serialize)]
+struct BootstrapFailed {
+    /// Timing phase of the step, e.g. "requirements_install"
+    phase: String,
+    exit_code: Option<i32>,
+    kind: BootstrapErrorKind,
+    hint: &'static str,
+    /// Last FAILED_OUTPUT_LINES lines of the step's raw output
+    output: Vec<String>,
+}
+
+/// Runs one bootstrap command to completion while keeping it killable by request_cancel.
+/// Its output goes through the log pipeline (source "bootstrap") and to `on_line`.
+/// A failing exit status is not an error here (matching the previous best-effort
+/// bootstrap); it is classified and reported as `python-bootstrap-failed`, and only
+/// cancellation aborts the sequence.
+fn run_step<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    phase: &str,
+    cmd: &mut Command,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), PythonError> {
+    check_cancelled()?;
+    let Ok(mut child) = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() else { return Ok(()) };
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        pipe_lines(stdout, Stream::Stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        pipe_lines(stderr, Stream::Stderr, tx);
+    }
+    *ACTIVE_STEP.lock().unwrap_or_else(|e| e.into_inner()) = Some(child);
+    let mut output = VecDeque::new();
+    let mut handle = |(stream, line): (Stream, String)| {
+        logs::forward_tool_line(app, "bootstrap", stream, &line);
+        on_line(&line);
+        if output.len() == FAILED_OUTPUT_LINES {
+            output.pop_front();
+        }
+        output.push_back(line);
+    };
+    let status = loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(item) => {
+                handle(item);
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // Both pipes closed; keep polling for the exit without spinning
+            Err(mpsc::RecvTimeoutError::Disconnected) => std::thread::sleep(Duration::from_millis(100)),
+        }
+        let mut guard = ACTIVE_STEP.lock().unwrap_or_else(|e| e.into_inner());
+        match guard.as_mut().map(|c| c.try_wait()) {
+            Some(Ok(None)) => continue,
+            result => {
+                let status = result.and_then(Result::ok).flatten();
+                // try_wait failed: reap it the blocking way rather than leave a zombie
+                if let Some(mut child) = guard.take().filter(|_| status.is_none()) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                break status;
+            }
+        }
+    };
+    // Lines still in flight when the process exited
+    while let Ok(item) = rx.recv_timeout(Duration::from_millis(500)) {
+        handle(item);
+    }
+    check_cancelled()?;
+    if let Some(status) = status.filter(|s| !s.success()) {
+        let output: Vec<String> = output.into_iter().collect();
+        let kind = pip_errors::classify(&output);
+        let hint = pip_errors::hint(kind);
+        logs::forward_tool_line(app, "bootstrap", Stream::Stderr, &format!("ERROR: {} failed with {}. {}", phase, status, hint));
+        let _ = app.emit("python-bootstrap-failed", BootstrapFailed { phase: phase.to_string(), exit_code: status.code(), kind, hint, output });
+    }
+    Ok(())
+}
+
+/// Sends each line of `pipe`, tagged with `stream`, to `tx` from a reader thread
+pub fn pipe_lines(pipe: impl Read + Send + 'static, stream: Stream, tx: mpsc::Sender<(Stream, String)>) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if tx.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pip_progress_lines() {
+        assert_eq!(parse_pip_line("Collecting numpy>=1.24 (from -r requirements.txt (line 1))"), PipLine::Collecting("numpy".into()));
+        assert_eq!(parse_pip_line("  Collecting bleak==0.21.1"), PipLine::Collecting("bleak".into()));
+        assert_eq!(
+            parse_pip_line("Requirement already satisfied: websockets in ./.venv/lib/python3.11/site-packages (12.0)"),
+            PipLine::AlreadySatisfied("websockets".into())
+        );
+        assert_eq!(parse_pip_line("Installing collected packages: numpy, bleak"), PipLine::Installing);
+        assert_eq!(parse_pip_line("  Downloading numpy-1.26.4.whl (18.2 MB)"), PipLine::Other);
+        assert_eq!(parse_pip_line("Collecting "), PipLine::Other);
+    }
+
+    #[test]
+    fn activates_venv_of_the_interpreter() {
+        let venv = std::env::temp_dir().join(format!("tv-activate-{}", std::process::id()));
+        std::fs::create_dir_all(venv.join("bin")).unwrap();
+        std::fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+
+        let mut cmd = Command::new(venv.join("bin").join("python"));
+        cmd.env("PATH", "/usr/bin");
+        activate_venv(&mut cmd);
+        let env: std::collections::HashMap<_, _> = cmd.get_envs().collect();
+        assert_eq!(env[std::ffi::OsStr::new("VIRTUAL_ENV")], Some(venv.as_os_str()));
+        let path: Vec<PathBuf> = std::env::split_paths(env[std::ffi::OsStr::new("PATH")].unwrap()).collect();
+        assert_eq!(path, vec![venv.join("bin"), PathBuf::from("/usr/bin")]);
+
+        let mut plain = Command::new("/usr/bin/python3");
+        activate_venv(&mut plain);
+        assert_eq!(plain.get_envs().count(), 0);
+        let _ = std::fs::remove_dir_all(&venv);
+    }
+}
@@ -0,0 +1,182 @@
+//! Structured options of the backend's `--help`, so the launcher UI can offer a form of
+//! the flags the installed televoodoo actually accepts. The parser targets argparse
+//! output but is lenient: anything it can't make sense of is reported via `raw`.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::bootstrap;
+use crate::EntryPoint;
+
+/// `--help` should print and exit immediately; anything slower is hung on an import
+const HELP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// One option from the help text
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct CliOption {
+    /// Spellings as listed, e.g. `["-p", "--port"]`
+    pub flags: Vec<String>,
+    pub takes_value: bool,
+    /// Value placeholder, e.g. `PORT`
+    pub metavar: Option<String>,
+    /// Allowed values when the placeholder is a `{a,b}` choice list
+    pub choices: Option<Vec<String>>,
+    pub description: String,
+}
+
+/// Returned by backend_help
+#[derive(Clone, serde::Serialize)]
+pub struct BackendHelp {
+    pub options: Vec<CliOption>,
+    /// The full help text, set when no options were found or some lines that looked like
+    /// options could not be parsed
+    pub raw: Option<String>,
+}
+
+/// Runs `<python> <entry> --help` and parses its output
+pub fn run(python: &Path, entry: &EntryPoint) -> Result<BackendHelp, String> {
+    let mut cmd = Command::new(python);
+    cmd.args(entry.args()).arg("--help");
+    bootstrap::activate_venv(&mut cmd);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", python.display(), e))?;
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut text);
+            }
+            text
+        })
+    };
+    let stdout = read(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = read(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= HELP_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("--help did not finish within {} s", HELP_TIMEOUT.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Could not wait for --help: {}", e));
+            }
+        }
+    };
+    let (stdout, stderr) = (stdout.join().unwrap_or_default(), stderr.join().unwrap_or_default());
+    if !status.success() {
+        return Err(format!("--help failed with {}: {}", status, stderr.trim()));
+    }
+    let text = if stdout.trim().is_empty() { stderr } else { stdout };
+    Ok(parse(&text))
+}
+
+pub fn parse(text: &str) -> BackendHelp {
+    let mut options: Vec<CliOption> = Vec::new();
+    let mut incomplete = false;
+    // Whether indented non-option lines continue the last option's description
+    let mut in_option = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let indented = line.starts_with([' ', '\t']);
+        if trimmed.is_empty() || !indented {
+            // Blank lines and section headers (`options:`) end a description
+            in_option = false;
+            continue;
+        }
+        if trimmed.starts_with('-') {
+            match parse_option(trimmed) {
+                Some(option) => {
+                    options.push(option);
+                    in_option = true;
+                }
+                None => {
+                    incomplete = true;
+                    in_option = false;
+                }
+            }
+        } else if in_option {
+            let last = options.last_mut().expect("in_option implies an option");
+            if !last.description.is_empty() {
+                last.description.push(' ');
+            }
+            last.description.push_str(trimmed);
+        }
+    }
+    let raw = (incomplete || options.is_empty()).then(|| text.to_string());
+    BackendHelp { options, raw }
+}
+
+/// Parses `-p PORT, --port PORT   Description` (the description may be absent when it
+/// starts on the next line)
+fn parse_option(line: &str) -> Option<CliOption> {
+    let (spec, description) = match line.find("  ") {
+        Some(gap) => (&line[..gap], line[gap..].trim()),
+        None => (line, ""),
+    };
+    let mut option = CliOption { flags: Vec::new(), takes_value: false, metavar: None, choices: None, description: description.to_string() };
+    for part in spec.split(", ") {
+        let (flag, metavar) = match part.split_once(['=', ' ']) {
+            Some((flag, metavar)) => (flag, Some(metavar.trim())),
+            None => (part, None),
+        };
+        let valid = flag.starts_with('-')
+            && flag.len() > 1
+            && flag.trim_start_matches('-').chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return None;
+        }
+        option.flags.push(flag.to_string());
+        if let Some(metavar) = metavar.filter(|m| !m.is_empty()) {
+            option.takes_value = true;
+            if let Some(list) = metavar.strip_prefix('{').and_then(|m| m.strip_suffix('}')) {
+                option.choices = Some(list.split(',').map(|c| c.trim().to_string()).collect());
+            }
+            option.metavar = Some(metavar.to_string());
+        }
+    }
+    Some(option)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_argparse_help() {
+        let help = parse(concat!(
+            "usage: televoodoo [-h] [--connection {ble,wifi}] [-p PORT]\n",
+            "\n",
+            "options:\n",
+            "  -h, --help            show this help message and exit\n",
+            "  --connection {ble,wifi}\n",
+            "                        Transport to the phone\n",
+            "  -p PORT, --port PORT  UDP port\n",
+            "                        (wifi only)\n",
+            "  --upside-down         Flip the pose\n",
+        ));
+        assert!(help.raw.is_none());
+        let flags: Vec<&[String]> = help.options.iter().map(|o| o.flags.as_slice()).collect();
+        assert_eq!(flags, [&["-h", "--help"][..], &["--connection"], &["-p", "--port"], &["--upside-down"]]);
+        let connection = &help.options[1];
+        assert_eq!(connection.choices, Some(vec!["ble".to_string(), "wifi".to_string()]));
+        assert_eq!(connection.description, "Transport to the phone");
+        assert_eq!(help.options[2].metavar.as_deref(), Some("PORT"));
+        assert_eq!(help.options[2].description, "UDP port (wifi only)");
+        assert!(!help.options[3].takes_value);
+
+        let odd = parse("  -x <weird> stuff, ??? here\n");
+        assert!(odd.options.is_empty() && odd.raw.is_some());
+    }
+}
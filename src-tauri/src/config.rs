@@ -0,0 +1,349 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::bootstrap::BootstrapTimings;
+use crate::logs::Level;
+
+// Cached copy of the on-disk config; loaded lazily on first access
+static CONFIG: Mutex<Option<Cached>> = Mutex::new(None);
+
+struct Cached {
+    config: ViewerConfig,
+    /// Why config.json couldn't be used; `config` is then the defaults (or what was loaded
+    /// before a failed reload)
+    load_error: Option<String>,
+}
+
+/// Persistent viewer settings, stored as `config.json` in the app config dir
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ViewerConfig {
+    /// Per-phase durations of the most recent runtime bootstrap
+    pub last_bootstrap_timings: Option<BootstrapTimings>,
+    /// Name of the runtime python directory under the app data dir
+    pub python_dir_name: String,
+    /// Robot config file used for the last launch
+    pub last_config_file: Option<PathBuf>,
+    /// Robot profile of the last launch or switch_profile
+    pub last_profile: Option<String>,
+    /// Bundled televoodoo version last installed into the runtime venv by auto-update
+    pub last_applied_bundle_version: Option<String>,
+    /// bundle_fingerprint of the source last copied into the runtime dir
+    pub last_applied_bundle_fingerprint: Option<String>,
+    /// pip `--timeout` (seconds) for bootstrap and package installs
+    pub pip_timeout_secs: u32,
+    /// pip `--retries` for bootstrap and package installs
+    pub pip_retries: u32,
+    /// System interpreters tried in order when no venv python is available
+    pub python_candidates: Vec<String>,
+    /// Capacity of each backend stdout/stderr read buffer, in bytes (clamped to
+    /// 1 KiB..=16 MiB). Larger buffers mean fewer read syscalls for high-volume or
+    /// long-line output, at the cost of that much memory per stream.
+    pub reader_buffer_bytes: usize,
+    /// Mirror lifecycle events and backend errors to syslog / the Windows Event Log
+    pub system_log: bool,
+    /// Native shared libraries checked by check_system_deps / preflight
+    pub system_libs: Vec<String>,
+    /// Restart the backend automatically after a crash exit
+    pub auto_restart: bool,
+    /// Minimum wait before each auto-restart, in milliseconds
+    pub restart_cooldown_ms: u64,
+    /// Auto-restarts allowed within restart_window_secs before giving up
+    pub restart_max_attempts: u32,
+    pub restart_window_secs: u64,
+    /// Regex matched against backend output to discover its listening address; the first
+    /// capture group must contain `host:port`. Empty disables discovery.
+    pub endpoint_pattern: String,
+    /// How long stop_python waits for the output reader threads to finish before
+    /// detaching them, in milliseconds (max 60000)
+    pub reader_join_timeout_ms: u64,
+    /// Minimum level of backend output, see set_log_level. None shows everything.
+    pub log_level: Option<Level>,
+    /// Interval of the `python-heartbeat` event in milliseconds; 0 disables it
+    pub heartbeat_interval_ms: u64,
+    /// Interval of the `python-metrics` event in milliseconds; 0 disables it
+    pub metrics_interval_ms: u64,
+    /// Top-level entries of the bundled televoodoo dir copied into the runtime dir
+    pub source_include: Vec<String>,
+    /// Copy the whole bundled televoodoo dir (tests, docs, examples) instead of source_include
+    pub copy_full_source: bool,
+    /// Allow run_python_snippet in release builds (always allowed in dev builds)
+    pub allow_python_snippets: bool,
+    /// Free space required on the app data volume before a runtime bootstrap, in bytes
+    pub bootstrap_required_bytes: u64,
+    /// reload_backend_config sends SIGHUP instead of the `config.reload` RPC (Unix)
+    pub reload_via_sighup: bool,
+    /// reload_backend_config restarts a backend that can't reload live
+    pub reload_restart_fallback: bool,
+    /// Command (argv, no shell) run before each backend spawn; a non-zero exit fails the start
+    pub pre_launch: Option<Vec<String>>,
+    /// Command (argv, no shell) run after the backend exits for any reason, for cleanup
+    pub post_exit: Option<Vec<String>>,
+    /// post_exit is killed after this many milliseconds (at most 2 s when the app quits)
+    pub post_exit_timeout_ms: u64,
+    /// Run the backend with PYTHONUNBUFFERED=1 so its output streams line by line; off
+    /// leaves Python's block buffering on the pipe (slightly cheaper, but bursty)
+    pub python_unbuffered: bool,
+    /// PYTHONIOENCODING for the backend (a UTF-8 value also sets PYTHONUTF8=1); None keeps
+    /// Python's locale-dependent default
+    pub python_io_encoding: Option<String>,
+    /// https endpoint of the remote televoodoo update manifest (see remote_update)
+    pub update_url: Option<String>,
+    /// PEM Ed25519 public key remote updates must be signed with; none means no remote
+    /// update is ever installed
+    pub update_public_key: Option<String>,
+    /// `.env` file merged into the backend's environment; variables the viewer sets itself
+    /// take precedence
+    pub env_file: Option<PathBuf>,
+    /// Restart a running backend after the machine resumes from sleep
+    pub restart_on_resume: bool,
+    /// Wall-clock gap (ms) beyond the ticker interval that counts as having slept
+    pub resume_gap_ms: u64,
+    /// Dev builds only: restart the backend when the repo's televoodoo source changes
+    pub hot_reload: bool,
+    /// Rotated backend logs kept, newest first
+    pub log_max_files: u32,
+    /// Rotated backend logs older than this are deleted; 0 keeps them regardless of age
+    pub log_max_age_days: u32,
+    /// Debugging aid: run the backend with checking allocators (see DEBUG_ALLOC_ENV in
+    /// main.rs) to catch heap corruption and leaks. Makes allocation-heavy code several
+    /// times slower and uses more memory, so leave it off outside a debugging session.
+    pub debug_alloc: bool,
+    /// Packaged builds: check the runtime venv, source copy and architecture at app launch
+    /// and repair what is broken (see self_repair)
+    pub startup_self_repair: bool,
+    /// Move a broken runtime venv to the quarantine dir instead of deleting it before a
+    /// rebuild (see quarantine)
+    pub quarantine_venvs: bool,
+    /// Quarantined venvs kept, newest first
+    pub quarantine_keep: u32,
+    /// Total size quarantined venvs may take, in bytes; the newest is kept regardless
+    pub quarantine_max_bytes: u64,
+    /// Remove interfering global Python variables (PYTHONHOME, PYTHONSTARTUP, ...) from the
+    /// backend's environment; see env_conflicts
+    pub neutralize_python_env: bool,
+    /// https endpoint share_logs uploads to (raw POST body, answers with the paste URL)
+    pub paste_url: String,
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        Self {
+            last_bootstrap_timings: None,
+            python_dir_name: "python".to_string(),
+            last_config_file: None,
+            last_profile: None,
+            last_applied_bundle_version: None,
+            last_applied_bundle_fingerprint: None,
+            pip_timeout_secs: 30,
+            pip_retries: 5,
+            python_candidates: ["python3.12", "python3.11", "python3", "python"].map(String::from).to_vec(),
+            // Same as BufReader's default
+            reader_buffer_bytes: 8 * 1024,
+            system_log: false,
+            system_libs: if cfg!(target_os = "linux") {
+                ["libGL.so.1", "libglib-2.0.so.0"].map(String::from).to_vec()
+            } else {
+                Vec::new()
+            },
+            auto_restart: false,
+            restart_cooldown_ms: 2000,
+            restart_max_attempts: 5,
+            restart_window_secs: 60,
+            endpoint_pattern: r"LISTENING on (\S+:\d+)".to_string(),
+            reader_join_timeout_ms: 1000,
+            log_level: None,
+            heartbeat_interval_ms: 0,
+            metrics_interval_ms: 0,
+            // pyproject.toml may reference README/LICENSE; the build fails without them
+            source_include: ["src", "pyproject.toml", "setup.py", "setup.cfg", "requirements.txt", "README.md", "LICENSE"]
+                .map(String::from)
+                .to_vec(),
+            copy_full_source: false,
+            allow_python_snippets: false,
+            bootstrap_required_bytes: 1024 * 1024 * 1024,
+            reload_via_sighup: false,
+            reload_restart_fallback: true,
+            pre_launch: None,
+            post_exit: None,
+            post_exit_timeout_ms: 10_000,
+            python_unbuffered: true,
+            python_io_encoding: Some("utf-8".to_string()),
+            update_url: None,
+            update_public_key: None,
+            env_file: None,
+            restart_on_resume: false,
+            resume_gap_ms: 30_000,
+            hot_reload: false,
+            log_max_files: 10,
+            log_max_age_days: 30,
+            debug_alloc: false,
+            startup_self_repair: false,
+            quarantine_venvs: false,
+            quarantine_keep: 3,
+            quarantine_max_bytes: 2 * 1024 * 1024 * 1024,
+            neutralize_python_env: true,
+            paste_url: "https://paste.rs".to_string(),
+        }
+    }
+}
+
+/// Checks that a runtime python dir name is a single plain path component on every
+/// platform: no separators, no drive or stream `:`, no Windows device name and no trailing
+/// dot or space (which Windows strips, so `python.` would be the same dir as `python`)
+pub fn validate_python_dir_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(format!("Invalid python directory name: {:?}", name));
+    }
+    if name.contains('/') || name.contains('\\') {
+        return Err(format!("Python directory name must not contain path separators: {:?}", name));
+    }
+    if let Some(c) = name.chars().find(|c| c.is_control() || matches!(c, ':' | '<' | '>' | '"' | '|' | '?' | '*')) {
+        return Err(format!("Python directory name must not contain {:?}: {:?}", c, name));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err(format!("Python directory name must not end with a dot or space: {:?}", name));
+    }
+    // Reserved with any extension too: `nul.txt` is still the NUL device
+    let stem = name.split('.').next().unwrap_or(name).trim_end().to_ascii_uppercase();
+    let device = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && matches!(stem.as_bytes()[3], b'1'..=b'9'));
+    if device {
+        return Err(format!("Python directory name is a reserved Windows device name: {:?}", name));
+    }
+    Ok(())
+}
+
+impl ViewerConfig {
+    /// reader_buffer_bytes within its supported range
+    pub fn reader_buffer_capacity(&self) -> usize {
+        self.reader_buffer_bytes.clamp(1024, 16 * 1024 * 1024)
+    }
+
+    /// reader_join_timeout_ms, capped at one minute
+    pub fn reader_join_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.reader_join_timeout_ms.min(60_000))
+    }
+
+    /// pip network flags for install commands
+    pub fn pip_network_args(&self) -> Vec<String> {
+        vec![
+            "--timeout".to_string(),
+            self.pip_timeout_secs.to_string(),
+            "--retries".to_string(),
+            self.pip_retries.to_string(),
+        ]
+    }
+}
+
+pub fn config_path<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join("config.json"))
+}
+
+/// The config file's contents; Ok(None) when there is no file yet
+fn read_from_disk<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<Option<ViewerConfig>, String> {
+    let path = config_path(app).ok_or_else(|| "Could not determine config directory".to_string())?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str::<ViewerConfig>(&text).map(Some).map_err(|e| {
+            format!("Invalid config at {} (line {}, column {}): {}", path.display(), e.line(), e.column(), e)
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+fn load_from_disk<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Cached {
+    match read_from_disk(app) {
+        Ok(config) => Cached { config: config.unwrap_or_default(), load_error: None },
+        Err(e) => Cached { config: ViewerConfig::default(), load_error: Some(e) },
+    }
+}
+
+/// Returns the current config, reading it from disk on first use. If config.json couldn't
+/// be used, this is the defaults; load_error says why.
+pub fn get<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> ViewerConfig {
+    let mut guard = CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(|| load_from_disk(app)).config.clone()
+}
+
+/// Why config.json could not be loaded (or last reloaded), if it couldn't
+pub fn load_error<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<String> {
+    let mut guard = CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(|| load_from_disk(app)).load_error.clone()
+}
+
+/// Re-reads the config from disk, replacing the cached copy. A missing file resets
+/// to defaults; invalid JSON is reported with its line/column, leaves the cached settings
+/// untouched and is kept as load_error until a reload succeeds.
+pub fn reload<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<ViewerConfig, String> {
+    let mut guard = CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    match read_from_disk(app) {
+        Ok(config) => {
+            let config = config.unwrap_or_default();
+            *guard = Some(Cached { config: config.clone(), load_error: None });
+            Ok(config)
+        }
+        Err(e) => {
+            let config = guard.take().map(|cached| cached.config).unwrap_or_default();
+            *guard = Some(Cached { config, load_error: Some(e.clone()) });
+            Err(e)
+        }
+    }
+}
+
+/// Applies `f` to the current config and writes the result back to disk
+pub fn update<R: tauri::Runtime>(app: &tauri::AppHandle<R>, f: impl FnOnce(&mut ViewerConfig)) -> Result<(), String> {
+    let mut guard = CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    let cached = guard.get_or_insert_with(|| load_from_disk(app));
+    f(&mut cached.config);
+    write_to_disk(app, &cached.config)
+}
+
+/// Replaces the whole config, on disk and in the cache
+pub fn replace<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: ViewerConfig) -> Result<(), String> {
+    let mut guard = CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    write_to_disk(app, &config)?;
+    *guard = Some(Cached { config, load_error: None });
+    Ok(())
+}
+
+/// Writes `config` as config.json. A file that doesn't parse is never overwritten, so
+/// hand edits with a typo aren't replaced by the defaults loaded in their place.
+fn write_to_disk<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: &ViewerConfig) -> Result<(), String> {
+    let path = config_path(app).ok_or_else(|| "Could not determine config directory".to_string())?;
+    if let Err(e) = read_from_disk(app) {
+        return Err(format!("Not saving settings over a config file that can't be loaded; fix it and reload the config. {}", e));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+/// Makes sure the config file exists on disk (writing defaults if needed) and returns its path
+pub fn ensure_on_disk<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    let path = config_path(app).ok_or_else(|| "Could not determine config directory".to_string())?;
+    if !path.exists() {
+        write_to_disk(app, &get(app))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn python_dir_name_must_be_portable() {
+        for name in ["python", "python-3.12", "console", "com10", "lpt0", ".venv-backup"] {
+            assert!(validate_python_dir_name(name).is_ok(), "{:?} was rejected", name);
+        }
+        for name in ["", ".", "..", "a/b", "a\\b", "C:", "python:stream", "py?", "python.", "python ", "CON", "nul", "Aux.txt", "com1", "LPT9.tar.gz", "nul .x"] {
+            assert!(validate_python_dir_name(name).is_err(), "{:?} was accepted", name);
+        }
+    }
+}
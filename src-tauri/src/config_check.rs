@@ -0,0 +1,211 @@
+//! Validation of a whole config before it is saved (save_config) or to lint a hand-edited
+//! file (validate_config). Each field is type-checked on its own, so one bad value doesn't
+//! hide the others, then ranges and cross-field constraints are checked with the same limits
+//! the individual setters enforce. Only "error" issues block a save; "warning" issues are
+//! settings that would be ignored, clamped or are likely mistakes.
+//!
+//! Settings that run, load or install code (PROTECTED) can't be changed
+//! through save_config at all; they need a deliberate edit of config.json.
+
+use serde_json::Value;
+
+use crate::config::{self, ViewerConfig};
+use crate::{dotenv, log_rotation, remote_update, share_logs};
+
+/// Opt-ins, hooks, trust anchors and code sources that only a hand edit of config.json
+/// may change
+pub const PROTECTED: &[&str] = &[
+    "allow_python_snippets",
+    "update_url",
+    "update_public_key",
+    "pre_launch",
+    "post_exit",
+    "env_file",
+    "hot_reload",
+    // Executed by bootstrap when looking for a system interpreter
+    "python_candidates",
+    // dlopen()ed by preflight
+    "system_libs",
+    // What of the bundled source ends up importable in the runtime dir
+    "source_include",
+    "copy_full_source",
+];
+
+/// One problem found by validate
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ConfigIssue {
+    /// Config key the issue is about; empty for the document as a whole
+    pub field: String,
+    /// "error" or "warning"
+    pub severity: &'static str,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn error(field: &str, message: impl Into<String>) -> Self {
+        ConfigIssue { field: field.to_string(), severity: "error", message: message.into() }
+    }
+
+    fn warning(field: &str, message: impl Into<String>) -> Self {
+        ConfigIssue { field: field.to_string(), severity: "warning", message: message.into() }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+/// All issues of `value` as a config document, errors and warnings
+pub fn validate(value: &Value) -> Vec<ConfigIssue> {
+    let Some(object) = value.as_object() else {
+        return vec![ConfigIssue::error("", "Config must be a JSON object")];
+    };
+    let known = serde_json::to_value(ViewerConfig::default()).unwrap_or_default();
+    let mut issues = Vec::new();
+    for (key, field_value) in object {
+        if known.get(key).is_none() {
+            issues.push(ConfigIssue::warning(key, "Unknown setting; it will be dropped on save"));
+            continue;
+        }
+        let single = serde_json::json!({ key: field_value });
+        if let Err(e) = serde_json::from_value::<ViewerConfig>(single) {
+            issues.push(ConfigIssue::error(key, format!("Invalid type: {}", e)));
+        }
+    }
+    if issues.iter().any(ConfigIssue::is_error) {
+        return issues;
+    }
+    match serde_json::from_value::<ViewerConfig>(value.clone()) {
+        Ok(config) => issues.extend(check(&config)),
+        Err(e) => issues.push(ConfigIssue::error("", e.to_string())),
+    }
+    issues
+}
+
+/// An error for each PROTECTED setting `proposed` changes from `current`
+pub fn protected_changes(current: &ViewerConfig, proposed: &ViewerConfig) -> Vec<ConfigIssue> {
+    let (current, proposed) = (serde_json::to_value(current).unwrap_or_default(), serde_json::to_value(proposed).unwrap_or_default());
+    PROTECTED
+        .iter()
+        .filter(|field| current.get(**field) != proposed.get(**field))
+        .map(|field| ConfigIssue::error(field, "Can only be changed by editing config.json"))
+        .collect()
+}
+
+fn check(c: &ViewerConfig) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut range = |field: &str, ok: bool, expected: &str| {
+        if !ok {
+            issues.push(ConfigIssue::error(field, format!("Must be {}", expected)));
+        }
+    };
+    range("pip_timeout_secs", (1..=600).contains(&c.pip_timeout_secs), "between 1 and 600 seconds");
+    range("pip_retries", c.pip_retries <= 50, "at most 50");
+    range("restart_cooldown_ms", c.restart_cooldown_ms <= 60_000, "at most 60000 ms");
+    range("restart_max_attempts", (1..=100).contains(&c.restart_max_attempts), "between 1 and 100");
+    range("restart_window_secs", (1..=3600).contains(&c.restart_window_secs), "between 1 and 3600 seconds");
+    let interval_ok = |ms: u64| ms == 0 || (100..=60_000).contains(&ms);
+    range("heartbeat_interval_ms", interval_ok(c.heartbeat_interval_ms), "0 (off) or between 100 and 60000 ms");
+    range("metrics_interval_ms", interval_ok(c.metrics_interval_ms), "0 (off) or between 100 and 60000 ms");
+    let files = &log_rotation::MAX_FILES_RANGE;
+    range("log_max_files", files.contains(&c.log_max_files), &format!("between {} and {}", files.start(), files.end()));
+    let days = &log_rotation::MAX_AGE_DAYS_RANGE;
+    range("log_max_age_days", days.contains(&c.log_max_age_days), &format!("between {} and {} days", days.start(), days.end()));
+    range("quarantine_keep", (1..=20).contains(&c.quarantine_keep), "between 1 and 20");
+    range("quarantine_max_bytes", c.quarantine_max_bytes >= 100 * 1024 * 1024, "at least 100 MiB");
+    range("python_candidates", !c.python_candidates.is_empty(), "a non-empty list of interpreters");
+
+    if let Err(e) = config::validate_python_dir_name(&c.python_dir_name) {
+        issues.push(ConfigIssue::error("python_dir_name", e));
+    }
+    if !c.endpoint_pattern.is_empty() {
+        match regex::Regex::new(&c.endpoint_pattern) {
+            Ok(regex) if regex.captures_len() > 1 => {}
+            Ok(_) => issues.push(ConfigIssue::error("endpoint_pattern", "Pattern has no capture group for host:port")),
+            Err(e) => issues.push(ConfigIssue::error("endpoint_pattern", format!("Invalid regex: {}", e))),
+        }
+    }
+    if let Some(ref path) = c.env_file {
+        if let Err(e) = dotenv::validate_path(path) {
+            issues.push(ConfigIssue::error("env_file", e));
+        }
+    }
+    for (field, hook) in [("pre_launch", &c.pre_launch), ("post_exit", &c.post_exit)] {
+        if hook.as_ref().is_some_and(|argv| argv.first().is_none_or(|program| program.trim().is_empty())) {
+            issues.push(ConfigIssue::error(field, "Hook command is empty; remove it or set a program"));
+        }
+    }
+    if !c.copy_full_source && c.source_include.is_empty() {
+        issues.push(ConfigIssue::error("source_include", "Nothing would be copied into the runtime dir; list entries or set copy_full_source"));
+    }
+
+    if let Some(ref url) = c.update_url {
+        if let Err(e) = remote_update::validate_url(url) {
+            issues.push(ConfigIssue::error("update_url", e));
+        } else if c.update_public_key.is_none() {
+            issues.push(ConfigIssue::warning("update_public_key", "update_url is set but without a public key updates can't be installed"));
+        }
+    }
+    if !(1024..=16 * 1024 * 1024).contains(&c.reader_buffer_bytes) {
+        issues.push(ConfigIssue::warning("reader_buffer_bytes", format!("Will be clamped to {} bytes", c.reader_buffer_capacity())));
+    }
+    if let Err(e) = share_logs::validate_paste_url(&c.paste_url) {
+        issues.push(ConfigIssue::error("paste_url", e));
+    }
+    if c.reader_join_timeout_ms > 60_000 {
+        issues.push(ConfigIssue::warning("reader_join_timeout_ms", "Will be capped at 60000 ms"));
+    }
+    if c.hot_reload && !cfg!(debug_assertions) {
+        issues.push(ConfigIssue::warning("hot_reload", "Only takes effect in dev builds"));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(issues: &[ConfigIssue], severity: &str) -> Vec<String> {
+        issues.iter().filter(|i| i.severity == severity).map(|i| i.field.clone()).collect()
+    }
+
+    #[test]
+    fn reports_types_ranges_and_cross_field() {
+        assert_eq!(validate(&serde_json::to_value(ViewerConfig::default()).unwrap()), vec![]);
+
+        let issues = validate(&serde_json::json!({ "pip_retries": "three", "restart_window_secs": 10, "colour": "red" }));
+        assert_eq!(fields(&issues, "error"), vec!["pip_retries"]);
+        assert_eq!(fields(&issues, "warning"), vec!["colour"]);
+
+        let issues = validate(&serde_json::json!({
+            "pip_timeout_secs": 0,
+            "endpoint_pattern": "listening on \\d+",
+            "pre_launch": [],
+            "update_url": "https://updates.example/manifest.json",
+        }));
+        assert_eq!(fields(&issues, "error"), vec!["pip_timeout_secs", "endpoint_pattern", "pre_launch"]);
+        assert_eq!(fields(&issues, "warning"), vec!["update_public_key"]);
+
+        assert_eq!(fields(&validate(&serde_json::json!([1, 2])), "error"), vec![""]);
+    }
+
+    #[test]
+    fn protected_settings_need_a_hand_edit() {
+        let current = ViewerConfig { update_url: Some("https://updates.example/m.json".to_string()), ..ViewerConfig::default() };
+        let proposed = ViewerConfig {
+            allow_python_snippets: true,
+            update_url: None,
+            pre_launch: Some(vec!["sh".to_string()]),
+            pip_retries: 9,
+            python_candidates: vec!["/tmp/python3".to_string()],
+            system_libs: vec!["/tmp/libevil.so".to_string()],
+            source_include: vec!["..".to_string()],
+            ..current.clone()
+        };
+        assert_eq!(
+            fields(&protected_changes(&current, &proposed), "error"),
+            vec!["allow_python_snippets", "update_url", "pre_launch", "python_candidates", "system_libs", "source_include"]
+        );
+        assert!(protected_changes(&current, &ViewerConfig { pip_retries: 9, ..current.clone() }).is_empty());
+    }
+}
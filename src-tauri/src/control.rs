@@ -0,0 +1,119 @@
+//! Control channel to the backend over its stdin/stdout.
+//!
+//! Plain commands are written to stdin as single lines. JSON-RPC 2.0 requests and responses
+//! are framed as one line each, prefixed with RPC_MARKER, so the stdout reader can route
+//! responses back to the waiting rpc_call instead of treating them as log output.
+//!
+//! Methods the viewer relies on:
+//! - `ping`: no params, any result
+//! - `config.get`: no params, result is the backend's config object
+//! - `config.set`: params `{ "config": { .. } }` with the keys to change, result is the
+//!   full config after applying them
+//! - `config.reload`: no params, re-reads the backend's config file(s)
+//! - `profile.switch`: params `{ "name": "<profile>" }`, switches the robot profile live
+//! - `device.check`: params `{ "device": "<id>" }`, result `{ "state": "available" |
+//!   "busy" | "not_found" | "permission_denied" | "unreachable", "detail": "..." }`
+//! - `memory.trim`: no params, runs `gc.collect()` and `malloc_trim(0)` where available;
+//!   result `{ "collected": <objects>, "malloc_trimmed": <bool> }`, both optional
+//! - `metrics.get`: no params, result is an object of performance counters (schema in
+//!   the metrics module)
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::ChildStdin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// Timeout for the viewer's own requests (ping, config)
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// JSON-RPC "method not found"
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Prefix of RPC lines in both directions
+pub const RPC_MARKER: &str = "@rpc ";
+
+// Stdin of the current backend (None while stopped)
+static STDIN: Mutex<Option<ChildStdin>> = Mutex::new(None);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+// In-flight rpc_call requests by id
+static PENDING: Mutex<Option<HashMap<u64, Waiter>>> = Mutex::new(None);
+
+type Waiter = mpsc::Sender<Result<Value, String>>;
+
+/// Installs the stdin of a newly spawned backend, or clears it on exit. Either way,
+/// requests still waiting on the previous backend fail.
+pub fn set_stdin(stdin: Option<ChildStdin>) {
+    *STDIN.lock().unwrap_or_else(|e| e.into_inner()) = stdin;
+    // Dropping the senders wakes the waiters with a disconnect
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// Writes one line (newline appended) to the backend's stdin
+pub fn send_line(line: &str) -> Result<(), String> {
+    if line.contains(['\n', '\r']) {
+        return Err("Control messages must be a single line".to_string());
+    }
+    let mut guard = STDIN.lock().unwrap_or_else(|e| e.into_inner());
+    let stdin = guard.as_mut().ok_or_else(|| "Backend is not running".to_string())?;
+    writeln!(stdin, "{}", line)
+        .and_then(|()| stdin.flush())
+        .map_err(|e| format!("Could not write to backend stdin: {}", e))
+}
+
+/// Sends a JSON-RPC request and waits up to `timeout` for the response with the same id.
+/// Returns the `result`, or the `error` object rendered as a message.
+pub fn call(method: &str, params: Value, timeout: Duration) -> Result<Value, String> {
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = mpsc::channel();
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(HashMap::new).insert(id, tx);
+
+    let request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    if let Err(e) = send_line(&format!("{}{}", RPC_MARKER, request)) {
+        remove_pending(id);
+        return Err(e);
+    }
+    let response = rx.recv_timeout(timeout);
+    remove_pending(id);
+    match response {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(format!("RPC {} timed out after {} ms", method, timeout.as_millis())),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err("Backend exited before responding".to_string()),
+    }
+}
+
+/// Whether a call error means the backend doesn't implement the method: it answered
+/// "method not found". A timeout is not taken as a no; it may just be busy.
+pub fn unsupported(error: &str) -> bool {
+    error.starts_with(&format!("RPC error {}:", METHOD_NOT_FOUND))
+}
+
+fn remove_pending(id: u64) {
+    if let Some(pending) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        pending.remove(&id);
+    }
+}
+
+/// Called by the stdout reader for every line. Returns true when the line was an RPC
+/// response (and so must not be forwarded as log output).
+pub fn handle_line(line: &str) -> bool {
+    let Some(body) = line.strip_prefix(RPC_MARKER) else { return false };
+    let Ok(message) = serde_json::from_str::<Value>(body) else { return false };
+    let Some(id) = message.get("id").and_then(Value::as_u64) else { return false };
+    let result = match message.get("error") {
+        Some(error) => Err(format!(
+            "RPC error {}: {}",
+            error.get("code").and_then(Value::as_i64).unwrap_or(0),
+            error.get("message").and_then(Value::as_str).unwrap_or("unknown error")
+        )),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let sender = PENDING.lock().unwrap_or_else(|e| e.into_inner()).as_mut().and_then(|p| p.remove(&id));
+    if let Some(sender) = sender {
+        let _ = sender.send(result);
+    }
+    true
+}
@@ -0,0 +1,332 @@
+//! Detached backends (`detached` start option), which keep running when the viewer exits
+//! and are reattached by the next start_python with the same settings; stop_python stops
+//! one that a start with other settings refused.
+//!
+//! A detached backend runs in its own session (setsid on Unix, DETACHED_PROCESS on
+//! Windows) with stdout and stderr written to files under `<app data>/detached` instead of
+//! pipes, since pipes would break when the viewer goes away; the viewer tails those files
+//! and empties them once they pass MAX_OUTPUT_BYTES (the backend appends, so it carries on
+//! at the start). Its stdin is not connected, so the control channel (RPC, send_to_python)
+//! is not available.
+//!
+//! The pid, start time and a random token, which the backend gets as TOKEN_ENV, are kept
+//! in `detached.json`. Before a pid is reattached or signalled it must still be that
+//! process: on Linux the token is checked in its environment, on macOS and Windows its
+//! start time against the recorded one. Where neither can be checked the state is
+//! discarded.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{bootstrap, token};
+use crate::logs::Stream;
+
+/// Environment variable carrying the reattach token
+pub const TOKEN_ENV: &str = "TELEVOODOO_DETACH_TOKEN";
+/// An output file this large is emptied once the tail has read it to the end
+const MAX_OUTPUT_BYTES: u64 = 16 * 1024 * 1024;
+/// How long before `started_at_ms` (recorded right after the spawn) the process may have
+/// started and still count as ours
+const START_TOLERANCE_MS: u64 = 5_000;
+
+/// Contents of `detached.json`
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct State {
+    pub pid: u32,
+    pub token: String,
+    pub started_at_ms: u64,
+    pub stdout: PathBuf,
+    pub stderr: PathBuf,
+    pub interpreter: Option<PathBuf>,
+    /// StartConfig of the launch, restored as the last start config on reattach
+    pub config: serde_json::Value,
+}
+
+/// Output files of a detached backend being spawned
+pub struct Output {
+    pub token: String,
+    pub stdout: PathBuf,
+    pub stderr: PathBuf,
+}
+
+// Set while the current backend is detached (spawned here or reattached), so viewer exit
+// leaves it running
+static DETACHED: AtomicBool = AtomicBool::new(false);
+// A reattached backend, which is not a child of this viewer
+static ATTACHED: Mutex<Option<State>> = Mutex::new(None);
+// State file of the current detached backend, removed once it is gone
+static STATE_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    bootstrap::data_dir(app).map(|d| d.join("detached")).ok_or_else(|| "Could not determine app data directory".to_string())
+}
+
+/// Points `cmd`'s output at fresh files, detaches it from stdin and sets the token
+pub fn prepare<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: &mut Command) -> Result<Output, String> {
+    let dir = dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let token = token::random()?;
+    let (stdout, stderr) = (dir.join(format!("{}.out", token)), dir.join(format!("{}.err", token)));
+    // Append mode, so the backend keeps writing at the end after tail empties a file
+    let create = |path: &Path| OpenOptions::new().append(true).create_new(true).open(path).map_err(|e| format!("Could not create {}: {}", path.display(), e));
+    cmd.stdin(Stdio::null()).stdout(create(&stdout)?).stderr(create(&stderr)?).env(TOKEN_ENV, &token);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP
+        cmd.creation_flags(0x0000_0008 | 0x0000_0200);
+    }
+    Ok(Output { token, stdout, stderr })
+}
+
+/// Records a spawned detached backend so the next start can reattach to it
+pub fn save<R: tauri::Runtime>(app: &tauri::AppHandle<R>, state: &State) -> Result<(), String> {
+    let path = dir(app)?.join("detached.json");
+    let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    *STATE_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(path);
+    DETACHED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// A still-running detached backend from an earlier viewer run. Stale state (and its
+/// output files) is cleaned up.
+pub fn find_running<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<State> {
+    let path = dir(app).ok()?.join("detached.json");
+    let state: State = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+    if alive(state.pid) && same_process(&state) {
+        return Some(state);
+    }
+    let _ = std::fs::remove_file(&state.stdout);
+    let _ = std::fs::remove_file(&state.stderr);
+    let _ = std::fs::remove_file(&path);
+    None
+}
+
+/// Adopts a backend found by find_running
+pub fn attach<R: tauri::Runtime>(app: &tauri::AppHandle<R>, state: &State) {
+    *ATTACHED.lock().unwrap_or_else(|e| e.into_inner()) = Some(state.clone());
+    *STATE_FILE.lock().unwrap_or_else(|e| e.into_inner()) = dir(app).ok().map(|d| d.join("detached.json"));
+    DETACHED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_detached() -> bool {
+    DETACHED.load(Ordering::SeqCst)
+}
+
+pub fn attached() -> Option<State> {
+    ATTACHED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn attached_pid() -> Option<u32> {
+    attached().map(|state| state.pid)
+}
+
+pub fn attached_alive() -> bool {
+    attached().is_some_and(|state| alive(state.pid) && same_process(&state))
+}
+
+/// Forgets the current detached backend and removes its state and output files
+pub fn clear() {
+    DETACHED.store(false, Ordering::SeqCst);
+    ATTACHED.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(path) = STATE_FILE.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        if let Some(state) = std::fs::read_to_string(&path).ok().and_then(|json| serde_json::from_str::<State>(&json).ok()) {
+            let _ = std::fs::remove_file(state.stdout);
+            let _ = std::fs::remove_file(state.stderr);
+        }
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Stops a reattached backend: SIGTERM (its whole session) and SIGKILL after 2 s. Nothing
+/// is signalled once the pid no longer belongs to `state`'s process.
+pub fn terminate(state: &State) {
+    let pid = state.pid;
+    let ours = || alive(pid) && same_process(state);
+    if !ours() {
+        return;
+    }
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(-(pid as i32), libc::SIGTERM) };
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while ours() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if ours() {
+            unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+        }
+    }
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if !handle.is_null() {
+            TerminateProcess(handle, 1);
+            CloseHandle(handle);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as i32, 0) == 0 }
+}
+
+#[cfg(windows)]
+pub fn alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut code = 0;
+        let ok = GetExitCodeProcess(handle, &mut code) != 0;
+        CloseHandle(handle);
+        ok && code == STILL_ACTIVE as u32
+    }
+}
+
+/// Whether `state.pid` is still the process `state` was saved for, so a reused pid isn't
+/// adopted or killed: started with our token
+#[cfg(target_os = "linux")]
+fn same_process(state: &State) -> bool {
+    let wanted = format!("{}={}", TOKEN_ENV, state.token);
+    std::fs::read(format!("/proc/{}/environ", state.pid))
+        .map(|environ| environ.split(|b| *b == 0).any(|var| var == wanted.as_bytes()))
+        .unwrap_or(false)
+}
+
+/// Whether `state.pid` is still the process `state` was saved for: started when it was
+/// recorded
+#[cfg(target_os = "macos")]
+fn same_process(state: &State) -> bool {
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+    let read = unsafe { libc::proc_pidinfo(state.pid as libc::c_int, libc::PROC_PIDTBSDINFO, 0, &mut info as *mut _ as *mut libc::c_void, size) };
+    read == size && started_near(info.pbi_start_tvsec * 1000 + info.pbi_start_tvusec / 1000, state.started_at_ms)
+}
+
+/// Whether `state.pid` is still the process `state` was saved for: started when it was
+/// recorded
+#[cfg(windows)]
+fn same_process(state: &State) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows_sys::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, state.pid);
+        if handle.is_null() {
+            return false;
+        }
+        let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+        let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+        let ok = GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user) != 0;
+        CloseHandle(handle);
+        // 100 ns intervals since 1601-01-01
+        let ticks = ((created.dwHighDateTime as u64) << 32) | created.dwLowDateTime as u64;
+        ok && started_near((ticks / 10_000).saturating_sub(11_644_473_600_000), state.started_at_ms)
+    }
+}
+
+/// Identity can't be checked here, so no saved backend is trusted
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn same_process(_state: &State) -> bool {
+    false
+}
+
+/// Whether a process started at `start_ms` is the one recorded at `recorded_ms`, allowing
+/// a second of clock granularity after it
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn started_near(start_ms: u64, recorded_ms: u64) -> bool {
+    start_ms <= recorded_ms + 1_000 && recorded_ms.saturating_sub(start_ms) <= START_TOLERANCE_MS
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Follows `path` from `offset`, sending complete lines, until `alive` turns false and
+/// the file has been read to its end. A file read to its end past MAX_OUTPUT_BYTES is
+/// emptied; lines the backend writes between that read and the truncation are lost.
+pub fn tail(path: PathBuf, offset: u64, stream: Stream, tx: Sender<(Stream, String)>, alive: impl Fn() -> bool + Send + 'static) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let Ok(mut file) = OpenOptions::new().read(true).write(true).open(&path) else { return };
+        if file.seek(SeekFrom::Start(offset)).is_err() {
+            return;
+        }
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => {
+                    if !alive() {
+                        // One more read for anything written right before the exit
+                        let _ = reader.read_until(b'\n', &mut buf);
+                        if !buf.is_empty() {
+                            let _ = tx.send((stream, String::from_utf8_lossy(&buf).trim_end_matches(['\n', '\r']).to_string()));
+                        }
+                        return;
+                    }
+                    if buf.is_empty() && reader.stream_position().is_ok_and(|position| position >= MAX_OUTPUT_BYTES) && reader.get_ref().set_len(0).is_ok() {
+                        let _ = reader.seek(SeekFrom::Start(0));
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                // A partial line stays in `buf` until the rest arrives
+                Ok(_) if buf.ends_with(b"\n") => {
+                    let line = String::from_utf8_lossy(&buf).trim_end_matches(['\n', '\r']).to_string();
+                    buf.clear();
+                    if tx.send((stream, line)).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_time_must_be_just_before_the_record() {
+        assert!(started_near(10_000, 10_400));
+        assert!(started_near(10_900, 10_400));
+        assert!(!started_near(2_000, 10_400));
+        assert!(!started_near(12_000, 10_400));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn identity_needs_the_token() {
+        let mut child = Command::new("sleep").arg("5").env(TOKEN_ENV, "abc123").spawn().unwrap();
+        let state = State {
+            pid: child.id(),
+            token: "abc123".to_string(),
+            started_at_ms: now_ms(),
+            stdout: PathBuf::new(),
+            stderr: PathBuf::new(),
+            interpreter: None,
+            config: serde_json::Value::Null,
+        };
+        // The kernel fills in the new image's environment a moment after spawn returns
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(same_process(&state));
+        assert!(!same_process(&State { token: "other".to_string(), ..state.clone() }));
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(!same_process(&state));
+    }
+}
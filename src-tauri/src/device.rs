@@ -0,0 +1,198 @@
+//! Hardware preflight for check_device: whether a serial/USB device node or a network
+//! endpoint is usable before a teleop session starts. Device nodes are opened read/write
+//! (non-blocking, without becoming the controlling terminal) and, on Linux, checked for
+//! other processes holding them; `host:port` targets get a TCP connect. Anything else
+//! (BLE addresses, camera ids, ...) is asked of a running backend via `device.check`.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::control;
+
+/// Timeout of the TCP probe per resolved address
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceState {
+    Available,
+    /// Exists but another process holds it
+    Busy,
+    NotFound,
+    PermissionDenied,
+    /// Network target that didn't answer in time
+    Unreachable,
+    /// Could not be probed (no backend running, or the backend can't check it)
+    Unknown,
+}
+
+/// Returned by check_device
+#[derive(Clone, serde::Serialize)]
+pub struct DeviceStatus {
+    pub target: String,
+    /// "device", "network" or "backend": how it was probed
+    pub kind: &'static str,
+    pub state: DeviceState,
+    /// Human-readable explanation, including how to fix permission problems
+    pub detail: String,
+}
+
+/// Probes `target`: a device path (`/dev/ttyUSB0`, `COM3`), `host:port`, or an id the
+/// backend understands
+pub fn check(target: &str, backend_running: bool) -> DeviceStatus {
+    let target = target.trim();
+    let status = |kind, state, detail: String| DeviceStatus { target: target.to_string(), kind, state, detail };
+    if let Some(path) = device_path(target) {
+        let (state, detail) = probe_device(&path);
+        return status("device", state, detail);
+    }
+    if let Some(addr) = network_target(target) {
+        let (state, detail) = probe_network(addr);
+        return status("network", state, detail);
+    }
+    if !backend_running {
+        return status("backend", DeviceState::Unknown, "Not a device path or host:port; start the backend to let it check this device".to_string());
+    }
+    match control::call("device.check", serde_json::json!({ "device": target }), control::DEFAULT_TIMEOUT) {
+        Ok(result) => {
+            let state = result.get("state").cloned().and_then(|s| serde_json::from_value(s).ok()).unwrap_or(DeviceState::Unknown);
+            let detail = result.get("detail").and_then(Value::as_str).unwrap_or_default().to_string();
+            status("backend", state, detail)
+        }
+        Err(e) if control::unsupported(&e) => status("backend", DeviceState::Unknown, "The backend does not support device checks".to_string()),
+        Err(e) => status("backend", DeviceState::Unknown, format!("Device check failed: {}", e)),
+    }
+}
+
+fn device_path(target: &str) -> Option<std::path::PathBuf> {
+    if cfg!(windows) {
+        let upper = target.to_ascii_uppercase();
+        if upper.strip_prefix("COM").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())) {
+            return Some(format!(r"\\.\{}", upper).into());
+        }
+        return target.starts_with(r"\\.\").then(|| target.into());
+    }
+    target.starts_with('/').then(|| target.into())
+}
+
+/// `host:port`, `tcp://host:port` or `[v6]:port`. A host with colons must be bracketed,
+/// so MAC addresses like `AA:BB:CC:DD:EE:12` aren't taken for one.
+fn network_target(target: &str) -> Option<&str> {
+    let addr = target.strip_prefix("tcp://").unwrap_or(target).trim_end_matches('/');
+    let (host, port) = addr.rsplit_once(':')?;
+    let host_ok = match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(v6) => v6.parse::<std::net::Ipv6Addr>().is_ok(),
+        None => !host.is_empty() && !host.contains(':'),
+    };
+    (host_ok && port.parse::<u16>().is_ok()).then_some(addr)
+}
+
+fn probe_network(addr: &str) -> (DeviceState, String) {
+    let addrs: Vec<_> = match addr.to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(e) => return (DeviceState::NotFound, format!("Could not resolve {}: {}", addr, e)),
+    };
+    let mut last_error = None;
+    for socket in &addrs {
+        match TcpStream::connect_timeout(socket, CONNECT_TIMEOUT) {
+            Ok(_) => return (DeviceState::Available, format!("Connected to {}", socket)),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => (DeviceState::NotFound, format!("Nothing is listening on {}", addr)),
+        Some(e) => (DeviceState::Unreachable, format!("Could not connect to {}: {}", addr, e)),
+        None => (DeviceState::NotFound, format!("{} resolved to no addresses", addr)),
+    }
+}
+
+fn probe_device(path: &std::path::Path) -> (DeviceState, String) {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true).write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // A serial port without carrier would otherwise block the open
+        options.custom_flags(libc::O_NONBLOCK | libc::O_NOCTTY);
+    }
+    match options.open(path) {
+        Ok(_) => match holders(path) {
+            pids if !pids.is_empty() => (DeviceState::Busy, format!("{} is open in process(es) {:?}", path.display(), pids)),
+            _ => (DeviceState::Available, format!("{} can be opened", path.display())),
+        },
+        Err(e) => match e.kind() {
+            std::io::ErrorKind::NotFound => (DeviceState::NotFound, format!("{} does not exist; is the device plugged in?", path.display())),
+            std::io::ErrorKind::PermissionDenied => (DeviceState::PermissionDenied, permission_hint(path)),
+            _ if e.raw_os_error() == Some(busy_errno()) => (DeviceState::Busy, format!("{} is in use: {}", path.display(), e)),
+            _ => (DeviceState::Unknown, format!("Could not open {}: {}", path.display(), e)),
+        },
+    }
+}
+
+#[cfg(unix)]
+fn busy_errno() -> i32 {
+    libc::EBUSY
+}
+
+/// ERROR_ACCESS_DENIED is what Windows returns for a COM port another process holds
+#[cfg(windows)]
+fn busy_errno() -> i32 {
+    5
+}
+
+fn permission_hint(path: &std::path::Path) -> String {
+    if cfg!(target_os = "linux") {
+        format!("No permission to open {}; add your user to its group (usually `dialout`: sudo usermod -aG dialout $USER, then log in again) or add a udev rule", path.display())
+    } else {
+        format!("No permission to open {}", path.display())
+    }
+}
+
+/// Pids of other processes (that we may inspect) with `path` open
+#[cfg(target_os = "linux")]
+fn holders(path: &std::path::Path) -> Vec<u32> {
+    let Ok(target) = path.canonicalize() else { return Vec::new() };
+    let own = std::process::id();
+    let Ok(procs) = std::fs::read_dir("/proc") else { return Vec::new() };
+    procs
+        .filter_map(Result::ok)
+        .filter_map(|p| p.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != own)
+        .filter(|pid| {
+            std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .map(|fds| fds.filter_map(Result::ok).any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| link == target)))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn holders(_path: &std::path::Path) -> Vec<u32> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_targets() {
+        assert_eq!(network_target("192.168.1.20:5000"), Some("192.168.1.20:5000"));
+        assert_eq!(network_target("tcp://robot.local:80/"), Some("robot.local:80"));
+        assert_eq!(network_target("[::1]:9000"), Some("[::1]:9000"));
+        assert_eq!(network_target("AA:BB:CC:DD:EE:FF"), None);
+        assert_eq!(network_target("AA:BB:CC:DD:EE:12"), None);
+        assert_eq!(network_target("::1:9000"), None);
+        assert_eq!(network_target("camera0"), None);
+        #[cfg(unix)]
+        {
+            assert_eq!(check("/dev/televoodoo-does-not-exist", false).state, DeviceState::NotFound);
+            let file = std::env::temp_dir().join(format!("televoodoo-device-{}", std::process::id()));
+            std::fs::write(&file, "").unwrap();
+            assert_eq!(check(&file.to_string_lossy(), false).state, DeviceState::Available);
+            let _ = std::fs::remove_file(&file);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! `.env` files merged into the backend's environment (`env_file` config).
+//!
+//! Supported syntax: `KEY=value` lines, optionally prefixed with `export `; blank lines and
+//! `#` comments; unquoted values (trailing ` # comment` stripped), single-quoted values
+//! (taken literally) and double-quoted values (`\n`, `\t`, `\"` and `\\` escapes). Values
+//! don't span lines. Lines that don't parse are skipped and reported.
+
+use std::path::Path;
+
+/// Larger files are refused; a `.env` this big is almost certainly the wrong file
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Checks that `path` is an absolute path to a readable regular file of sane size
+pub fn validate_path(path: &Path) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err(format!("env_file must be an absolute path: {}", path.display()));
+    }
+    let meta = std::fs::metadata(path).map_err(|e| format!("env_file {} is not readable: {}", path.display(), e))?;
+    if !meta.is_file() {
+        return Err(format!("env_file is not a regular file: {}", path.display()));
+    }
+    if meta.len() > MAX_FILE_BYTES {
+        return Err(format!("env_file {} is larger than {} bytes", path.display(), MAX_FILE_BYTES));
+    }
+    Ok(())
+}
+
+/// Parsed variables in file order, plus `(line number, reason)` for each skipped line
+pub struct DotEnv {
+    pub vars: Vec<(String, String)>,
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// Validates and reads `path`
+pub fn load(path: &Path) -> Result<DotEnv, String> {
+    validate_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read env_file {}: {}", path.display(), e))?;
+    Ok(parse(&text))
+}
+
+pub fn parse(text: &str) -> DotEnv {
+    let mut env = DotEnv { vars: Vec::new(), skipped: Vec::new() };
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(var) => env.vars.push(var),
+            Err(reason) => env.skipped.push((index + 1, reason)),
+        }
+    }
+    env
+}
+
+fn parse_line(line: &str) -> Result<(String, String), String> {
+    let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+    let (key, raw) = line.split_once('=').ok_or_else(|| "missing `=`".to_string())?;
+    let key = key.trim();
+    let valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_key {
+        return Err(format!("invalid variable name {:?}", key));
+    }
+    let raw = raw.trim_start();
+    let (value, rest) = match raw.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let mut value = String::new();
+            let mut chars = raw[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, c)) if c == quote => break i + 1,
+                    Some((_, '\\')) if quote == '"' => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, c @ ('"' | '\\'))) => value.push(c),
+                        Some((_, c)) => {
+                            value.push('\\');
+                            value.push(c);
+                        }
+                        None => return Err("unterminated quoted value".to_string()),
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => return Err("unterminated quoted value".to_string()),
+                }
+            };
+            (value, raw[end + 1..].trim())
+        }
+        _ => {
+            let value = match raw.find(" #") {
+                Some(comment) => &raw[..comment],
+                None => raw,
+            };
+            (value.trim_end().to_string(), "")
+        }
+    };
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err("unexpected text after closing quote".to_string());
+    }
+    Ok((key.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comments_quotes_and_bad_lines() {
+        let env = parse(concat!(
+            "# robot settings\n",
+            "ROBOT_IP=10.0.0.5  # lab arm\n",
+            "export NAME='it''s'\n",
+            "GREETING=\"hi \\\"there\\\"\\nbye\" # quoted\n",
+            "RAW='a\\nb'\n",
+            "EMPTY=\n",
+            "not a var\n",
+            "1BAD=x\n",
+            "OPEN=\"never closed\n",
+        ));
+        let vars: Vec<(&str, &str)> = env.vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(
+            vars,
+            vec![("ROBOT_IP", "10.0.0.5"), ("GREETING", "hi \"there\"\nbye"), ("RAW", "a\\nb"), ("EMPTY", "")]
+        );
+        let lines: Vec<usize> = env.skipped.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![3, 7, 8, 9]);
+    }
+}
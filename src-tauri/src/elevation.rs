@@ -0,0 +1,442 @@
+//! Launching the backend with administrator rights, for hardware the user's account can't
+//! open (serial ports, raw USB). The user is prompted by the platform on every elevated
+//! start, including auto-restarts.
+//!
+//! Security: the whole backend, every Python package in its environment and any code the
+//! robot config loads then run as root. Only use it with a trusted environment, and prefer
+//! fixing device permissions (e.g. adding the user to the `dialout` group or a udev rule)
+//! where possible.
+//!
+//! Linux runs the backend through pkexec, or `sudo -A` when SUDO_ASKPASS is set; output is
+//! streamed as usual. macOS uses an administrator prompt from osascript, which only hands
+//! over the output once the backend exits. Windows can't give an elevated process pipes
+//! of a non-elevated one, so the viewer binary runs itself as a broker (BROKER_ARG) that
+//! asks for elevation through the UAC prompt (ShellExecuteEx `runas`). The elevated copy
+//! (WRAPPER_ARG) runs the backend with its output written to files next to the stop file,
+//! which the broker follows onto its own stdout/stderr. As with detached backends, the
+//! control channel (RPC, send_to_python) is not connected there.
+//!
+//! The viewer can't signal an elevated process, so the elevated side runs a small wrapper
+//! (a shell script, or the viewer binary on Windows) that stops the backend when the
+//! viewer creates a stop file. Stop files live under `<app data>/elevated`, where other
+//! users can't create or remove them.
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::bootstrap;
+
+// Stop file of the running elevated backend
+static STOP_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// How long request_stop waits for the wrapper to stop the backend
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run as root with `$1` the stop file, `$2` the working dir and the command after them.
+/// The explicit `<&0` keeps stdin (the RPC channel) attached to the background job.
+const WRAPPER: &str = r#"stop="$1"; cd "$2" || exit 1; shift 2
+"$@" <&0 &
+pid=$!
+while kill -0 "$pid" 2>/dev/null; do
+    if [ -e "$stop" ]; then
+        kill -TERM "$pid" 2>/dev/null
+        sleep 2
+        kill -KILL "$pid" 2>/dev/null
+        break
+    fi
+    sleep 1
+done
+wait "$pid""#;
+
+/// Returns `cmd` wrapped to run elevated. Elevation tools reset the environment, so the
+/// variables set on `cmd` are passed explicitly; inherited ones are not.
+pub fn wrap<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: &Command) -> Result<Command, String> {
+    let dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?.join("elevated");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let stop_file = dir.join(format!(
+        "{}-{}.stop",
+        std::process::id(),
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+    ));
+    let elevated = elevated_command(cmd, &stop_file)?;
+    *STOP_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(stop_file);
+    Ok(elevated)
+}
+
+/// `/bin/sh -c WRAPPER` with `cmd`'s working dir, environment, program and arguments
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+fn wrapper_argv(cmd: &Command, stop_file: &Path) -> Vec<String> {
+    let mut argv: Vec<String> = vec![
+        "/bin/sh".into(),
+        "-c".into(),
+        WRAPPER.into(),
+        "sh".into(),
+        stop_file.to_string_lossy().into_owned(),
+        cmd.get_current_dir().unwrap_or(Path::new(".")).to_string_lossy().into_owned(),
+        "env".into(),
+    ];
+    for (key, value) in cmd.get_envs() {
+        if let Some(value) = value {
+            argv.push(format!("{}={}", key.to_string_lossy(), value.to_string_lossy()));
+        }
+    }
+    argv.push(cmd.get_program().to_string_lossy().into_owned());
+    argv.extend(cmd.get_args().map(|a| a.to_string_lossy().into_owned()));
+    argv
+}
+
+#[cfg(target_os = "linux")]
+fn elevated_command(cmd: &Command, stop_file: &Path) -> Result<Command, String> {
+    let argv = wrapper_argv(cmd, stop_file);
+    if on_path("pkexec") {
+        let mut cmd = Command::new("pkexec");
+        cmd.args(&argv);
+        return Ok(cmd);
+    }
+    if std::env::var_os("SUDO_ASKPASS").is_some() && on_path("sudo") {
+        let mut cmd = Command::new("sudo");
+        cmd.arg("-A").arg("--").args(&argv);
+        return Ok(cmd);
+    }
+    Err("Elevated start needs pkexec (polkit), or sudo with SUDO_ASKPASS set".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn elevated_command(cmd: &Command, stop_file: &Path) -> Result<Command, String> {
+    let script = wrapper_argv(cmd, stop_file).iter().map(|a| sh_quote(a)).collect::<Vec<_>>().join(" ") + " 2>&1";
+    let escaped = script.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(format!("do shell script \"{}\" with administrator privileges", escaped));
+    Ok(cmd)
+}
+
+/// The viewer binary as broker for `cmd` (see run_helper)
+#[cfg(windows)]
+fn elevated_command(cmd: &Command, stop_file: &Path) -> Result<Command, String> {
+    use std::os::windows::process::CommandExt;
+    let exe = std::env::current_exe().map_err(|e| format!("Could not locate the viewer executable: {}", e))?;
+    let mut broker = Command::new(exe);
+    broker.arg(BROKER_ARG).args(helper_args(cmd, stop_file));
+    // CREATE_NO_WINDOW
+    broker.creation_flags(0x0800_0000);
+    Ok(broker)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn elevated_command(_cmd: &Command, _stop_file: &Path) -> Result<Command, String> {
+    Err("Elevated start is not supported on this platform; run the viewer as administrator instead".to_string())
+}
+
+/// First argument of the viewer binary run as the non-elevated broker
+#[cfg(windows)]
+const BROKER_ARG: &str = "--televoodoo-elevation-broker";
+/// First argument of the viewer binary run elevated, as the wrapper around the backend
+#[cfg(windows)]
+const WRAPPER_ARG: &str = "--televoodoo-elevation-wrapper";
+/// An output file this large is emptied once the broker has forwarded all of it
+#[cfg(windows)]
+const MAX_OUTPUT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Broker and wrapper arguments: `<stop file> <working dir> [KEY=VALUE ...] -- <program> [args ...]`
+#[cfg(windows)]
+fn helper_args(cmd: &Command, stop_file: &Path) -> Vec<String> {
+    let mut args = vec![
+        stop_file.to_string_lossy().into_owned(),
+        cmd.get_current_dir().unwrap_or(Path::new(".")).to_string_lossy().into_owned(),
+    ];
+    for (key, value) in cmd.get_envs() {
+        if let Some(value) = value {
+            args.push(format!("{}={}", key.to_string_lossy(), value.to_string_lossy()));
+        }
+    }
+    args.push("--".into());
+    args.push(cmd.get_program().to_string_lossy().into_owned());
+    args.extend(cmd.get_args().map(|a| a.to_string_lossy().into_owned()));
+    args
+}
+
+#[cfg(windows)]
+struct Helper {
+    stop_file: PathBuf,
+    cwd: PathBuf,
+    envs: Vec<(String, String)>,
+    program: String,
+    args: Vec<String>,
+}
+
+#[cfg(windows)]
+fn parse_helper_args(args: &[String]) -> Option<Helper> {
+    let [stop_file, cwd, rest @ ..] = args else { return None };
+    let split = rest.iter().position(|a| a == "--")?;
+    let envs = rest[..split].iter().map(|kv| kv.split_once('=').map(|(k, v)| (k.to_string(), v.to_string()))).collect::<Option<Vec<_>>>()?;
+    let (program, args) = rest[split + 1..].split_first()?;
+    Some(Helper { stop_file: stop_file.into(), cwd: cwd.into(), envs, program: program.clone(), args: args.to_vec() })
+}
+
+/// Stdout and stderr files of the elevated backend stopped by `stop_file`
+#[cfg(windows)]
+fn output_files(stop_file: &Path) -> (PathBuf, PathBuf) {
+    (stop_file.with_extension("out"), stop_file.with_extension("err"))
+}
+
+/// Runs the broker or wrapper side of an elevated start when this process was launched
+/// as one, returning its exit code; None for a normal app start
+#[cfg(windows)]
+pub fn run_helper() -> Option<i32> {
+    let args: Vec<String> = std::env::args_os().skip(1).map(|a| a.to_string_lossy().into_owned()).collect();
+    let (role, rest) = args.split_first()?;
+    let helper = || parse_helper_args(rest).ok_or_else(|| format!("Invalid arguments for {}", role));
+    let result = match role.as_str() {
+        BROKER_ARG => helper().and_then(|h| broker(&h, rest)),
+        WRAPPER_ARG => helper().and_then(|h| wrapper(&h)),
+        _ => return None,
+    };
+    Some(result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        1
+    }))
+}
+
+/// Non-elevated side: starts the wrapper elevated and forwards the backend's output until
+/// it exits, with its exit code
+#[cfg(windows)]
+fn broker(helper: &Helper, args: &[String]) -> Result<i32, String> {
+    let (stdout, stderr) = output_files(&helper.stop_file);
+    for path in [&stdout, &stderr] {
+        std::fs::write(path, b"").map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    }
+    // The viewer writes its control channel here, but nothing on the elevated side reads it
+    std::thread::spawn(|| std::io::copy(&mut std::io::stdin(), &mut std::io::sink()));
+    let params = std::iter::once(WRAPPER_ARG).chain(args.iter().map(String::as_str)).map(windows_quote).collect::<Vec<_>>().join(" ");
+    let result = run_as_admin(&params).map(|process| follow(process, &stdout, &stderr));
+    let _ = std::fs::remove_file(&stdout);
+    let _ = std::fs::remove_file(&stderr);
+    result
+}
+
+/// Starts the viewer binary with `params` through the UAC prompt
+#[cfg(windows)]
+fn run_as_admin(params: &str) -> Result<windows_sys::Win32::Foundation::HANDLE, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::ERROR_CANCELLED;
+    use windows_sys::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+
+    let exe = std::env::current_exe().map_err(|e| format!("Could not locate the viewer executable: {}", e))?;
+    let wide = |s: &std::ffi::OsStr| s.encode_wide().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let (verb, file, params) = (wide("runas".as_ref()), wide(exe.as_os_str()), wide(params.as_ref()));
+    unsafe {
+        let mut info: SHELLEXECUTEINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        info.fMask = SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC;
+        info.lpVerb = verb.as_ptr();
+        info.lpFile = file.as_ptr();
+        info.lpParameters = params.as_ptr();
+        // SW_HIDE
+        info.nShow = 0;
+        if ShellExecuteExW(&mut info) == 0 {
+            let e = std::io::Error::last_os_error();
+            return Err(if e.raw_os_error() == Some(ERROR_CANCELLED as i32) {
+                "The administrator prompt was declined".to_string()
+            } else {
+                format!("Could not start the backend elevated: {}", e)
+            });
+        }
+        if info.hProcess.is_null() {
+            return Err("The elevated backend started without a process handle".to_string());
+        }
+        Ok(info.hProcess)
+    }
+}
+
+/// Copies what the elevated side writes to `stdout`/`stderr` onto ours until `process`
+/// exits, then returns its exit code
+#[cfg(windows)]
+fn follow(process: windows_sys::Win32::Foundation::HANDLE, stdout: &Path, stderr: &Path) -> i32 {
+    use windows_sys::Win32::Foundation::{CloseHandle, WAIT_OBJECT_0};
+    use windows_sys::Win32::System::Threading::{GetExitCodeProcess, WaitForSingleObject};
+
+    let mut offsets = [0u64; 2];
+    loop {
+        let exited = unsafe { WaitForSingleObject(process, 200) } == WAIT_OBJECT_0;
+        // Once exited, this pass forwards the last of the output
+        copy_new(stdout, &mut offsets[0], &mut std::io::stdout());
+        copy_new(stderr, &mut offsets[1], &mut std::io::stderr());
+        if exited {
+            break;
+        }
+    }
+    let mut code = 1u32;
+    unsafe {
+        GetExitCodeProcess(process, &mut code);
+        CloseHandle(process);
+    }
+    code as i32
+}
+
+/// Writes what was appended to `path` since `offset` to `out`; empties the file once it
+/// passes MAX_OUTPUT_BYTES (the wrapper appends, so the backend carries on at the start)
+#[cfg(windows)]
+fn copy_new(path: &Path, offset: &mut u64, out: &mut impl std::io::Write) {
+    use std::io::{Read, Seek, SeekFrom};
+    let Ok(mut file) = std::fs::OpenOptions::new().read(true).write(true).open(path) else { return };
+    let mut new = Vec::new();
+    if file.seek(SeekFrom::Start(*offset)).is_err() || file.read_to_end(&mut new).is_err() {
+        return;
+    }
+    *offset += new.len() as u64;
+    let _ = out.write_all(&new);
+    let _ = out.flush();
+    if *offset > MAX_OUTPUT_BYTES && file.set_len(0).is_ok() {
+        *offset = 0;
+    }
+}
+
+/// Elevated side: runs the backend with its output in the broker's files and stops it
+/// once the stop file appears
+#[cfg(windows)]
+fn wrapper(helper: &Helper) -> Result<i32, String> {
+    use std::io::Write;
+    use std::os::windows::process::CommandExt;
+
+    let (stdout, stderr) = output_files(&helper.stop_file);
+    let open = |path: &Path| std::fs::OpenOptions::new().append(true).open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e));
+    let (out, mut err) = (open(&stdout)?, open(&stderr)?);
+    let mut cmd = Command::new(&helper.program);
+    cmd.args(&helper.args)
+        .current_dir(&helper.cwd)
+        .envs(helper.envs.iter().map(|(k, v)| (k, v)))
+        .stdin(std::process::Stdio::null())
+        .stdout(out)
+        .stderr(err.try_clone().map_err(|e| e.to_string())?);
+    // CREATE_NO_WINDOW: the backend is a console program; don't open a console for it
+    cmd.creation_flags(0x0800_0000);
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = writeln!(err, "Could not start {}: {}", helper.program, e);
+            return Ok(1);
+        }
+    };
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status.code().unwrap_or(1)),
+            Ok(None) if helper.stop_file.exists() => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(1);
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(250)),
+            Err(e) => return Err(format!("Could not wait for the backend: {}", e)),
+        }
+    }
+}
+
+/// Quotes `arg` for a Windows command line as CommandLineToArgvW splits it
+#[cfg_attr(not(windows), allow(dead_code))]
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\x0b', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escaped, and so is the quote
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Before the closing quote they would escape it
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(target_os = "linux")]
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Single-quotes `arg` for sh
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn sh_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Forgets the stop file (the next start is not elevated)
+pub fn clear() {
+    STOP_FILE.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// When `child` is an elevated backend, asks its wrapper to stop it and waits up to
+/// STOP_TIMEOUT. No-op otherwise.
+pub fn request_stop(child: &mut Child) {
+    let Some(stop_file) = STOP_FILE.lock().unwrap_or_else(|e| e.into_inner()).take() else { return };
+    if std::fs::write(&stop_file, b"").is_ok() {
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while matches!(child.try_wait(), Ok(None)) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    let _ = std::fs::remove_file(&stop_file);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_for_sh() {
+        assert_eq!(sh_quote("it's"), "'it'\\''s'");
+        assert_eq!(sh_quote("plain"), "'plain'");
+    }
+
+    #[test]
+    fn quotes_for_windows_command_lines() {
+        assert_eq!(windows_quote("plain"), "plain");
+        assert_eq!(windows_quote(""), r#""""#);
+        assert_eq!(windows_quote(r"C:\Program Files\py"), r#""C:\Program Files\py""#);
+        assert_eq!(windows_quote(r"C:\dir with space\"), r#""C:\dir with space\\""#);
+        assert_eq!(windows_quote(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(windows_quote(r#"a\"b"#), r#""a\\\"b""#);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wrapper_stops_the_command_on_the_stop_file() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let stop_file = std::env::temp_dir().join(format!("tv-elevation-{}.stop", std::process::id()));
+        let mut cmd = Command::new(manifest_dir.join("tests").join("fixtures").join("fake-python"));
+        cmd.args(["-m", "televoodoo"]).env("FAKE_PYTHON_SLEEP", "30").current_dir(manifest_dir);
+        let argv = wrapper_argv(&cmd, &stop_file);
+        let mut wrapper = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(500));
+        assert!(matches!(wrapper.try_wait(), Ok(None)));
+
+        std::fs::write(&stop_file, b"").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(6);
+        while matches!(wrapper.try_wait(), Ok(None)) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        let exited = !matches!(wrapper.try_wait(), Ok(None));
+        let _ = wrapper.kill();
+        let _ = std::fs::remove_file(&stop_file);
+        assert!(exited, "wrapper still running after the stop file appeared");
+    }
+}
@@ -0,0 +1,104 @@
+//! Discovery of the address the backend listens on, from a line it prints at startup
+//! (e.g. `LISTENING on 127.0.0.1:8765`). The pattern comes from the `endpoint_pattern`
+//! config; its first capture group must hold the `host:port`.
+
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use regex::Regex;
+use tauri::Emitter;
+
+use crate::logs::{self, Stream};
+use crate::start_timings;
+
+// Pattern of the current backend; None when unset or invalid
+static PATTERN: Mutex<Option<Regex>> = Mutex::new(None);
+static ENDPOINT: Mutex<Option<SocketAddr>> = Mutex::new(None);
+// Set once the current backend's endpoint was found, so later lines skip the regex
+static FOUND: AtomicBool = AtomicBool::new(false);
+
+/// Payload of `python-endpoint-discovered`
+#[derive(Clone, serde::Serialize)]
+struct EndpointDiscovered {
+    address: SocketAddr,
+}
+
+/// Forgets the previous backend's endpoint and compiles `pattern` for the new one. An
+/// invalid pattern is reported and disables discovery.
+pub fn reset<R: tauri::Runtime>(app: &tauri::AppHandle<R>, pattern: &str) {
+    let regex = if pattern.is_empty() {
+        None
+    } else {
+        match Regex::new(pattern) {
+            Ok(regex) if regex.captures_len() > 1 => Some(regex),
+            Ok(_) => {
+                warn(app, &format!("endpoint_pattern {:?} has no capture group", pattern));
+                None
+            }
+            Err(e) => {
+                warn(app, &format!("invalid endpoint_pattern: {}", e));
+                None
+            }
+        }
+    };
+    *PATTERN.lock().unwrap_or_else(|e| e.into_inner()) = regex;
+    clear();
+}
+
+/// Forgets the endpoint, e.g. when the backend exits
+pub fn clear() {
+    *ENDPOINT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    FOUND.store(false, Ordering::SeqCst);
+}
+
+/// Address discovered for the current backend, if it printed one
+pub fn current() -> Option<SocketAddr> {
+    *ENDPOINT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Called by the readers for every backend line until the endpoint is found
+pub fn scan<R: tauri::Runtime>(app: &tauri::AppHandle<R>, line: &str) {
+    if FOUND.load(Ordering::SeqCst) {
+        return;
+    }
+    let address = {
+        let pattern = PATTERN.lock().unwrap_or_else(|e| e.into_inner());
+        match pattern.as_ref().and_then(|p| match_endpoint(p, line)) {
+            Some(address) => address,
+            None => return,
+        }
+    };
+    if FOUND.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    *ENDPOINT.lock().unwrap_or_else(|e| e.into_inner()) = Some(address);
+    let _ = app.emit("python-endpoint-discovered", EndpointDiscovered { address });
+    // The announced endpoint doubles as the backend's ready marker
+    start_timings::ready(app);
+}
+
+/// Extracts the first capture group of `pattern` from `line` as a socket address;
+/// host names (e.g. `localhost:8765`) are resolved
+fn match_endpoint(pattern: &Regex, line: &str) -> Option<SocketAddr> {
+    let text = pattern.captures(logs::normalize_line(line))?.get(1)?.as_str();
+    text.parse().ok().or_else(|| text.to_socket_addrs().ok()?.next())
+}
+
+fn warn<R: tauri::Runtime>(app: &tauri::AppHandle<R>, message: &str) {
+    logs::forward_tool_line(app, "endpoint", Stream::Stderr, &format!("WARNING: {}", message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_listening_address() {
+        let pattern = Regex::new(crate::config::ViewerConfig::default().endpoint_pattern.as_str()).unwrap();
+        assert_eq!(match_endpoint(&pattern, "LISTENING on 127.0.0.1:8765\r"), Some("127.0.0.1:8765".parse().unwrap()));
+        assert_eq!(match_endpoint(&pattern, "INFO LISTENING on [::1]:9000"), Some("[::1]:9000".parse().unwrap()));
+        assert_eq!(match_endpoint(&pattern, "LISTENING on nowhere"), None);
+        assert_eq!(match_endpoint(&pattern, "connected to 127.0.0.1:8765"), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Python and pip environment variables that, set globally in the user's shell profile or
+//! by a launcher, make the backend behave differently from a clean shell: a foreign stdlib
+//! (PYTHONHOME), shadowed venv packages (PYTHONPATH), an interactive prompt after exit
+//! (PYTHONINSPECT) and the like. check_env_conflicts reports them; with
+//! `neutralize_python_env` (config, on by default) a start removes the ones marked
+//! `neutralize` from the backend's environment, in every launch mode. resolve_command
+//! already removes PYTHONHOME, PYTHONEXECUTABLE and PYTHONUSERBASE and sets or clears
+//! PYTHONPATH unless an explicit interpreter is used.
+
+use std::process::Command;
+
+/// An interfering variable found in the viewer's environment
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct EnvConflict {
+    pub name: &'static str,
+    pub value: String,
+    pub problem: &'static str,
+    pub recommendation: &'static str,
+    /// Whether a start with the current settings keeps it from the backend
+    pub neutralized: bool,
+}
+
+/// What resolve_command removes or replaces unless an explicit interpreter is used
+const LAUNCH_CLEARED: &[&str] = &["PYTHONHOME", "PYTHONPATH", "PYTHONEXECUTABLE", "PYTHONUSERBASE"];
+
+struct Known {
+    name: &'static str,
+    problem: &'static str,
+    recommendation: &'static str,
+    /// Removed from the backend's environment by neutralize
+    neutralize: bool,
+}
+
+const KNOWN: &[Known] = &[
+    Known {
+        name: "PYTHONHOME",
+        problem: "points the interpreter at another installation's standard library; a venv python then fails with \"No module named 'encodings'\"",
+        recommendation: "unset it; it is only needed for embedded or relocated interpreters",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONPATH",
+        problem: "puts directories ahead of the venv's site-packages, so other copies of numpy, televoodoo etc. can be imported",
+        recommendation: "unset it globally and set it per project, or in the env_file for an explicit interpreter",
+        neutralize: false,
+    },
+    Known {
+        name: "PYTHONSTARTUP",
+        problem: "runs a script in every interactive interpreter, including python -i diagnostics of the venv",
+        recommendation: "keep it, if needed, in your shell's interactive setup only",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONINSPECT",
+        problem: "drops the backend into an interactive prompt when it exits instead of exiting, so it looks hung",
+        recommendation: "unset it",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONEXECUTABLE",
+        problem: "overrides sys.executable (macOS), so subprocesses and pip may run a different interpreter",
+        recommendation: "unset it",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONUSERBASE",
+        problem: "moves the user site dir, whose packages a system interpreter imports ahead of its own",
+        recommendation: "unset it, or set PYTHONNOUSERSITE=1 for the viewer",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONPLATLIBDIR",
+        problem: "changes where the interpreter looks for its platform libraries (e.g. lib64), which breaks a venv built without it",
+        recommendation: "unset it unless your distribution requires it",
+        neutralize: true,
+    },
+    Known {
+        name: "PYTHONOPTIMIZE",
+        problem: "strips asserts (and docstrings at level 2), which some libraries rely on",
+        recommendation: "unset it, or set it in the env_file if you want it only for the backend",
+        neutralize: false,
+    },
+    Known {
+        name: "PYTHONWARNINGS",
+        problem: "can turn warnings into errors (e.g. \"error\"), making deprecations in dependencies fatal",
+        recommendation: "set it in the env_file if you want it only for the backend",
+        neutralize: false,
+    },
+    Known {
+        name: "PIP_USER",
+        problem: "makes pip install into the user site dir, which fails inside the runtime venv",
+        recommendation: "unset it, or use `pip install --user` explicitly where wanted",
+        neutralize: false,
+    },
+    Known {
+        name: "PIP_TARGET",
+        problem: "makes pip install into another directory instead of the runtime venv",
+        recommendation: "unset it",
+        neutralize: false,
+    },
+    Known {
+        name: "PIP_PREFIX",
+        problem: "makes pip install under another prefix instead of the runtime venv",
+        recommendation: "unset it",
+        neutralize: false,
+    },
+];
+
+/// Known interfering variables among `vars`. `neutralizing` is the neutralize_python_env
+/// setting and `explicit_interpreter` whether starts use StartConfig.interpreter.
+pub fn check(vars: impl Iterator<Item = (String, String)>, neutralizing: bool, explicit_interpreter: bool) -> Vec<EnvConflict> {
+    let vars: Vec<(String, String)> = vars.collect();
+    KNOWN
+        .iter()
+        .filter_map(|known| {
+            let (_, value) = vars.iter().find(|(name, value)| name == known.name && !value.is_empty())?;
+            let neutralized = (neutralizing && known.neutralize) || (!explicit_interpreter && LAUNCH_CLEARED.contains(&known.name));
+            Some(EnvConflict { name: known.name, value: value.clone(), problem: known.problem, recommendation: known.recommendation, neutralized })
+        })
+        .collect()
+}
+
+/// Removes the neutralizable variables from `cmd`'s environment
+pub fn neutralize(cmd: &mut Command) {
+    for known in KNOWN.iter().filter(|known| known.neutralize) {
+        cmd.env_remove(known.name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_known_variables() {
+        let vars = [("PYTHONHOME", "/opt/py"), ("PYTHONPATH", "/src"), ("PIP_USER", "1"), ("PYTHONINSPECT", ""), ("HOME", "/home/op")]
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        let neutralized = |neutralizing, explicit| -> Vec<(&str, bool)> {
+            check(vars.clone().into_iter(), neutralizing, explicit).iter().map(|c| (c.name, c.neutralized)).collect()
+        };
+        assert_eq!(neutralized(true, false), vec![("PYTHONHOME", true), ("PYTHONPATH", true), ("PIP_USER", false)]);
+        assert_eq!(neutralized(true, true), vec![("PYTHONHOME", true), ("PYTHONPATH", false), ("PIP_USER", false)]);
+        assert_eq!(neutralized(false, true), vec![("PYTHONHOME", false), ("PYTHONPATH", false), ("PIP_USER", false)]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Snapshots of the backend environment (installed packages, versions, viewer config)
+//! kept under `<app data>/snapshots`, and diffs between two of them, for tracking down
+//! the dependency drift behind "it worked yesterday".
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::{bootstrap, config};
+
+/// Number of snapshots kept; older ones are deleted when a new one is taken
+const SNAPSHOT_LIMIT: usize = 10;
+
+const PREFIX: &str = "snapshot-";
+
+/// Stored as `<id>.json`
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Snapshot {
+    created_at_ms: u64,
+    viewer_version: String,
+    os: String,
+    arch: String,
+    python: PathBuf,
+    python_version: Option<String>,
+    /// Distribution name -> version (or `@ <url>` for direct references), from pip freeze
+    packages: BTreeMap<String, String>,
+    /// Viewer config, minus the `last_*` bookkeeping fields
+    config: BTreeMap<String, Value>,
+}
+
+/// One stored snapshot, returned by list_environment_snapshots
+#[derive(Clone, serde::Serialize)]
+pub struct SnapshotInfo {
+    /// Identifier accepted by diff_environment
+    pub id: String,
+    pub created_at_ms: u64,
+}
+
+/// One difference between two snapshots. `old` is None for additions, `new` for removals.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Change {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// What changed from snapshot `a` to snapshot `b`, returned by diff_environment
+#[derive(Clone, serde::Serialize)]
+pub struct EnvDiff {
+    /// Viewer, OS, arch, interpreter and Python version changes
+    pub versions: Vec<Change>,
+    pub packages: Vec<Change>,
+    /// Config values, rendered as JSON
+    pub config: Vec<Change>,
+}
+
+fn snapshots_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    bootstrap::data_dir(app)
+        .map(|d| d.join("snapshots"))
+        .ok_or_else(|| "Could not determine app data directory".to_string())
+}
+
+/// Records the environment of `python` and prunes old snapshots
+pub fn take<R: tauri::Runtime>(app: &tauri::AppHandle<R>, python: &Path) -> Result<SnapshotInfo, String> {
+    let freeze = Command::new(python)
+        .args(["-m", "pip", "freeze", "--all"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run pip freeze with {}: {}", python.display(), e))?;
+    if !freeze.status.success() {
+        return Err(format!("pip freeze failed with {}", freeze.status));
+    }
+    let python_version = Command::new(python)
+        .args(["-c", "import platform; print(platform.python_version())"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|v| !v.is_empty());
+    let config = match serde_json::to_value(config::get(app)) {
+        Ok(Value::Object(map)) => map.into_iter().filter(|(k, _)| !k.starts_with("last_")).collect(),
+        _ => BTreeMap::new(),
+    };
+    let created_at_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let snapshot = Snapshot {
+        created_at_ms,
+        viewer_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        python: python.to_path_buf(),
+        python_version,
+        packages: parse_freeze(&String::from_utf8_lossy(&freeze.stdout)),
+        config,
+    };
+
+    let dir = snapshots_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let id = format!("{}{}", PREFIX, created_at_ms);
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.json", id));
+    std::fs::write(&path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    for old in list(app).into_iter().skip(SNAPSHOT_LIMIT) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", old.id)));
+    }
+    Ok(SnapshotInfo { id, created_at_ms })
+}
+
+/// Stored snapshots, newest first
+pub fn list<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<SnapshotInfo> {
+    let Ok(dir) = snapshots_dir(app) else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return Vec::new() };
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().strip_suffix(".json")?.to_string();
+            let created_at_ms = id.strip_prefix(PREFIX)?.parse().ok()?;
+            Some(SnapshotInfo { id, created_at_ms })
+        })
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at_ms));
+    snapshots
+}
+
+fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>, id: &str) -> Result<Snapshot, String> {
+    // Ids come from the UI; only accept the names take() generates
+    if !id.strip_prefix(PREFIX).is_some_and(|ms| !ms.is_empty() && ms.chars().all(|c| c.is_ascii_digit())) {
+        return Err(format!("Invalid snapshot id: {:?}", id));
+    }
+    let path = snapshots_dir(app)?.join(format!("{}.json", id));
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Could not read snapshot {}: {}", id, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Snapshot {} is corrupt: {}", id, e))
+}
+
+/// Differences from snapshot `a` to snapshot `b`
+pub fn diff<R: tauri::Runtime>(app: &tauri::AppHandle<R>, a: &str, b: &str) -> Result<EnvDiff, String> {
+    let (a, b) = (load(app, a)?, load(app, b)?);
+    let versions = |s: &Snapshot| {
+        BTreeMap::from([
+            ("viewer".to_string(), s.viewer_version.clone()),
+            ("os".to_string(), s.os.clone()),
+            ("arch".to_string(), s.arch.clone()),
+            ("python".to_string(), s.python.display().to_string()),
+            ("python_version".to_string(), s.python_version.clone().unwrap_or_default()),
+        ])
+    };
+    let config = |s: &Snapshot| s.config.iter().map(|(k, v)| (k.clone(), v.to_string())).collect();
+    Ok(EnvDiff {
+        versions: diff_maps(&versions(&a), &versions(&b)),
+        packages: diff_maps(&a.packages, &b.packages),
+        config: diff_maps(&config(&a), &config(&b)),
+    })
+}
+
+/// Keys added, removed or changed from `old` to `new`, in key order
+fn diff_maps(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Vec<Change> {
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| Change { name: name.clone(), old: old.get(name).cloned(), new: new.get(name).cloned() })
+        .collect()
+}
+
+/// Parses `pip freeze` output into name -> version. Names are normalized (PEP 503) so a
+/// changed spelling isn't reported as a removal plus an addition; editable (`-e`) and
+/// option lines are skipped.
+fn parse_freeze(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['-', '#']))
+        .filter_map(|line| {
+            let (name, version) = match line.split_once("==") {
+                Some((name, version)) => (name, version.to_string()),
+                None => line.split_once(" @ ").map(|(name, url)| (name, format!("@ {}", url)))?,
+            };
+            let name = name.trim().to_ascii_lowercase().replace(['_', '.'], "-");
+            Some((name, version.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_pip_freeze_output() {
+        let old = parse_freeze("numpy==1.26.4\nPyObjC_Core==10.1\nrich==13.0.0\n-e git+https://x/y.git#egg=z\n");
+        let new = parse_freeze("numpy==2.0.0\npyobjc-core==10.1\nopencv @ file:///wheels/opencv.whl\n");
+        let change = |name: &str, old: Option<&str>, new: Option<&str>| Change {
+            name: name.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+        };
+        assert_eq!(
+            diff_maps(&old, &new),
+            vec![
+                change("numpy", Some("1.26.4"), Some("2.0.0")),
+                change("opencv", None, Some("@ file:///wheels/opencv.whl")),
+                change("rich", Some("13.0.0"), None),
+            ]
+        );
+    }
+}
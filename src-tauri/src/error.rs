@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+/// Errors surfaced to the UI by backend lifecycle commands.
+///
+/// Serialized as `{ kind, message }` so the frontend can branch on `kind` and show
+/// `message` as-is.
+#[derive(Debug, thiserror::Error)]
+pub enum PythonError {
+    #[error("Start cancelled")]
+    Cancelled,
+    #[error("App data directory is read-only: {path}. Set TELEVOODOO_VIEWER_DATA_DIR to a writable location.")]
+    DataDirReadOnly { path: PathBuf },
+    #[error("Not enough free disk space for the Python environment: {available} bytes available, {required} required")]
+    InsufficientDiskSpace { available: u64, required: u64 },
+    #[error("Python interpreter not found: {path}")]
+    InterpreterNotFound { path: PathBuf },
+    #[error("Python interpreter is not executable (permission denied): {path}")]
+    InterpreterNotExecutable { path: PathBuf },
+    #[error("Python interpreter is not a valid executable for this system: {path}")]
+    InterpreterInvalidFormat { path: PathBuf },
+    #[error("Backend working directory does not exist: {path}")]
+    WorkingDirNotFound { path: PathBuf },
+    #[error("Another viewer instance is using the runtime python environment (lock held on {path}). Close it first.")]
+    AnotherInstanceActive { path: PathBuf },
+    #[error("System resources exhausted while starting {path}: {message}")]
+    ResourcesExhausted { path: PathBuf, message: String },
+    #[error("Backend output pipe setup failed: no {missing} pipe after spawn")]
+    PipeSetupFailed { missing: String },
+    #[error("The {hook} hook failed: {message}")]
+    HookFailed { hook: String, message: String },
+    #[error("Failed to start {path}: {message}")]
+    SpawnFailed { path: PathBuf, message: String },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl PythonError {
+    /// Stable identifier for the UI
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PythonError::Cancelled => "cancelled",
+            PythonError::DataDirReadOnly { .. } => "data_dir_read_only",
+            PythonError::InsufficientDiskSpace { .. } => "insufficient_disk_space",
+            PythonError::InterpreterNotFound { .. } => "interpreter_not_found",
+            PythonError::InterpreterNotExecutable { .. } => "interpreter_not_executable",
+            PythonError::InterpreterInvalidFormat { .. } => "interpreter_invalid_format",
+            PythonError::WorkingDirNotFound { .. } => "working_dir_not_found",
+            PythonError::AnotherInstanceActive { .. } => "another_instance_active",
+            PythonError::ResourcesExhausted { .. } => "resources_exhausted",
+            PythonError::PipeSetupFailed { .. } => "pipe_setup_failed",
+            PythonError::HookFailed { .. } => "hook_failed",
+            PythonError::SpawnFailed { .. } => "spawn_failed",
+            PythonError::Other(_) => "other",
+        }
+    }
+
+    /// Maps an `io::Error` from `Command::spawn` to a friendlier variant. `program` is the
+    /// interpreter that was attempted and `cwd` the working directory, if one was set.
+    pub fn from_spawn_error(program: &Path, cwd: Option<&Path>, err: std::io::Error) -> Self {
+        let path = program.to_path_buf();
+        // A missing cwd also surfaces as ENOENT; blame the right path
+        if let Some(cwd) = cwd.filter(|d| !d.is_dir()) {
+            return PythonError::WorkingDirNotFound { path: cwd.to_path_buf() };
+        }
+        match err.kind() {
+            std::io::ErrorKind::NotFound => return PythonError::InterpreterNotFound { path },
+            std::io::ErrorKind::PermissionDenied => return PythonError::InterpreterNotExecutable { path },
+            _ => {}
+        }
+        #[cfg(unix)]
+        match err.raw_os_error() {
+            Some(libc::ENOEXEC) => return PythonError::InterpreterInvalidFormat { path },
+            Some(libc::EAGAIN) | Some(libc::ENOMEM) => {
+                return PythonError::ResourcesExhausted { path, message: err.to_string() }
+            }
+            _ => {}
+        }
+        #[cfg(windows)]
+        match err.raw_os_error() {
+            // ERROR_BAD_EXE_FORMAT
+            Some(193) => return PythonError::InterpreterInvalidFormat { path },
+            // ERROR_NOT_ENOUGH_MEMORY, ERROR_OUTOFMEMORY, ERROR_NO_SYSTEM_RESOURCES
+            Some(8) | Some(14) | Some(1450) => {
+                return PythonError::ResourcesExhausted { path, message: err.to_string() }
+            }
+            _ => {}
+        }
+        PythonError::SpawnFailed { path, message: err.to_string() }
+    }
+}
+
+impl From<String> for PythonError {
+    fn from(message: String) -> Self {
+        PythonError::Other(message)
+    }
+}
+
+impl serde::Serialize for PythonError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("PythonError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
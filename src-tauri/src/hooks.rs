@@ -0,0 +1,112 @@
+//! User-configured commands run around the backend's lifetime. Hooks are argv lists and
+//! are never passed through a shell; their output goes through the log pipeline tagged
+//! with the hook name.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error::PythonError;
+use crate::logs::{self, Stream};
+
+// Set when a backend was spawned, cleared once its post-exit hook ran
+static POST_EXIT_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Cap on the post-exit hook while the app quits, so closing the window isn't held up
+const QUIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runs the `pre_launch` hook, if configured. A hook that can't be run or exits non-zero
+/// fails the start.
+pub fn run_pre_launch<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), PythonError> {
+    match config::get(app).pre_launch {
+        Some(argv) if !argv.is_empty() => run(app, "pre-launch", &argv),
+        _ => Ok(()),
+    }
+}
+
+fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>, hook: &str, argv: &[String]) -> Result<(), PythonError> {
+    let failed = |message: String| PythonError::HookFailed { hook: hook.to_string(), message };
+    let mut cmd = Command::new(&argv[0]);
+    cmd.args(&argv[1..]).stdin(Stdio::null());
+    let status = logs::run_streamed(app, &mut cmd, hook).map_err(|e| failed(format!("could not run {}: {}", argv[0], e)))?;
+    if !status.success() {
+        return Err(failed(format!("{} exited with {}", argv[0], status)));
+    }
+    Ok(())
+}
+
+/// Records that a backend was spawned, so its exit triggers the post-exit hook
+pub fn backend_spawned() {
+    POST_EXIT_PENDING.store(true, Ordering::SeqCst);
+}
+
+/// Runs the `post_exit` hook once per spawned backend, whichever exit path (crash, stop,
+/// restart, app close) gets here first. The hook is killed after `post_exit_timeout_ms`;
+/// failures are logged, since the backend is already gone.
+pub fn run_post_exit<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    post_exit(app, None);
+}
+
+/// run_post_exit for app close: the hook gets at most QUIT_TIMEOUT, as the quit waits for it
+pub fn run_post_exit_on_quit<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    post_exit(app, Some(QUIT_TIMEOUT));
+}
+
+fn post_exit<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cap: Option<Duration>) {
+    if !POST_EXIT_PENDING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let config = config::get(app);
+    let Some(argv) = config.post_exit.filter(|a| !a.is_empty()) else { return };
+    let timeout = Duration::from_millis(config.post_exit_timeout_ms);
+    let timeout = cap.map_or(timeout, |cap| timeout.min(cap));
+    if let Err(e) = run_with_timeout(app, "post-exit", &argv, timeout) {
+        logs::forward_tool_line(app, "post-exit", Stream::Stderr, &format!("WARNING: {}", e));
+    }
+}
+
+fn run_with_timeout<R: tauri::Runtime>(app: &tauri::AppHandle<R>, hook: &str, argv: &[String], timeout: Duration) -> Result<(), PythonError> {
+    let failed = |message: String| PythonError::HookFailed { hook: hook.to_string(), message };
+    let mut child = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(format!("could not run {}: {}", argv[0], e)))?;
+    // Readers are not joined: a grandchild may keep the pipes open past the timeout
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(app, hook, Stream::Stdout, stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(app, hook, Stream::Stderr, stderr);
+    }
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(failed(format!("{} did not finish within {} ms; killed it", argv[0], timeout.as_millis())));
+            }
+            Err(e) => return Err(failed(e.to_string())),
+        }
+    };
+    if !status.success() {
+        return Err(failed(format!("{} exited with {}", argv[0], status)));
+    }
+    Ok(())
+}
+
+fn forward_lines<R: tauri::Runtime>(app: &tauri::AppHandle<R>, hook: &str, stream: Stream, pipe: impl Read + Send + 'static) {
+    let (app, hook) = (app.clone(), hook.to_string());
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            logs::forward_tool_line(&app, &hook, stream, &line);
+        }
+    });
+}
@@ -0,0 +1,100 @@
+//! Dev-build hot reload: restarts the backend when the televoodoo source in the repo
+//! changes (`hot_reload` config). The tree is polled for `.py` modification times rather
+//! than watched through OS notifications; a source checkout is small enough for that to
+//! be cheap, and it behaves the same on every platform and filesystem.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config;
+
+/// Interval between scans of the source tree
+const POLL: Duration = Duration::from_millis(500);
+
+/// A change is acted on once the tree has been quiet this long, so saving several files
+/// (or an editor's write-and-rename) restarts once
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Modification time of every `.py` file under `dir`, skipping `__pycache__` and hidden
+/// directories
+fn scan(dir: &Path, files: &mut BTreeMap<PathBuf, SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if !name.starts_with('.') && name != "__pycache__" {
+                scan(&path, files);
+            }
+        } else if name.ends_with(".py") {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                files.insert(path, modified);
+            }
+        }
+    }
+}
+
+/// Files added, removed or modified from `old` to `new`
+fn changed(old: &BTreeMap<PathBuf, SystemTime>, new: &BTreeMap<PathBuf, SystemTime>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = new.iter().filter(|(path, time)| old.get(*path) != Some(time)).map(|(path, _)| path.clone()).collect();
+    paths.extend(old.keys().filter(|path| !new.contains_key(*path)).cloned());
+    paths.sort();
+    paths
+}
+
+/// Polls `dir` while `hot_reload` is enabled and calls `on_change` with the changed files
+/// once they settle
+pub fn watch<R: tauri::Runtime>(app: tauri::AppHandle<R>, dir: PathBuf, on_change: impl Fn(&tauri::AppHandle<R>, Vec<PathBuf>) + Send + 'static) {
+    std::thread::spawn(move || {
+        // None while disabled, so enabling it doesn't report the whole tree as changed
+        let mut baseline: Option<BTreeMap<PathBuf, SystemTime>> = None;
+        loop {
+            std::thread::sleep(POLL);
+            if !config::get(&app).hot_reload {
+                baseline = None;
+                continue;
+            }
+            let mut current = BTreeMap::new();
+            scan(&dir, &mut current);
+            let Some(old) = baseline.as_ref() else {
+                baseline = Some(current);
+                continue;
+            };
+            if changed(old, &current).is_empty() {
+                continue;
+            }
+            // Wait until a scan comes back identical to the previous one
+            loop {
+                std::thread::sleep(DEBOUNCE);
+                let mut settled = BTreeMap::new();
+                scan(&dir, &mut settled);
+                let quiet = settled == current;
+                current = settled;
+                if quiet {
+                    break;
+                }
+            }
+            let files = changed(old, &current);
+            baseline = Some(current);
+            if !files.is_empty() {
+                on_change(&app, files);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_added_removed_and_modified_files() {
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let old = BTreeMap::from([(PathBuf::from("a.py"), t(1)), (PathBuf::from("b.py"), t(1)), (PathBuf::from("c.py"), t(1))]);
+        let new = BTreeMap::from([(PathBuf::from("a.py"), t(1)), (PathBuf::from("b.py"), t(2)), (PathBuf::from("d.py"), t(1))]);
+        assert_eq!(changed(&old, &new), vec![PathBuf::from("b.py"), PathBuf::from("c.py"), PathBuf::from("d.py")]);
+    }
+}
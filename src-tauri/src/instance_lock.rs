@@ -0,0 +1,50 @@
+//! Advisory lock on the runtime python dir so two viewer instances never bootstrap or run
+//! the same venv at once. The OS drops the lock when the holding process dies, so a crashed
+//! instance never leaves a stale lock behind.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::sync::Mutex;
+
+use crate::bootstrap;
+use crate::error::PythonError;
+
+pub const LOCK_FILE: &str = ".lock";
+
+// Held from the first start until stop/exit
+static LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Takes the lock unless this instance already holds it
+pub fn acquire<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), PythonError> {
+    let mut held = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if held.is_some() {
+        return Ok(());
+    }
+    let Some(dir) = bootstrap::runtime_python_dir(app) else { return Ok(()) };
+    bootstrap::ensure_writable(&dir)?;
+    let path = dir.join(LOCK_FILE);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    match file.try_lock() {
+        Ok(()) => {
+            *held = Some(file);
+            Ok(())
+        }
+        Err(TryLockError::WouldBlock) => Err(PythonError::AnotherInstanceActive { path }),
+        Err(TryLockError::Error(e)) => Err(format!("Could not lock {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Whether this instance holds the lock
+pub fn held() -> bool {
+    LOCK.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Releases the lock if held
+pub fn release() {
+    // Closing the file drops the lock
+    LOCK.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
@@ -0,0 +1,63 @@
+//! What start_python would do with a given config, computed by the same resolution code
+//! with every side effect (bootstrap, package update, pip, spawn) left out.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A step of the start, in the order it would run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchStep {
+    /// A detached backend from an earlier run is adopted; nothing else runs
+    Reattach,
+    /// The runtime venv is (re)built from the bundle
+    Bootstrap,
+    /// The bundled televoodoo is reinstalled into the runtime venv (auto-update)
+    PackageUpdate,
+    /// The `pre_launch` hook
+    PreLaunch,
+    /// cpu_quota/mem_limit are applied around the backend
+    ResourceLimits,
+    Spawn,
+}
+
+/// An environment variable the viewer sets (or removes, `value: None`) for the backend
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EnvOverride {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// Returned by preview_launch
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct LaunchPlan {
+    /// Runs from the repo checkout rather than the packaged layout
+    pub dev: bool,
+    pub interpreter: PathBuf,
+    /// Arguments after the interpreter
+    pub args: Vec<String>,
+    /// None inherits the viewer's working dir
+    pub cwd: Option<PathBuf>,
+    /// Only what differs from the viewer's own environment
+    pub env: Vec<EnvOverride>,
+    pub bootstrap: bool,
+    pub package_update: bool,
+    pub reattach: bool,
+    pub steps: Vec<LaunchStep>,
+}
+
+impl LaunchPlan {
+    /// Records the interpreter, argv, working dir and environment overrides of `cmd`
+    pub fn describe(&mut self, cmd: &Command) {
+        self.interpreter = PathBuf::from(cmd.get_program());
+        self.args = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        self.cwd = cmd.get_current_dir().map(PathBuf::from);
+        self.env = cmd
+            .get_envs()
+            .map(|(key, value)| EnvOverride {
+                name: key.to_string_lossy().to_string(),
+                value: value.map(|v| v.to_string_lossy().to_string()),
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::bootstrap;
+
+#[cfg(unix)]
+const FILE_NAME: &str = "launch-backend.sh";
+#[cfg(not(unix))]
+const FILE_NAME: &str = "launch-backend.cmd";
+
+/// Renders `cmd` as a script and writes it to the app data dir, returning its path
+pub fn write<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: &Command) -> Result<PathBuf, String> {
+    let dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(FILE_NAME);
+    std::fs::write(&path, render(cmd)).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755));
+    }
+    Ok(path)
+}
+
+/// Full environment the child would see: ours, with the command's overrides applied
+fn effective_env(cmd: &Command) -> BTreeMap<OsString, OsString> {
+    let mut env: BTreeMap<OsString, OsString> = std::env::vars_os().collect();
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => env.insert(key.to_os_string(), value.to_os_string()),
+            None => env.remove(key),
+        };
+    }
+    env
+}
+
+#[cfg(unix)]
+fn render(cmd: &Command) -> String {
+    let mut script = String::from("#!/bin/sh\n");
+    script.push_str("# Launches the televoodoo backend the way the viewer does.\n");
+    script.push_str("# Contains a full copy of the viewer's environment; review before sharing.\n\n");
+    for (key, value) in effective_env(cmd) {
+        let key = key.to_string_lossy();
+        // Variables the shell can't name (e.g. `ProgramFiles(x86)`) are skipped
+        if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !key.starts_with(|c: char| c.is_ascii_digit()) {
+            script.push_str(&format!("export {}={}\n", key, sh_quote(&value.to_string_lossy())));
+        }
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        script.push_str(&format!("\ncd {} || exit 1\n", sh_quote(&dir.to_string_lossy())));
+    }
+    script.push_str(&format!("exec {}", sh_quote(&cmd.get_program().to_string_lossy())));
+    for arg in cmd.get_args() {
+        script.push(' ');
+        script.push_str(&sh_quote(&arg.to_string_lossy()));
+    }
+    script.push('\n');
+    script
+}
+
+/// Single-quotes `s` for POSIX sh
+#[cfg(unix)]
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(not(unix))]
+fn render(cmd: &Command) -> String {
+    let mut script = String::from("@echo off\r\n");
+    script.push_str("rem Launches the televoodoo backend the way the viewer does.\r\n");
+    script.push_str("rem Contains a full copy of the viewer's environment; review before sharing.\r\n\r\n");
+    for (key, value) in effective_env(cmd) {
+        script.push_str(&format!("set \"{}={}\"\r\n", key.to_string_lossy(), cmd_escape(&value.to_string_lossy())));
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        script.push_str(&format!("\r\ncd /d \"{}\" || exit /b 1\r\n", dir.display()));
+    }
+    script.push_str(&format!("\"{}\"", cmd.get_program().to_string_lossy()));
+    for arg in cmd.get_args() {
+        script.push_str(&format!(" \"{}\"", cmd_escape(&arg.to_string_lossy())));
+    }
+    script.push_str("\r\n");
+    script
+}
+
+// `%` would otherwise expand as a variable reference inside the batch file
+#[cfg(not(unix))]
+fn cmd_escape(s: &str) -> String {
+    s.replace('%', "%%")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_cwd_env_and_quoted_args() {
+        let mut cmd = Command::new("/opt/py/bin/python");
+        cmd.args(["-m", "televoodoo", "--name", "it's me"])
+            .current_dir("/tmp/tv dir")
+            .env("PYTHONUNBUFFERED", "1")
+            .env_remove("PYTHONHOME");
+        let script = render(&cmd);
+        assert!(script.contains("export PYTHONUNBUFFERED='1'\n"));
+        assert!(!script.contains("export PYTHONHOME="));
+        assert!(script.contains("cd '/tmp/tv dir' || exit 1\n"));
+        assert!(script.ends_with("exec '/opt/py/bin/python' '-m' 'televoodoo' '--name' 'it'\\''s me'\n"));
+    }
+}
@@ -0,0 +1,175 @@
+//! The backend log as a local server-sent-events stream, for dashboards and other tools
+//! that tail it without the UI. `GET /log` (or `/`) answers `text/event-stream` with one
+//! `data:` event per line, carrying the `python-log` payload as JSON.
+//!
+//! The server binds to 127.0.0.1 only and has no TLS. Each enable generates a random
+//! token that clients must send as `?token=` or `Authorization: Bearer`; it keeps out
+//! other local users' processes and web pages, but any process that learns the token can
+//! read the log.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::logs::LogLine;
+use crate::token;
+
+/// Lines buffered per client; a client that falls further behind misses lines
+const CLIENT_BUFFER: usize = 1024;
+
+/// Comment sent to idle clients so proxies and dead peers are noticed
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Returned by enable_log_http
+#[derive(Clone, serde::Serialize)]
+pub struct LogHttpInfo {
+    pub port: u16,
+    pub token: String,
+    /// Ready-to-use stream URL including the token
+    pub url: String,
+}
+
+struct Server {
+    info: LogHttpInfo,
+    clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>,
+}
+
+static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+// Bumped by disable so the accept loop of a previous server ends
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+// Connected clients, so broadcast skips serializing when nobody listens
+static CLIENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts the server on `port` (0 picks a free one), replacing a running one
+pub fn enable(port: u16) -> Result<LogHttpInfo, String> {
+    disable();
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(|e| format!("Could not listen on 127.0.0.1:{}: {}", port, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let token = token::random()?;
+    let info = LogHttpInfo { port, url: format!("http://127.0.0.1:{}/log?token={}", port, token), token: token.clone() };
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Server { info: info.clone(), clients: clients.clone() });
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    std::thread::spawn(move || {
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let (clients, token) = (clients.clone(), token.clone());
+                    std::thread::spawn(move || serve(stream, &token, &clients));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(100)),
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+    });
+    Ok(info)
+}
+
+/// Stops the server and disconnects all clients
+pub fn disable() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(server) = SERVER.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        // Dropping the senders ends each client's stream
+        server.clients.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+pub fn status() -> Option<LogHttpInfo> {
+    SERVER.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|s| s.info.clone())
+}
+
+/// Sends `line` to every connected client
+pub fn broadcast(line: &LogLine) {
+    if CLIENT_COUNT.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let Ok(json) = serde_json::to_string(line) else { return };
+    let json: Arc<str> = json.into();
+    let guard = SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(server) = guard.as_ref() else { return };
+    server.clients.lock().unwrap_or_else(|e| e.into_inner()).retain(|client| match client.try_send(json.clone()) {
+        Ok(()) | Err(TrySendError::Full(_)) => true,
+        Err(TrySendError::Disconnected(_)) => false,
+    });
+}
+
+/// Whether the request line and headers carry `token`
+fn authorized(request: &[String], token: &str) -> bool {
+    let Some(target) = request.first().and_then(|line| line.strip_prefix("GET ")).and_then(|rest| rest.split(' ').next()) else {
+        return false;
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/log" && path != "/" {
+        return false;
+    }
+    let in_query = query.split('&').any(|pair| pair.strip_prefix("token=").is_some_and(|given| token::matches(given, token)));
+    let in_header = request.iter().skip(1).any(|header| {
+        header.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("authorization") && value.trim().strip_prefix("Bearer ").is_some_and(|given| token::matches(given, token))
+        })
+    });
+    in_query || in_header
+}
+
+fn serve(stream: TcpStream, token: &str, clients: &Mutex<Vec<SyncSender<Arc<str>>>>) {
+    // Accepted sockets may inherit the listener's non-blocking mode
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut request = Vec::new();
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+    // Request line and headers, capped so a client can't make us buffer forever
+    for _ in 0..100 {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(n) if n > 0 && line.len() <= 8192 && !line.trim().is_empty() => request.push(line.trim_end().to_string()),
+            _ => break,
+        }
+    }
+    let mut stream = stream;
+    if !authorized(&request, token) {
+        let _ = stream.write_all(b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return;
+    }
+    let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    if stream.write_all(headers.as_bytes()).is_err() {
+        return;
+    }
+    let (tx, rx) = mpsc::sync_channel(CLIENT_BUFFER);
+    clients.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+    CLIENT_COUNT.fetch_add(1, Ordering::Relaxed);
+    loop {
+        let frame = match rx.recv_timeout(KEEPALIVE) {
+            Ok(json) => format!("data: {}\n\n", json),
+            Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if stream.write_all(frame.as_bytes()).and_then(|()| stream.flush()).is_err() {
+            break;
+        }
+    }
+    CLIENT_COUNT.fetch_sub(1, Ordering::Relaxed);
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_the_token_on_the_log_path() {
+        let request = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert!(authorized(&request(&["GET /log?token=abc HTTP/1.1"]), "abc"));
+        assert!(authorized(&request(&["GET / HTTP/1.1", "authorization: Bearer abc"]), "abc"));
+        assert!(!authorized(&request(&["GET /log?token=abcd HTTP/1.1"]), "abc"));
+        assert!(!authorized(&request(&["GET /other?token=abc HTTP/1.1"]), "abc"));
+        assert!(!authorized(&request(&["POST /log?token=abc HTTP/1.1"]), "abc"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Rotation and retention of the shared backend log. `backend.log` is renamed to
+//! `backend-<unix ms>.log` once it reaches ROTATE_BYTES; rotated files beyond the
+//! `log_max_files` newest, or older than `log_max_age_days`, are deleted after each
+//! rotation and at startup. The active file is never deleted.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::{Emitter, Manager};
+
+use crate::config;
+
+pub const ACTIVE_FILE: &str = "backend.log";
+
+/// Size at which the active file is rotated
+pub const ROTATE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Accepted by set_log_retention
+pub const MAX_FILES_RANGE: std::ops::RangeInclusive<u32> = 1..=1000;
+pub const MAX_AGE_DAYS_RANGE: std::ops::RangeInclusive<u32> = 0..=3650;
+
+/// Payload of `python-logs-pruned`
+#[derive(Clone, serde::Serialize)]
+struct LogsPruned {
+    pruned: usize,
+}
+
+pub fn log_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    app.path().app_log_dir().ok()
+}
+
+fn is_rotated(name: &str) -> bool {
+    name.strip_prefix("backend-").and_then(|rest| rest.strip_suffix(".log")).is_some_and(|ms| !ms.is_empty() && ms.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Renames the active file aside (the caller has closed it) and prunes
+pub fn rotate<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    let Some(dir) = log_dir(app) else { return };
+    let ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let _ = std::fs::rename(dir.join(ACTIVE_FILE), dir.join(format!("backend-{}.log", ms)));
+    prune_and_report(app);
+}
+
+/// Applies the retention policy and emits `python-logs-pruned` when files were deleted
+pub fn prune_and_report<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> usize {
+    let Some(dir) = log_dir(app) else { return 0 };
+    let config = config::get(app);
+    let pruned = prune(&dir, config.log_max_files as usize, config.log_max_age_days, SystemTime::now());
+    if pruned > 0 {
+        let _ = app.emit("python-logs-pruned", LogsPruned { pruned });
+    }
+    pruned
+}
+
+/// Deletes rotated files beyond the `max_files` newest or older than `max_age_days`
+/// (0: no age limit) as of `now`. Returns how many were deleted.
+fn prune(dir: &Path, max_files: usize, max_age_days: u32, now: SystemTime) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
+    let mut rotated: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter(|e| is_rotated(&e.file_name().to_string_lossy()))
+        .filter_map(|e| Some((e.metadata().and_then(|m| m.modified()).ok()?, e.path())))
+        .collect();
+    rotated.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    let max_age = Duration::from_secs(max_age_days as u64 * 24 * 60 * 60);
+    rotated
+        .iter()
+        .enumerate()
+        .filter(|(index, (modified, _))| {
+            *index >= max_files || (max_age_days > 0 && now.duration_since(*modified).unwrap_or_default() > max_age)
+        })
+        .filter(|(_, (_, path))| std::fs::remove_file(path).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prunes_by_count_and_age_but_never_the_active_file() {
+        let dir = std::env::temp_dir().join(format!("televoodoo-log-rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [ACTIVE_FILE, "backend-1.log", "backend-2.log", "backend-3.log", "notes.log"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+        // Count: keep the two newest of three
+        assert_eq!(prune(&dir, 2, 0, SystemTime::now()), 1);
+        // Age: everything rotated is older than a day, seen from two days ahead
+        let later = SystemTime::now() + Duration::from_secs(2 * 24 * 60 * 60);
+        assert_eq!(prune(&dir, 100, 1, later), 2);
+        assert!(dir.join(ACTIVE_FILE).exists() && dir.join("notes.log").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
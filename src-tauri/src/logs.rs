@@ -0,0 +1,416 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+use crate::{log_http, log_rotation, recording, system_log, throughput};
+
+// Shared on-disk log (app log dir / backend.log), opened on first write
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+// Size of the open log file, for rotation
+static LOG_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of recent backend lines kept in memory
+pub const RECENT_CAPACITY: usize = 1000;
+
+// Ring buffer of recent backend lines; seq increases monotonically across sessions
+static RECENT: Mutex<VecDeque<BufferedLine>> = Mutex::new(VecDeque::new());
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// A backend line retained in the in-memory ring buffer
+#[derive(Clone, serde::Serialize)]
+pub struct BufferedLine {
+    pub seq: u64,
+    pub stream: Stream,
+    pub text: String,
+    /// Unix timestamp in milliseconds when the line was read
+    pub timestamp_ms: u64,
+}
+
+/// Buffers a backend line and returns its seq
+fn push_recent(stream: Stream, text: &str) -> u64 {
+    let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let seq = NEXT_SEQ.fetch_add(1, Ordering::SeqCst);
+    let line = BufferedLine { seq, stream, text: text.to_string(), timestamp_ms };
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+    seq
+}
+
+/// Sequence number the next buffered line will get
+pub fn next_seq() -> u64 {
+    NEXT_SEQ.load(Ordering::SeqCst)
+}
+
+/// Read time of the newest buffered line
+pub fn last_line_timestamp_ms() -> Option<u64> {
+    RECENT.lock().unwrap_or_else(|e| e.into_inner()).back().map(|l| l.timestamp_ms)
+}
+
+/// Buffered lines with `seq >= from_seq`, oldest first, at most `max`
+pub fn recent_since(from_seq: u64, max: usize) -> Vec<BufferedLine> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent.iter().filter(|l| l.seq >= from_seq).take(max).cloned().collect()
+}
+
+// Whether backend lines of each stream are emitted to the UI (see set_forwarding)
+static FORWARD_STDOUT: AtomicBool = AtomicBool::new(true);
+static FORWARD_STDERR: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables emitting backend stdout/stderr lines as events. Muted lines are
+/// still read, written to the log file and kept in the ring buffer, so the pipes never
+/// back up.
+pub fn set_forwarding(stdout: bool, stderr: bool) {
+    FORWARD_STDOUT.store(stdout, Ordering::SeqCst);
+    FORWARD_STDERR.store(stderr, Ordering::SeqCst);
+}
+
+// Transient matchers registered by wait_for_line, by id
+static MATCHERS: Mutex<Vec<(u64, regex::Regex, mpsc::Sender<String>)>> = Mutex::new(Vec::new());
+static NEXT_MATCHER: AtomicU64 = AtomicU64::new(1);
+
+/// Waits up to `timeout` for a backend line (either stream, ANSI codes stripped) matching
+/// `pattern` and returns it. Only lines read after the call are considered.
+pub fn wait_for_match(pattern: regex::Regex, timeout: Duration) -> Option<String> {
+    let id = NEXT_MATCHER.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = mpsc::channel();
+    MATCHERS.lock().unwrap_or_else(|e| e.into_inner()).push((id, pattern, tx));
+    let line = rx.recv_timeout(timeout).ok();
+    MATCHERS.lock().unwrap_or_else(|e| e.into_inner()).retain(|(m, _, _)| *m != id);
+    line
+}
+
+// Hands `text` to every matcher it satisfies; each one fires once
+fn run_matchers(text: &str) {
+    let mut matchers = MATCHERS.lock().unwrap_or_else(|e| e.into_inner());
+    if matchers.is_empty() {
+        return;
+    }
+    matchers.retain(|(_, pattern, tx)| {
+        if !pattern.is_match(text) {
+            return true;
+        }
+        let _ = tx.send(text.to_string());
+        false
+    });
+}
+
+// Backend lines detected below this level are not emitted; None emits everything
+static MIN_LEVEL: Mutex<Option<Level>> = Mutex::new(None);
+
+/// Sets the minimum level of emitted backend lines (see set_log_level)
+pub fn set_min_level(level: Option<Level>) {
+    *MIN_LEVEL.lock().unwrap_or_else(|e| e.into_inner()) = level;
+}
+
+fn below_min_level(level: Level) -> bool {
+    MIN_LEVEL.lock().unwrap_or_else(|e| e.into_inner()).is_some_and(|min| level < min)
+}
+
+// While set, backend lines are not emitted as `python-log`; SKIPPED counts what was held back
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Stops emitting backend lines as `python-log` (they are still read, logged to file, kept
+/// in the ring buffer and sent on the protocol events)
+pub fn pause() {
+    SKIPPED.store(0, Ordering::SeqCst);
+    PAUSED.store(true, Ordering::SeqCst);
+}
+
+/// Resumes emitting `python-log` for backend lines, returning how many were skipped while paused
+pub fn resume() -> u64 {
+    PAUSED.store(false, Ordering::SeqCst);
+    SKIPPED.swap(0, Ordering::SeqCst)
+}
+
+fn forwarding_enabled(stream: Stream) -> bool {
+    match stream {
+        Stream::Stdout => FORWARD_STDOUT.load(Ordering::SeqCst),
+        Stream::Stderr => FORWARD_STDERR.load(Ordering::SeqCst),
+    }
+}
+
+/// Text of the last `n` buffered lines of `stream` with `seq >= from_seq`, oldest first
+pub fn tail_since(from_seq: u64, stream: Stream, n: usize) -> Vec<String> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    let mut tail: Vec<String> =
+        recent.iter().rev().filter(|l| l.seq >= from_seq && l.stream == stream).take(n).map(|l| l.text.clone()).collect();
+    tail.reverse();
+    tail
+}
+
+/// Identifies one backend launch; lines in the shared log file are prefixed with `[tag]`
+#[derive(Clone)]
+pub struct Session {
+    pub id: String,
+    pub tag: String,
+}
+
+impl Session {
+    /// `tag` defaults to the session id when not provided
+    pub fn new(id: String, tag: Option<String>) -> Self {
+        let tag = tag.unwrap_or_else(|| id.clone());
+        Self { id, tag }
+    }
+}
+
+/// Checks a user-provided log tag: non-empty, single line, at most 64 chars
+pub fn validate_tag(tag: &str) -> Result<(), String> {
+    if tag.trim().is_empty() || tag.len() > 64 || tag.contains(['\n', '\r', ']']) {
+        return Err(format!("Invalid log tag: {:?}", tag));
+    }
+    Ok(())
+}
+
+/// Which backend pipe a line came from
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    /// Legacy per-stream event carrying just the line text
+    fn legacy_event(self) -> &'static str {
+        match self {
+            Stream::Stdout => "python-line",
+            Stream::Stderr => "python-error",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Payload of `python-log`: one output line plus metadata for coloring in the UI
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct LogLine {
+    /// Session id of the backend launch, for lines produced by the backend itself
+    pub session: Option<String>,
+    /// Position of a backend line in the merged stdout+stderr stream, in the order the
+    /// viewer received them (monotonic across sessions); None for helper lines
+    #[serde(default)]
+    pub seq: Option<u64>,
+    /// "backend" for televoodoo itself, otherwise the helper that produced it (e.g. "pip")
+    pub source: String,
+    pub stream: Stream,
+    pub level: Level,
+    /// Line text with ANSI escape sequences removed
+    pub text: String,
+    /// Whether the raw line contained ANSI escape sequences
+    pub had_ansi: bool,
+    /// Emitted by replay_log from a recording rather than by a live backend
+    #[serde(default)]
+    pub replayed: bool,
+}
+
+impl LogLine {
+    pub fn new(source: &str, stream: Stream, raw: &str) -> Self {
+        let (text, had_ansi) = strip_ansi(normalize_line(raw));
+        let level = detect_level(&text);
+        Self { session: None, seq: None, source: source.to_string(), stream, level, text, had_ansi, replayed: false }
+    }
+}
+
+/// Emits a backend line both as the structured `python-log` event and the legacy
+/// `python-line`/`python-error` string event, unless its stream is muted. Pausing and the
+/// minimum level only hold back `python-log`: the legacy events carry the pose protocol.
+pub fn forward_line<R: tauri::Runtime>(app: &tauri::AppHandle<R>, session: &Session, stream: Stream, raw: &str) {
+    let mut line = LogLine::new("backend", stream, raw);
+    line.session = Some(session.id.clone());
+    line.seq = Some(push_recent(stream, &line.text));
+    write_to_file(app, &session.tag, &line);
+    recording::record(&line);
+    log_http::broadcast(&line);
+    run_matchers(&line.text);
+    if line.level == Level::Error {
+        system_log::log(Level::Error, &format!("[{}] {}", session.tag, line.text));
+    }
+    if !forwarding_enabled(stream) {
+        throughput::record_dropped();
+        return;
+    }
+    // Pose and heartbeat lines are detected as Info, and the UI disconnects without them
+    let _ = app.emit(stream.legacy_event(), &line.text);
+    if PAUSED.load(Ordering::SeqCst) {
+        SKIPPED.fetch_add(1, Ordering::SeqCst);
+        throughput::record_dropped();
+        return;
+    }
+    if below_min_level(line.level) {
+        throughput::record_dropped();
+        return;
+    }
+    let _ = app.emit("python-log", &line);
+}
+
+/// Emits a line from a helper command (pip, hooks, ...) as `python-log` only, so it never
+/// reaches the backend's stdout protocol handling in the UI
+pub fn forward_tool_line<R: tauri::Runtime>(app: &tauri::AppHandle<R>, source: &str, stream: Stream, raw: &str) {
+    let line = LogLine::new(source, stream, raw);
+    write_to_file(app, source, &line);
+    recording::record(&line);
+    log_http::broadcast(&line);
+    let _ = app.emit("python-log", line);
+}
+
+/// Appends `[tag] text` (stderr lines marked with `!`) to the shared log file
+fn write_to_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>, tag: &str, line: &LogLine) {
+    let Ok(mut guard) = LOG_FILE.lock() else { return };
+    if guard.is_none() {
+        *guard = open_log_file(app);
+    }
+    if let Some(file) = guard.as_mut() {
+        let marker = if line.stream == Stream::Stderr { "!" } else { "" };
+        let entry = format!("[{}]{} {}\n", tag, marker, line.text);
+        if file.write_all(entry.as_bytes()).is_ok() && LOG_BYTES.fetch_add(entry.len() as u64, Ordering::Relaxed) + entry.len() as u64 >= log_rotation::ROTATE_BYTES {
+            // Reopened by the next write
+            *guard = None;
+            log_rotation::rotate(app);
+        }
+    }
+}
+
+/// Flushes the shared log file, if open
+pub fn flush() {
+    if let Some(file) = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        let _ = file.flush();
+    }
+}
+
+fn open_log_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<File> {
+    let dir = log_rotation::log_dir(app)?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(dir.join(log_rotation::ACTIVE_FILE)).ok()?;
+    LOG_BYTES.store(file.metadata().map(|m| m.len()).unwrap_or(0), Ordering::Relaxed);
+    Some(file)
+}
+
+/// Runs a helper command to completion, streaming its stdout/stderr through the log
+/// pipeline tagged with `source`
+pub fn run_streamed<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: &mut Command, source: &str) -> std::io::Result<ExitStatus> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (app_err, source_err) = (app.clone(), source.to_string());
+    let stderr_thread = std::thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                forward_tool_line(&app_err, &source_err, Stream::Stderr, &line);
+            }
+        }
+    });
+    if let Some(stdout) = stdout {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            forward_tool_line(app, source, Stream::Stdout, &line);
+        }
+    }
+    let _ = stderr_thread.join();
+    child.wait()
+}
+
+/// Strips a leading UTF-8 BOM and a trailing CR, which Windows programs may emit and
+/// which `BufRead::lines` leaves in place when a line ends in a bare `\r`
+pub fn normalize_line(raw: &str) -> &str {
+    let line = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    line.trim_end_matches('\r')
+}
+
+/// Best-effort level detection from common Python logging / traceback markers
+pub fn detect_level(text: &str) -> Level {
+    let upper = text.to_ascii_uppercase();
+    if upper.contains("ERROR") || upper.contains("CRITICAL") || upper.contains("TRACEBACK") || upper.contains("EXCEPTION") {
+        Level::Error
+    } else if upper.contains("WARN") {
+        Level::Warning
+    } else if upper.contains("DEBUG") {
+        Level::Debug
+    } else {
+        Level::Info
+    }
+}
+
+/// Removes ANSI escape sequences (CSI such as colors, OSC such as titles, and two-byte
+/// escapes), returning the plain text and whether anything was removed
+pub fn strip_ansi(raw: &str) -> (String, bool) {
+    if !raw.contains('\u{1b}') {
+        return (raw.to_string(), false);
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters/intermediates until a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Other two-byte escapes, or a dangling ESC at end of line
+            _ => {}
+        }
+    }
+    (out, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn normalizes_bom_and_crlf() {
+        let input = Cursor::new("\u{feff}first\r\nsecond\r\r\nthird\n");
+        let lines: Vec<String> = input.lines().map_while(Result::ok).map(|l| normalize_line(&l).to_string()).collect();
+        assert_eq!(lines, vec!["first", "second", "third"]);
+
+        let line = LogLine::new("backend", Stream::Stdout, "\u{feff}\u{1b}[31mERROR\u{1b}[0m boom\r");
+        assert_eq!(line.text, "ERROR boom");
+        assert!(line.had_ansi);
+    }
+}
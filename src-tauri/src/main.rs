@@ -1,347 +1,2658 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::Emitter;
-use tauri::Manager; // for app.path()
 
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 
-// Global storage for Python child process to enable cleanup on exit
+mod affinity;
+mod bootstrap;
+mod cli_help;
+mod config;
+mod config_check;
+mod control;
+mod detached;
+mod device;
+mod dotenv;
+mod elevation;
+mod endpoint;
+mod env_conflicts;
+mod env_snapshots;
+mod error;
+mod hooks;
+mod hotreload;
+mod instance_lock;
+mod launch_plan;
+mod launch_script;
+mod log_http;
+mod log_rotation;
+mod logs;
+mod memory;
+mod metrics;
+mod monitor;
+mod open_files;
+mod pip_errors;
+mod preflight;
+mod profiler;
+mod quarantine;
+mod recording;
+mod remote_update;
+mod requirements;
+mod resource_limits;
+mod resume;
+mod self_repair;
+mod selftest;
+mod share_logs;
+mod shim;
+mod start_timings;
+mod system_log;
+mod throughput;
+mod token;
+mod update;
+mod venv_archive;
+mod venvs;
+
+use bootstrap::find_bundled_python_dir;
+use error::PythonError;
+use logs::{Level, Stream};
+
+// Global storage for Python child process to enable cleanup on exit.
+// Every spawned Child (backend, bootstrap steps, hooks, probes) has one owner that waits
+// on it; there is deliberately no SIGCHLD reaper, as waitpid(-1) would steal exit
+// statuses from Child::wait/try_wait.
 static PYTHON_CHILD: Mutex<Option<Child>> = Mutex::new(None);
+// Incremented per spawn so threads of a replaced process can tell they are stale
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+// Set while stop_python/cleanup is terminating the child, so EOF/exit is expected
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+// True while start_python is bootstrapping/spawning, so cancel_start has something to cancel
+static START_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+// Generation for which python-connection-lost was already emitted
+static LOST_REPORTED: AtomicU64 = AtomicU64::new(0);
+// Interpreter the current/last backend was launched with
+static LAUNCH_PYTHON: Mutex<Option<PathBuf>> = Mutex::new(None);
+// PYTHONIOENCODING the current/last backend was launched with (None: locale default)
+static LAUNCH_ENCODING: Mutex<Option<String>> = Mutex::new(None);
+// Exit info and stderr tail of the last non-zero exit, cleared on the next spawn
+static LAST_CRASH: Mutex<Option<CrashInfo>> = Mutex::new(None);
+// Config of the last successful start, reused by auto-restart
+static LAST_START: Mutex<Option<StartConfig>> = Mutex::new(None);
+// Times of recent auto-restart attempts, pruned to the configured window
+static RESTART_ATTEMPTS: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+// stdout/stderr reader threads of the current child, so stop_python can wait for them to drain
+static READERS: Mutex<Vec<std::thread::JoinHandle<()>>> = Mutex::new(Vec::new());
+// oom_score_adj applied to every backend after spawn (Linux), set by set_oom_score_adj
+static OOM_SCORE_ADJ: Mutex<Option<i32>> = Mutex::new(None);
+// Cores set by set_cpu_affinity, applied to every launch (None: no pinning)
+static CPU_AFFINITY: Mutex<Option<Vec<usize>>> = Mutex::new(None);
+
+/// Spawns the prepared backend command, registers it as the current child and starts
+/// the stdout/stderr forwarding threads plus an exit watcher. A `detached` backend writes
+/// its output to files that are tailed instead (see the detached module).
+fn spawn_backend<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    mut cmd: Command,
+    log_tag: Option<String>,
+    priority: Option<i32>,
+    detached: bool,
+) -> Result<Option<detached::Output>, PythonError> {
+    // On Unix, create new process group for cleaner termination
+    #[cfg(unix)]
+    unsafe {
+        cmd.pre_exec(move || {
+            if detached {
+                // Own session, so it outlives the viewer and its terminal
+                libc::setsid();
+            } else {
+                // Create new process group with this process as leader
+                libc::setpgid(0, 0);
+            }
+            Ok(())
+        });
+    }
+
+    let output = if detached {
+        Some(detached::prepare(app, &mut cmd)?)
+    } else {
+        cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        None
+    };
+    let mut attempt = 0;
+    let mut child = loop {
+        match cmd.spawn() {
+            Ok(child) => break child,
+            // EAGAIN: fork hit a transient process/memory limit; worth another try
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock && attempt < SPAWN_RETRIES => {
+                attempt += 1;
+                let delay_ms = SPAWN_RETRY_DELAY_MS * attempt as u64;
+                let _ = app.emit("python-spawn-retry", SpawnRetry { attempt, max_attempts: SPAWN_RETRIES, delay_ms, message: e.to_string() });
+                std::thread::sleep(Duration::from_millis(delay_ms));
+            }
+            Err(e) => return Err(PythonError::from_spawn_error(Path::new(cmd.get_program()), cmd.get_current_dir(), e)),
+        }
+    };
+
+    start_timings::mark(start_timings::Mark::Spawn);
+
+    // Without both pipes the backend would run with its output silently dropped
+    let missing: Vec<&str> = [("stdout", output.is_none() && child.stdout.is_none()), ("stderr", output.is_none() && child.stderr.is_none())]
+        .into_iter()
+        .filter_map(|(name, missing)| missing.then_some(name))
+        .collect();
+    if !missing.is_empty() {
+        let missing = missing.join(" and ");
+        logs::forward_tool_line(app, "spawn", Stream::Stderr, &format!("ERROR: backend {} pipe was not attached; stopping it", missing));
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(PythonError::PipeSetupFailed { missing });
+    }
+
+    if let Some(nice) = priority {
+        set_priority(app, &child, nice);
+    }
+    if let Some(adj) = *OOM_SCORE_ADJ.lock().unwrap_or_else(|e| e.into_inner()) {
+        if let Err(e) = write_oom_score_adj(child.id(), adj) {
+            logs::forward_tool_line(app, "oom", Stream::Stderr, &format!("WARNING: could not set oom_score_adj to {}: {}", adj, e));
+        }
+    }
+    if let Some(cores) = CPU_AFFINITY.lock().unwrap_or_else(|e| e.into_inner()).as_deref() {
+        if let Err(e) = affinity::apply(&child, cores) {
+            logs::forward_tool_line(app, "affinity", Stream::Stderr, &format!("WARNING: could not pin backend to cores {:?}: {}", cores, e));
+        }
+    }
+
+    // take pipes before moving child into threads
+    let first_seq = logs::next_seq();
+    let stdin = child.stdin.take();
+    let viewer_config = config::get(app);
+    let buffer_capacity = viewer_config.reader_buffer_capacity();
+    system_log::set_enabled(viewer_config.system_log);
+    endpoint::reset(app, &viewer_config.endpoint_pattern);
+    throughput::reset();
+    metrics::reset();
+    logs::set_min_level(viewer_config.log_level);
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // Store child process for cleanup on exit
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let session = logs::Session::new(format!("{}-{}", started_at, generation), log_tag);
+    {
+        // Never drop a Child unwaited (not even on a poisoned lock): it would stay a zombie
+        let mut guard = PYTHON_CHILD.lock().unwrap_or_else(|e| e.into_inner());
+        // Kill any existing Python process first
+        if let Some(mut old_child) = guard.take() {
+            let _ = old_child.kill();
+            let _ = old_child.wait();
+        }
+        STOP_REQUESTED.store(false, Ordering::SeqCst);
+        *guard = Some(child);
+    }
+    control::set_stdin(stdin);
+    *LAST_CRASH.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    system_log::log(Level::Info, &format!("backend started (session {})", session.id));
+
+    // Both readers feed one channel and a single emitter forwards the lines, so the UI sees
+    // one stream in receive order. The OS gives no ordering between two pipes, so a line
+    // can still overtake one the other stream wrote just before it; receive order itself is
+    // stable, and logs::forward_line numbers lines in it.
+    let (tx, rx) = std::sync::mpsc::channel::<(Stream, String)>();
+    let (stdout_reader, stderr_reader) = match output {
+        // Files instead of pipes: follow them while this generation's backend runs
+        Some(ref output) => {
+            let alive = move || GENERATION.load(Ordering::SeqCst) == generation && backend_running();
+            (
+                detached::tail(output.stdout.clone(), 0, Stream::Stdout, tx.clone(), alive),
+                detached::tail(output.stderr.clone(), 0, Stream::Stderr, tx, alive),
+            )
+        }
+        None => spawn_pipe_readers(buffer_capacity, stdout, stderr, tx),
+    };
+    // Only pipes closing early say something about the connection
+    let lost_generation = output.is_none().then_some(generation);
+    let emitter = spawn_emitter(app, session, rx, lost_generation);
+    if let Ok(mut readers) = READERS.lock() {
+        *readers = vec![stdout_reader, stderr_reader, emitter];
+    }
+
+    let app_handle_exit = app.clone();
+    std::thread::spawn(move || watch_exit(&app_handle_exit, generation, first_seq));
+    let app_handle_heartbeat = app.clone();
+    std::thread::spawn(move || heartbeat(&app_handle_heartbeat, generation, first_seq));
+    let app_handle_metrics = app.clone();
+    std::thread::spawn(move || poll_metrics(&app_handle_metrics, generation));
+    let app_handle_throughput = app.clone();
+    std::thread::spawn(move || {
+        throughput::report(&app_handle_throughput, || GENERATION.load(Ordering::SeqCst) == generation && backend_running())
+    });
+
+    Ok(output)
+}
+
+/// Reads the backend's stdout/stderr pipes into `tx`; stdout lines that are RPC responses
+/// go to their caller instead
+fn spawn_pipe_readers(
+    buffer_capacity: usize,
+    stdout: Option<std::process::ChildStdout>,
+    stderr: Option<std::process::ChildStderr>,
+    tx: std::sync::mpsc::Sender<(Stream, String)>,
+) -> (std::thread::JoinHandle<()>, std::thread::JoinHandle<()>) {
+    let tx_err = tx.clone();
+    let stdout_reader = std::thread::spawn(move || {
+        if let Some(stdout) = stdout {
+            let reader = BufReader::with_capacity(buffer_capacity, stdout);
+            for line in reader.lines().map_while(Result::ok) {
+                throughput::record_read(Stream::Stdout, line.len());
+                // RPC responses go straight to their caller
+                if control::handle_line(&line) {
+                    continue;
+                }
+                let _ = tx.send((Stream::Stdout, line));
+            }
+        }
+    });
+
+    // forward stderr too
+    let stderr_reader = std::thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            let reader = BufReader::with_capacity(buffer_capacity, stderr);
+            for line in reader.lines().map_while(Result::ok) {
+                throughput::record_read(Stream::Stderr, line.len());
+                let _ = tx_err.send((Stream::Stderr, line));
+            }
+        }
+    });
+    (stdout_reader, stderr_reader)
+}
+
+/// Forwards the lines both readers send into `rx`; ends once both have dropped their
+/// senders. With `lost_generation`, the end of the output is then checked for a lost
+/// connection, so `python-connection-lost` comes after the backend's last lines.
+fn spawn_emitter<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    session: logs::Session,
+    rx: std::sync::mpsc::Receiver<(Stream, String)>,
+    lost_generation: Option<u64>,
+) -> std::thread::JoinHandle<()> {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for (stream, line) in rx {
+            start_timings::mark(start_timings::Mark::FirstLine);
+            endpoint::scan(&app, &line);
+            logs::forward_line(&app, &session, stream, &line);
+            throughput::record_forwarded();
+        }
+        if let Some(generation) = lost_generation {
+            report_unexpected_eof(&app, generation);
+        }
+    })
+}
+
+/// A detached backend from an earlier run for a start with `config` to adopt: only one
+/// launched with the same settings (the detached flag aside). Any other still running is
+/// an error rather than silently standing in for the requested backend.
+fn detached_to_reattach<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: &StartConfig) -> Result<Option<detached::State>, PythonError> {
+    if backend_running() {
+        return Ok(None);
+    }
+    let Some(state) = detached::find_running(app) else { return Ok(None) };
+    let wanted = serde_json::to_value(StartConfig { detached: true, ..config.clone() }).unwrap_or_default();
+    if state.config == wanted {
+        return Ok(Some(state));
+    }
+    Err(PythonError::Other(format!(
+        "A detached backend from an earlier run is still running (pid {}) with other settings; stop it first, or start with its settings to reattach",
+        state.pid
+    )))
+}
+
+/// Payload of `python-reattached`
+#[derive(Clone, serde::Serialize)]
+struct Reattached {
+    pid: u32,
+    started_at_ms: u64,
+}
+
+/// Adopts a detached backend left running by an earlier viewer run: tails its output from
+/// the current end of the files and watches for it to exit
+fn reattach<R: tauri::Runtime>(app: &tauri::AppHandle<R>, state: detached::State) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    STOP_REQUESTED.store(false, Ordering::SeqCst);
+    detached::attach(app, &state);
+    let viewer_config = config::get(app);
+    endpoint::reset(app, &viewer_config.endpoint_pattern);
+    logs::set_min_level(viewer_config.log_level);
+    let session = logs::Session::new(format!("{}-{}", state.started_at_ms / 1000, generation), None);
+    system_log::log(Level::Info, &format!("reattached to detached backend (pid {})", state.pid));
+
+    let (tx, rx) = std::sync::mpsc::channel::<(Stream, String)>();
+    let alive = move || GENERATION.load(Ordering::SeqCst) == generation && detached::attached_alive();
+    let len = |path: &Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let stdout_reader = detached::tail(state.stdout.clone(), len(&state.stdout), Stream::Stdout, tx.clone(), alive);
+    let stderr_reader = detached::tail(state.stderr.clone(), len(&state.stderr), Stream::Stderr, tx, alive);
+    let emitter = spawn_emitter(app, session, rx, None);
+    *READERS.lock().unwrap_or_else(|e| e.into_inner()) = vec![stdout_reader, stderr_reader, emitter];
+
+    *LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()) = state.interpreter.clone();
+    if let Ok(config) = serde_json::from_value::<StartConfig>(state.config.clone()) {
+        *LAST_START.lock().unwrap_or_else(|e| e.into_inner()) = Some(StartConfig { detached: true, ..config });
+    }
+    let _ = app.emit("python-reattached", Reattached { pid: state.pid, started_at_ms: state.started_at_ms });
+
+    // Not our child, so there is no exit status; only that it is gone
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(250));
+        if GENERATION.load(Ordering::SeqCst) != generation || STOP_REQUESTED.load(Ordering::SeqCst) {
+            return;
+        }
+        if !detached::attached_alive() {
+            detached::clear();
+            instance_lock::release();
+            drain_readers(Duration::from_millis(500));
+            system_log::log(Level::Info, "detached backend exited");
+            let _ = app.emit("python-exited", ExitInfo { code: None, success: false });
+            return;
+        }
+    });
+}
+
+/// Extra spawn attempts after EAGAIN, each delayed by SPAWN_RETRY_DELAY_MS times the attempt
+const SPAWN_RETRIES: u32 = 3;
+const SPAWN_RETRY_DELAY_MS: u64 = 200;
+
+/// Payload of `python-spawn-retry`
+#[derive(Clone, serde::Serialize)]
+struct SpawnRetry {
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    /// The transient spawn error being retried
+    message: String,
+}
+
+/// Niceness range accepted by start_python's `priority` (Unix semantics: lower is more
+/// CPU priority, 0 is the default)
+const PRIORITY_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// Applies a niceness to the freshly spawned backend. On Unix this covers the whole process
+/// group (the child is its leader); on Windows it maps onto the nearest priority class.
+/// Out-of-range values are clamped; failures (e.g. raising priority without privileges)
+/// are logged and the backend keeps running at the default priority.
+fn set_priority<R: tauri::Runtime>(app: &tauri::AppHandle<R>, child: &Child, nice: i32) {
+    let clamped = nice.clamp(*PRIORITY_RANGE.start(), *PRIORITY_RANGE.end());
+    if clamped != nice {
+        logs::forward_tool_line(app, "priority", Stream::Stderr, &format!("WARNING: priority {} out of range, clamped to {}", nice, clamped));
+    }
+    #[cfg(unix)]
+    {
+        let result = unsafe { libc::setpriority(libc::PRIO_PGRP, child.id() as libc::id_t, clamped) };
+        if result != 0 {
+            let err = std::io::Error::last_os_error();
+            logs::forward_tool_line(app, "priority", Stream::Stderr, &format!("WARNING: could not set backend priority to {}: {}", clamped, err));
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::Threading::{
+            SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+            IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+        };
+        let class = match clamped {
+            i32::MIN..=-10 => HIGH_PRIORITY_CLASS,
+            -9..=-1 => ABOVE_NORMAL_PRIORITY_CLASS,
+            0 => NORMAL_PRIORITY_CLASS,
+            1..=9 => BELOW_NORMAL_PRIORITY_CLASS,
+            _ => IDLE_PRIORITY_CLASS,
+        };
+        if unsafe { SetPriorityClass(child.as_raw_handle() as _, class) } == 0 {
+            let err = std::io::Error::last_os_error();
+            logs::forward_tool_line(app, "priority", Stream::Stderr, &format!("WARNING: could not set backend priority to {}: {}", clamped, err));
+        }
+    }
+}
+
+const OOM_SCORE_ADJ_RANGE: std::ops::RangeInclusive<i32> = -1000..=1000;
+
+#[cfg(target_os = "linux")]
+fn write_oom_score_adj(pid: u32, adj: i32) -> std::io::Result<()> {
+    std::fs::write(format!("/proc/{}/oom_score_adj", pid), adj.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn write_oom_score_adj(_pid: u32, _adj: i32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Sets the OOM-killer adjustment of the backend (-1000 never kill, 1000 kill first) and
+/// keeps it for later launches. Lowering it below the current value needs CAP_SYS_RESOURCE.
+/// No-op on platforms other than Linux.
+#[tauri::command]
+fn set_oom_score_adj(value: i32) -> Result<(), String> {
+    if !OOM_SCORE_ADJ_RANGE.contains(&value) {
+        return Err(format!("oom_score_adj must be between {} and {}", OOM_SCORE_ADJ_RANGE.start(), OOM_SCORE_ADJ_RANGE.end()));
+    }
+    *OOM_SCORE_ADJ.lock().unwrap_or_else(|e| e.into_inner()) = Some(value);
+    let pid = backend_pid();
+    match pid {
+        Some(pid) if backend_running() => {
+            write_oom_score_adj(pid, value).map_err(|e| format!("Could not set oom_score_adj: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Clears the `.env` file merged into the backend's environment on launch. Choosing a
+/// different one needs a hand edit of config.json (env_file is PROTECTED).
+#[tauri::command]
+fn set_env_file<R: tauri::Runtime>(app: tauri::AppHandle<R>, path: Option<PathBuf>) -> Result<(), String> {
+    if let Some(ref path) = path {
+        if config::get(&app).env_file.as_ref() != Some(path) {
+            return Err("env_file can only be changed by editing config.json".to_string());
+        }
+        dotenv::validate_path(path)?;
+    }
+    config::update(&app, |c| c.env_file = path)
+}
+
+/// Pins the backend to `cores` (indices below the number of available cores) and keeps
+/// the setting for later launches; an empty list removes the pinning. Where the platform
+/// can't pin another process (macOS) this only logs a warning.
+#[tauri::command]
+fn set_cpu_affinity<R: tauri::Runtime>(app: tauri::AppHandle<R>, cores: Vec<usize>) -> Result<(), String> {
+    affinity::validate(&cores)?;
+    let pinned = (!cores.is_empty()).then(|| cores.clone());
+    *CPU_AFFINITY.lock().unwrap_or_else(|e| e.into_inner()) = pinned;
+    let guard = PYTHON_CHILD.lock().unwrap_or_else(|e| e.into_inner());
+    let result = match guard.as_ref() {
+        Some(child) => affinity::apply(child, &cores),
+        None => Ok(()),
+    };
+    drop(guard);
+    match result {
+        Err(e) if cfg!(not(any(target_os = "linux", windows))) => {
+            logs::forward_tool_line(&app, "affinity", Stream::Stderr, &format!("WARNING: {}", e));
+            Ok(())
+        }
+        result => result.map_err(|e| format!("Could not set CPU affinity: {}", e)),
+    }
+}
+
+/// Payload of `python-heartbeat`
+#[derive(Clone, serde::Serialize)]
+struct Heartbeat {
+    status: PythonStatus,
+    uptime_ms: u64,
+    /// Output lines read from this backend so far
+    lines: u64,
+    /// Unix timestamp in milliseconds of the most recent output line, if any
+    last_line_ms: Option<u64>,
+}
+
+/// Emits `python-heartbeat` every `heartbeat_interval_ms` while the backend of
+/// `generation` runs; the interval is re-read each tick so changes apply live
+fn heartbeat<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64, first_seq: u64) {
+    let started = Instant::now();
+    loop {
+        let interval_ms = config::get(app).heartbeat_interval_ms;
+        // While disabled, check back now and then in case it gets enabled
+        std::thread::sleep(Duration::from_millis(if interval_ms == 0 { 1000 } else { interval_ms }));
+        if GENERATION.load(Ordering::SeqCst) != generation || !backend_running() {
+            return;
+        }
+        if interval_ms == 0 {
+            continue;
+        }
+        let _ = app.emit("python-heartbeat", Heartbeat {
+            status: python_status(),
+            uptime_ms: started.elapsed().as_millis() as u64,
+            lines: logs::next_seq().saturating_sub(first_seq),
+            last_line_ms: logs::last_line_timestamp_ms(),
+        });
+    }
+}
+
+/// Emits `python-metrics` every `metrics_interval_ms` while the backend of `generation`
+/// runs and exposes metrics; like heartbeat, the interval is re-read each tick
+fn poll_metrics<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64) {
+    loop {
+        let interval_ms = config::get(app).metrics_interval_ms;
+        std::thread::sleep(Duration::from_millis(if interval_ms == 0 { 1000 } else { interval_ms }));
+        if GENERATION.load(Ordering::SeqCst) != generation || !backend_running() {
+            return;
+        }
+        if interval_ms == 0 {
+            continue;
+        }
+        match metrics::query() {
+            Ok(Some(metrics)) => {
+                let _ = app.emit("python-metrics", metrics);
+            }
+            // Not exposed by this backend; metrics::reset re-enables it for the next one
+            Ok(None) => return,
+            Err(_) => {}
+        }
+    }
+}
+
+/// Payload of `python-exited`
+#[derive(Clone, serde::Serialize)]
+struct ExitInfo {
+    code: Option<i32>,
+    success: bool,
+}
+
+/// Number of trailing stderr lines kept for get_last_crash
+const CRASH_TAIL_LINES: usize = 50;
+
+/// Returned by get_last_crash
+#[derive(Clone, serde::Serialize)]
+struct CrashInfo {
+    /// Exit code, or None when killed by a signal
+    code: Option<i32>,
+    /// Unix timestamp (seconds) of when the exit was observed
+    timestamp: u64,
+    /// Last stderr lines of the crashed session, oldest first
+    stderr_tail: Vec<String>,
+}
+
+/// Called by the emitter once both pipes hit EOF and their lines are forwarded. If no stop
+/// was requested and the backend has not simply exited cleanly, emits
+/// `python-connection-lost` (once per process) ahead of the `python-exited` event from the
+/// exit watcher.
+fn report_unexpected_eof<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64) {
+    if STOP_REQUESTED.load(Ordering::SeqCst) || GENERATION.load(Ordering::SeqCst) != generation {
+        return;
+    }
+    // The pipe usually closes right before the process becomes reapable; give it a moment
+    // so that a normal exit is reported only through python-exited
+    for _ in 0..3 {
+        let exited_cleanly = match PYTHON_CHILD.lock() {
+            Ok(mut guard) => match guard.as_mut().map(|c| c.try_wait()) {
+                Some(Ok(Some(status))) => status.success(),
+                Some(Ok(None)) => false,
+                // Already released by the exit watcher or a stop
+                _ => return,
+            },
+            Err(_) => return,
+        };
+        if exited_cleanly {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if STOP_REQUESTED.load(Ordering::SeqCst) {
+        return;
+    }
+    if LOST_REPORTED.swap(generation, Ordering::SeqCst) != generation {
+        system_log::log(Level::Warning, "backend connection lost");
+        let _ = app.emit("python-connection-lost", ());
+    }
+}
+
+/// Polls the current child until it exits, then emits `python-exited` and releases it.
+/// A non-zero exit also records the session's stderr tail (lines from `first_seq` on) for
+/// get_last_crash. Returns silently when the child is stopped or replaced by a newer spawn.
+fn watch_exit<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64, first_seq: u64) {
+    loop {
+        std::thread::sleep(Duration::from_millis(250));
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let Ok(mut guard) = PYTHON_CHILD.lock() else { return };
+        let status = match guard.as_mut().map(|c| c.try_wait()) {
+            Some(Ok(Some(status))) => status,
+            Some(Ok(None)) => continue,
+            _ => return,
+        };
+        guard.take();
+        drop(guard);
+        control::set_stdin(None);
+        endpoint::clear();
+        if STOP_REQUESTED.load(Ordering::SeqCst) {
+            return;
+        }
+        if !status.success() {
+            // Let the readers forward the final lines before taking the tail
+            drain_readers(Duration::from_millis(500));
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let stderr_tail = logs::tail_since(first_seq, Stream::Stderr, CRASH_TAIL_LINES);
+            *LAST_CRASH.lock().unwrap_or_else(|e| e.into_inner()) =
+                Some(CrashInfo { code: status.code(), timestamp, stderr_tail });
+        }
+        let level = if status.success() { Level::Info } else { Level::Error };
+        system_log::log(level, &format!("backend exited ({})", status));
+        let _ = app.emit("python-exited", ExitInfo { code: status.code(), success: status.success() });
+        hooks::run_post_exit(app);
+        if !status.success() {
+            auto_restart(app, generation);
+        }
+        return;
+    }
+}
+
+/// Payload of `python-restart-cooldown`
+#[derive(Clone, serde::Serialize)]
+struct RestartCooldown {
+    seconds_remaining: u64,
+}
+
+/// Payload of `python-restarted` and `python-restart-giveup`
+#[derive(Clone, serde::Serialize)]
+struct RestartAttempt {
+    /// Auto-restarts within the current window, including this one
+    attempt: u32,
+}
+
+/// Crash supervision: after a crash exit, restarts the backend with the last start config
+/// when `auto_restart` is enabled. Waits `restart_cooldown_ms` first (emitting
+/// `python-restart-cooldown` every second) and gives up with `python-restart-giveup` after
+/// `restart_max_attempts` restarts within `restart_window_secs`. A stop or a new start during
+/// the cooldown cancels the restart.
+fn auto_restart<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64) {
+    let cfg = config::get(app);
+    if !cfg.auto_restart {
+        return;
+    }
+    let Some(start_config) = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).clone() else { return };
+    let attempt = {
+        let mut attempts = RESTART_ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner());
+        let window = Duration::from_secs(cfg.restart_window_secs);
+        while attempts.front().is_some_and(|t| t.elapsed() > window) {
+            attempts.pop_front();
+        }
+        if attempts.len() >= cfg.restart_max_attempts as usize {
+            drop(attempts);
+            system_log::log(Level::Error, "backend keeps crashing; giving up on auto-restart");
+            let _ = app.emit("python-restart-giveup", RestartAttempt { attempt: cfg.restart_max_attempts });
+            return;
+        }
+        attempts.push_back(Instant::now());
+        attempts.len() as u32
+    };
+
+    let superseded = || STOP_REQUESTED.load(Ordering::SeqCst) || GENERATION.load(Ordering::SeqCst) != generation;
+    let deadline = Instant::now() + Duration::from_millis(cfg.restart_cooldown_ms);
+    loop {
+        if superseded() {
+            return;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let _ = app.emit("python-restart-cooldown", RestartCooldown { seconds_remaining: remaining.as_millis().div_ceil(1000) as u64 });
+        std::thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+    // Claimed like a manual start, so ensure_running and cancel_start see this one too
+    if START_IN_PROGRESS.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return;
+    }
+
+    match run_claimed_start(app, start_config) {
+        Ok(()) => {
+            let _ = app.emit("python-restarted", RestartAttempt { attempt });
+        }
+        Err(e) => {
+            logs::forward_tool_line(app, "supervisor", Stream::Stderr, &format!("Auto-restart failed: {}", e));
+        }
+    }
+}
+
+/// Configuration for starting the Python sidecar
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct StartConfig {
+    /// Connection type: "wifi", "ble", or "usb"
+    connection: String,
+    /// Peripheral/server name (optional - uses random if not provided)
+    name: Option<String>,
+    /// Authentication code (optional - uses random if not provided)
+    code: Option<String>,
+    /// Upsample poses to target frequency (Hz) using linear extrapolation
+    upsample_hz: Option<f64>,
+    /// Rate limit pose output to maximum frequency (Hz)
+    rate_limit_hz: Option<f64>,
+    /// Robot config file passed to televoodoo as `--config`
+    config_file: Option<PathBuf>,
+    /// Prefix for this session's lines in the shared log file (defaults to the session id)
+    log_tag: Option<String>,
+    /// Backend niceness, -20 (highest) to 19 (lowest); clamped when out of range
+    priority: Option<i32>,
+    /// Packaged builds only: run the interpreter shipped in the app resources instead of
+    /// the runtime venv, to tell bundle problems from runtime-copy problems. The bundled
+    /// environment may be read-only, so nothing is installed or updated in this mode.
+    #[serde(default)]
+    prefer_bundled: bool,
+    /// Interpreter that already has televoodoo installed. Skips the repo/bundled lookup
+    /// and the runtime bootstrap entirely.
+    interpreter: Option<PathBuf>,
+    /// Robot profile passed to televoodoo as `--profile`
+    profile: Option<String>,
+    /// What the interpreter runs; defaults to `-m televoodoo`
+    #[serde(default)]
+    entry: EntryPoint,
+    /// Linux only: CPU cap in CPUs (e.g. 1.5)
+    cpu_quota: Option<f64>,
+    /// Linux only: memory cap in bytes
+    mem_limit: Option<u64>,
+    /// Run with administrator rights (see elevation); only set by start_python_elevated
+    #[serde(default, skip_deserializing)]
+    elevated: bool,
+    /// Keep the backend running when the viewer exits; the next start with the same
+    /// settings reattaches to it (see the detached module)
+    #[serde(default)]
+    detached: bool,
+    /// Stop the backend gracefully after this many seconds, emitting
+    /// `python-runtime-expired`; an auto-restart starts the clock again
+    max_runtime_secs: Option<u64>,
+}
+
+/// Backend entry point, e.g. `{ "module": "televoodoo" }` or `{ "script": "/path/run.py" }`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EntryPoint {
+    /// Run as `python -m <module>`
+    Module(String),
+    /// Run a script file as `python <path>`
+    Script(PathBuf),
+}
+
+impl Default for EntryPoint {
+    fn default() -> Self {
+        EntryPoint::Module("televoodoo".to_string())
+    }
+}
+
+impl EntryPoint {
+    /// Interpreter arguments that select the entry point
+    fn args(&self) -> Vec<std::ffi::OsString> {
+        match self {
+            EntryPoint::Module(module) => vec!["-m".into(), module.into()],
+            EntryPoint::Script(path) => vec![path.into()],
+        }
+    }
+
+    /// Modules must be dotted Python identifiers; scripts must exist
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            EntryPoint::Module(module) => {
+                let valid = module.split('.').all(|part| {
+                    part.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                        && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+                });
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid entry module name: {:?}", module))
+                }
+            }
+            EntryPoint::Script(path) if path.is_file() => Ok(()),
+            EntryPoint::Script(path) => Err(format!("Entry script not found: {}", path.display())),
+        }
+    }
+}
+
+impl StartConfig {
+    /// Appends the optional televoodoo CLI flags
+    fn apply_optional_args(&self, cmd: &mut Command) {
+        // Add optional name and code
+        if let Some(ref name) = self.name {
+            cmd.arg("--name").arg(name);
+        }
+        if let Some(ref code) = self.code {
+            cmd.arg("--code").arg(code);
+        }
+        // Add optional upsampling and rate limiting
+        if let Some(hz) = self.upsample_hz {
+            cmd.arg("--upsample-hz").arg(hz.to_string());
+        }
+        if let Some(hz) = self.rate_limit_hz {
+            cmd.arg("--rate-limit-hz").arg(hz.to_string());
+        }
+        if let Some(ref path) = self.config_file {
+            cmd.arg("--config").arg(path);
+        }
+        if let Some(ref profile) = self.profile {
+            cmd.arg("--profile").arg(profile);
+        }
+    }
+}
+
+/// Accepts profile names of up to 64 letters, digits, `-`, `_` and `.`
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name: {:?}", name))
+    }
+}
+
+/// Checks that a robot config file exists and can be opened for reading
+fn validate_config_file(path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("Robot config file not found: {}", path.display()));
+    }
+    std::fs::File::open(path)
+        .map(|_| ())
+        .map_err(|e| format!("Robot config file is not readable: {} ({})", path.display(), e))
+}
+
+#[tauri::command]
+async fn start_python<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: StartConfig) -> Result<(), PythonError> {
+    if START_IN_PROGRESS.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return Err("A start is already in progress".to_string().into());
+    }
+    // A manual start begins a fresh auto-restart budget
+    RESTART_ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    run_claimed_start(&app, config)
+}
+
+/// run_start for a caller that has set START_IN_PROGRESS, which is cleared once it returns
+fn run_claimed_start<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: StartConfig) -> Result<(), PythonError> {
+    bootstrap::reset_cancel();
+    let result = run_start(app, config);
+    START_IN_PROGRESS.store(false, Ordering::SeqCst);
+
+    // A cancel that lands after the spawn must not leave the new child running
+    if bootstrap::is_cancelled() {
+        if result.is_ok() {
+            cleanup_python();
+        }
+        let _ = app.emit("python-start-cancelled", ());
+        return Err(PythonError::Cancelled);
+    }
+    result
+}
+
+/// Starts the backend with administrator rights, after a platform password prompt. See
+/// the elevation module for what this exposes and where it is supported.
+#[tauri::command]
+async fn start_python_elevated<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: StartConfig) -> Result<(), PythonError> {
+    start_python(app, StartConfig { elevated: true, ..config }).await
+}
+
+/// Starts the backend only if it is not already running (or starting). Returns whether a
+/// new process was started, so reconnection timers can call it unconditionally.
+#[tauri::command]
+async fn ensure_running<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: StartConfig) -> Result<bool, PythonError> {
+    // Checked and claimed in one step, so two concurrent calls can't both start one
+    if backend_running() || START_IN_PROGRESS.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return Ok(false);
+    }
+    if backend_running() {
+        START_IN_PROGRESS.store(false, Ordering::SeqCst);
+        return Ok(false);
+    }
+    RESTART_ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    run_claimed_start(&app, config).map(|()| true)
+}
+
+/// Cancels an in-progress start_python: kills any running bootstrap step, discards a
+/// partially built venv, and stops the backend if it already spawned.
+/// Returns false when no start was in progress.
+#[tauri::command]
+fn cancel_start() -> bool {
+    if !START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return false;
+    }
+    bootstrap::request_cancel();
+    true
+}
+
+fn run_start<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: StartConfig) -> Result<(), PythonError> {
+    self_repair::wait_idle();
+    start_timings::begin();
+    validate_start(&config)?;
+    if let Some(ref profile) = config.profile {
+        let _ = config::update(app, |c| c.last_profile = Some(profile.clone()));
+    }
+    if let Some(ref path) = config.config_file {
+        // Remember it so the UI can prefill the next launch
+        let _ = config::update(app, |c| c.last_config_file = Some(path.clone()));
+    }
+    instance_lock::acquire(app)?;
+    match detached_to_reattach(app, &config) {
+        Ok(Some(state)) => {
+            reattach(app, state);
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            instance_lock::release();
+            return Err(e);
+        }
+    }
+    let result = build_command(app, &config, None).and_then(|cmd| {
+        // Recorded before a resource-limit wrapper can replace the program
+        let interpreter = PathBuf::from(cmd.get_program());
+        let encoding = io_encoding(&cmd);
+        let cmd = resource_limits::apply(app, cmd, config.cpu_quota, config.mem_limit);
+        elevation::clear();
+        let cmd = if config.elevated { elevation::wrap(app, &cmd)? } else { cmd };
+        // Cleanup for a previous backend that was replaced without going through stop
+        hooks::run_post_exit(app);
+        hooks::run_pre_launch(app)?;
+        let output = spawn_backend(app, cmd, config.log_tag.clone(), config.priority, config.detached)?;
+        if let Some(output) = output {
+            let pid = PYTHON_CHILD.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|c| c.id()).unwrap_or(0);
+            let state = detached::State {
+                pid,
+                token: output.token,
+                started_at_ms: detached::now_ms(),
+                stdout: output.stdout,
+                stderr: output.stderr,
+                interpreter: Some(interpreter.clone()),
+                config: serde_json::to_value(&config).unwrap_or_default(),
+            };
+            if let Err(e) = detached::save(app, &state) {
+                logs::forward_tool_line(app, "detached", Stream::Stderr, &format!("WARNING: the backend won't be reattached after a restart: {}", e));
+            }
+        }
+        hooks::backend_spawned();
+        *LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()) = Some(interpreter);
+        *LAUNCH_ENCODING.lock().unwrap_or_else(|e| e.into_inner()) = encoding;
+        Ok(())
+    });
+    if result.is_err() && !backend_running() {
+        instance_lock::release();
+    }
+    result?;
+    if let Some(max_runtime_secs) = config.max_runtime_secs {
+        let generation = GENERATION.load(Ordering::SeqCst);
+        let app = app.clone();
+        std::thread::spawn(move || enforce_max_runtime(&app, generation, max_runtime_secs));
+    }
+    let pid = backend_pid();
+    let interpreter = LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let encoding = LAUNCH_ENCODING.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let _ = app.emit("python-spawned", Spawned { pid, interpreter: interpreter.clone(), entry: config.entry.clone(), encoding });
+    if let Some(python) = interpreter {
+        // pip freeze takes a while; don't hold up the start for it
+        let app = app.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = env_snapshots::take(&app, &python) {
+                logs::forward_tool_line(&app, "snapshot", Stream::Stderr, &format!("WARNING: environment snapshot failed: {}", e));
+            }
+        });
+    }
+    *LAST_START.lock().unwrap_or_else(|e| e.into_inner()) = Some(config);
+    Ok(())
+}
+
+/// Payload of `python-runtime-expired`
+#[derive(Clone, serde::Serialize)]
+struct RuntimeExpired {
+    pid: Option<u32>,
+    max_runtime_secs: u64,
+}
+
+/// Stops the backend of `generation` once it has run `max_runtime_secs`. Ends without
+/// stopping anything when that backend exits, is stopped or is replaced by another start
+/// (which arms its own timer).
+fn enforce_max_runtime<R: tauri::Runtime>(app: &tauri::AppHandle<R>, generation: u64, max_runtime_secs: u64) {
+    let deadline = Instant::now() + Duration::from_secs(max_runtime_secs);
+    let current = || GENERATION.load(Ordering::SeqCst) == generation && !STOP_REQUESTED.load(Ordering::SeqCst) && backend_running();
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(250).min(deadline.saturating_duration_since(Instant::now())));
+        if !current() {
+            return;
+        }
+    }
+    let pid = backend_pid();
+    logs::forward_tool_line(app, "runtime", Stream::Stdout, &format!("Maximum runtime of {} s reached; stopping the backend", max_runtime_secs));
+    system_log::log(Level::Info, &format!("backend reached its maximum runtime of {} s", max_runtime_secs));
+    run_stop(app);
+    let _ = app.emit("python-runtime-expired", RuntimeExpired { pid, max_runtime_secs });
+}
+
+/// Checks a start config before anything runs
+fn validate_start(config: &StartConfig) -> Result<(), PythonError> {
+    config.entry.validate()?;
+    if let Some(ref tag) = config.log_tag {
+        logs::validate_tag(tag)?;
+    }
+    if let Some(ref profile) = config.profile {
+        validate_profile_name(profile)?;
+    }
+    if let Some(ref path) = config.config_file {
+        validate_config_file(path)?;
+    }
+    resource_limits::validate(config.cpu_quota, config.mem_limit)?;
+    if config.max_runtime_secs == Some(0) {
+        return Err(PythonError::Other("max_runtime_secs must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+/// What start_python would do with `config`: validation, reattach, bootstrap, update and
+/// the resolved command, without running any of it (interpreters may be probed for their
+/// version)
+fn plan_start<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: &StartConfig) -> Result<launch_plan::LaunchPlan, PythonError> {
+    use launch_plan::LaunchStep;
+
+    validate_start(config)?;
+    let mut plan = launch_plan::LaunchPlan::default();
+    if let Some(state) = detached_to_reattach(app, config)? {
+        plan.reattach = true;
+        plan.interpreter = state.interpreter.unwrap_or_default();
+        plan.steps = vec![LaunchStep::Reattach];
+        return Ok(plan);
+    }
+    let cmd = build_command(app, config, Some(&mut plan))?;
+    plan.describe(&cmd);
+    if plan.bootstrap {
+        plan.steps.push(LaunchStep::Bootstrap);
+    }
+    if plan.package_update {
+        plan.steps.push(LaunchStep::PackageUpdate);
+    }
+    if config.cpu_quota.is_some() || config.mem_limit.is_some() {
+        plan.steps.push(LaunchStep::ResourceLimits);
+    }
+    if config::get(app).pre_launch.is_some_and(|argv| !argv.is_empty()) {
+        plan.steps.push(LaunchStep::PreLaunch);
+    }
+    plan.steps.push(LaunchStep::Spawn);
+    Ok(plan)
+}
+
+/// Payload of `python-spawned`
+#[derive(Clone, serde::Serialize)]
+struct Spawned {
+    pid: Option<u32>,
+    interpreter: Option<PathBuf>,
+    entry: EntryPoint,
+    /// PYTHONIOENCODING of the backend; None means Python picks it from the locale
+    encoding: Option<String>,
+}
+
+/// Resolves the interpreter (bootstrapping the runtime venv when packaged, and seeing
+/// through version-manager shims), working dir, environment and argv for the backend,
+/// without spawning it. With a `plan`, nothing is bootstrapped, updated or installed; what
+/// would be is recorded in the plan instead.
+fn build_command<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: &StartConfig, plan: Option<&mut launch_plan::LaunchPlan>) -> Result<Command, PythonError> {
+    let planning = plan.is_some();
+    let mut cmd = resolve_command(app, config, plan)?;
+    let viewer_config = config::get(app);
+    if viewer_config.neutralize_python_env {
+        env_conflicts::neutralize(&mut cmd);
+    }
+    // A pipe makes Python block-buffer stdout, so lines would arrive in delayed bursts.
+    // Set before env_file is merged, so the toggle wins over it either way.
+    if viewer_config.python_unbuffered {
+        cmd.env("PYTHONUNBUFFERED", "1");
+    } else {
+        cmd.env_remove("PYTHONUNBUFFERED");
+    }
+    if let Some(ref path) = viewer_config.env_file {
+        merge_env_file(app, &mut cmd, path)?;
+    }
+    if let Some(level) = viewer_config.log_level {
+        cmd.env(BACKEND_LOG_LEVEL_ENV, level.as_str());
+    }
+    if viewer_config.debug_alloc {
+        if !planning {
+            logs::forward_tool_line(app, "debug-alloc", Stream::Stderr, "WARNING: debug allocators enabled (debug_alloc); the backend runs slower and uses more memory");
+        }
+        for (key, value) in DEBUG_ALLOC_ENV {
+            cmd.env(key, value);
+        }
+    }
+    // Without this, stdout follows the locale and non-ASCII log lines arrive garbled on
+    // systems with a non-UTF-8 one
+    if let Some(encoding) = viewer_config.python_io_encoding.filter(|e| !e.trim().is_empty()) {
+        let lower = encoding.to_ascii_lowercase();
+        if lower.starts_with("utf-8") || lower.starts_with("utf8") {
+            cmd.env("PYTHONUTF8", "1");
+        }
+        cmd.env("PYTHONIOENCODING", encoding);
+    }
+    // Spawn what a pyenv-style shim would exec, so the pid we track is the backend's
+    Ok(shim::resolve(app, cmd, planning))
+}
+
+/// Adds the variables of the `.env` file at `path` that `cmd` doesn't set already.
+/// Unparsable lines are skipped with a warning; an unreadable file fails the start.
+fn merge_env_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: &mut Command, path: &Path) -> Result<(), String> {
+    let env = dotenv::load(path)?;
+    for (line, reason) in &env.skipped {
+        logs::forward_tool_line(app, "dotenv", Stream::Stderr, &format!("WARNING: {}:{} skipped: {}", path.display(), line, reason));
+    }
+    let explicit: Vec<std::ffi::OsString> = cmd.get_envs().map(|(key, _)| key.to_os_string()).collect();
+    for (key, value) in env.vars {
+        if !explicit.iter().any(|k| *k == *key) {
+            cmd.env(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// PYTHONIOENCODING the backend will see: set on `cmd`, else inherited from the viewer
+fn io_encoding(cmd: &Command) -> Option<String> {
+    match cmd.get_envs().find(|(key, _)| *key == "PYTHONIOENCODING") {
+        Some((_, value)) => value.map(|v| v.to_string_lossy().to_string()),
+        None => std::env::var("PYTHONIOENCODING").ok(),
+    }
+}
+
+/// Verbosity read by televoodoo itself, set from the viewer's log_level
+const BACKEND_LOG_LEVEL_ENV: &str = "TELEVOODOO_LOG_LEVEL";
+
+/// Set with debug_alloc. PYTHONMALLOC=debug installs Python's allocator debug hooks
+/// (buffer over/underflow and API misuse checks) and PYTHONTRACEMALLOC keeps allocation
+/// tracebacks for the reports. The C allocator variables are read by glibc (Linux) and
+/// libmalloc (macOS) and ignored elsewhere.
+const DEBUG_ALLOC_ENV: &[(&str, &str)] = &[
+    ("PYTHONMALLOC", "debug"),
+    ("PYTHONTRACEMALLOC", "10"),
+    // Abort with a diagnostic on heap corruption; fill freed memory with a pattern
+    ("MALLOC_CHECK_", "3"),
+    ("MALLOC_PERTURB_", "165"),
+    ("MallocScribble", "1"),
+    ("MallocGuardEdges", "1"),
+];
+
+/// The repo's `python` dir, resolved at compile time from the src-tauri dir
+fn repo_python_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("python")
+}
+
+/// The repo's `python` dir in a dev build whose checkout has the televoodoo source in
+/// place. None selects the packaged layout, also in a dev build that doesn't run from a
+/// complete checkout.
+fn dev_python_dir() -> Option<PathBuf> {
+    let dir = repo_python_dir();
+    (cfg!(debug_assertions) && dir.join("televoodoo").join("src").is_dir()).then_some(dir)
+}
+
+/// Payload of `python-launch-mode`
+#[derive(Clone, serde::Serialize)]
+struct LaunchMode {
+    /// Running from the repo checkout rather than the packaged layout
+    dev: bool,
+    /// Repo or bundled python dir the backend comes from, if found
+    python_dir: Option<PathBuf>,
+}
+
+/// Interpreter, working dir, launch-mode specific environment and argv for build_command
+fn resolve_command<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: &StartConfig, plan: Option<&mut launch_plan::LaunchPlan>) -> Result<Command, PythonError> {
+    let plan_only = plan.is_some();
+    if let Some(ref python) = config.interpreter {
+        if !plan_only {
+            if let Some(warning) = preflight::interpreter_arch(python).warning {
+                logs::forward_tool_line(app, "arch", Stream::Stderr, &format!("WARNING: {}", warning));
+            }
+        }
+        let mut cmd = Command::new(python);
+        cmd.args(config.entry.args())
+            .arg("--connection").arg(&config.connection);
+        config.apply_optional_args(&mut cmd);
+        bootstrap::activate_venv(&mut cmd);
+        return Ok(cmd);
+    }
+
+    // In dev builds, run directly from the repo's python dir and venv
+    let dev_dir = dev_python_dir();
+    if cfg!(debug_assertions) && dev_dir.is_none() && !plan_only {
+        // E.g. a debug binary moved out of the checkout, or a checkout without the submodule
+        logs::forward_tool_line(app, "launch", Stream::Stderr, &format!(
+            "WARNING: dev build but no televoodoo source under {}; using the packaged layout",
+            repo_python_dir().display()
+        ));
+    }
+    if !plan_only {
+        let _ = app.emit("python-launch-mode", LaunchMode { dev: dev_dir.is_some(), python_dir: dev_dir.clone().or_else(|| find_bundled_python_dir(app)) });
+    }
+    if let Some(python_dir) = dev_dir {
+        if let Some(plan) = plan {
+            plan.dev = true;
+        }
+        let dev_python = python_dir.join(".venv").join("bin").join("python");
+        let python = if dev_python.exists() {
+            dev_python.to_string_lossy().to_string()
+        } else {
+            bootstrap::system_python(app)
+        };
+
+        let televoodoo_dir = python_dir.join("televoodoo");
+
+        let mut cmd = Command::new(&python);
+        cmd.args(config.entry.args())
+            .arg("--connection").arg(&config.connection)
+            .current_dir(&televoodoo_dir);
+        launch_pythonpath(app, false, false).apply(&mut cmd);
+        
+        config.apply_optional_args(&mut cmd);
+
+        // Ensure pyobjc on macOS for dev
+        #[cfg(target_os = "macos")]
+        if !plan_only {
+            if let Ok(status) = Command::new(&cmd.get_program())
+                .args(["-c", "import objc"]) // simple import test
+                .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
+                .status()
+            {
+                if !status.success() {
+                    let _ = Command::new(&cmd.get_program())
+                        .args(["-m", "pip", "install", "pyobjc"])
+                        .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
+                        .status();
+                }
+            }
+        }
+
+        // AppImage and some launchers may inject Python-related env vars that break venvs.
+        // For dev, we KEEP PYTHONPATH (we set it above) but sanitize the rest.
+        cmd.env_remove("PYTHONHOME")
+            .env_remove("PYTHONEXECUTABLE")
+            .env_remove("PYTHONUSERBASE");
+        bootstrap::activate_venv(&mut cmd);
+
+        return Ok(cmd);
+    }
+
+    let bundled_python = if config.prefer_bundled {
+        Some(bootstrap::bundled_python(app).ok_or_else(|| "No working bundled python interpreter found in app resources".to_string())?)
+    } else {
+        None
+    };
+    let (python, source) = match bundled_python {
+        Some(ref python) => (python.to_string_lossy().to_string(), PythonSource::Bundled),
+        None if plan_only => {
+            let (bootstrap, runtime_python) = bootstrap::runtime_python_plan(app);
+            if let Some(plan) = plan {
+                plan.bootstrap = bootstrap;
+                // A fresh bootstrap installs the current bundle
+                plan.package_update = !bootstrap && update::auto_update_due(app, &update::check(app));
+            }
+            match runtime_python {
+                Some(python) => (python.to_string_lossy().to_string(), PythonSource::Runtime),
+                None => (bootstrap::system_python(app), PythonSource::System),
+            }
+        }
+        None => {
+            // Always prefer a runtime venv under App Support and bootstrap it from bundled Resources if missing.
+            start_timings::mark(start_timings::Mark::BootstrapStart);
+            let runtime_python = bootstrap::ensure_runtime_python(app)?;
+            start_timings::mark(start_timings::Mark::BootstrapEnd);
+            // Pick up a newer televoodoo shipped with an app update
+            update::auto_update(app);
+            match runtime_python {
+                Some(python) => (python.to_string_lossy().to_string(), PythonSource::Runtime),
+                None => (bootstrap::system_python(app), PythonSource::System),
+            }
+        }
+    };
+
+    let mut cmd = Command::new(python);
+    cmd.args(config.entry.args())
+        .arg("--connection").arg(&config.connection);
+    
+    config.apply_optional_args(&mut cmd);
+
+    let bundled_py_dir = find_bundled_python_dir(app);
+    let runtime_py_dir = bootstrap::runtime_python_dir(app);
+    if let Some(cwd) = backend_cwd(source, bundled_py_dir.as_deref(), runtime_py_dir.as_deref()) {
+        cmd.current_dir(cwd);
+    }
+
+    // Only ensure pyobjc on macOS; Linux must not try to install it. The bundled
+    // environment may be read-only and is never installed into (see prefer_bundled).
+    #[cfg(target_os = "macos")]
+    if !plan_only && source != PythonSource::Bundled {
+        if let Ok(status) = Command::new(&cmd.get_program())
+            .args(["-c", "import objc"]) // simple import test
+            .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
+            .status()
+        {
+            if !status.success() {
+                let _ = Command::new(&cmd.get_program())
+                    .args(["-m", "pip", "install", "pyobjc"])
+                    .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
+                    .status();
+            }
+        }
+    }
+
+    // Clean up Python-related env vars that AppImage sets (PYTHONHOME, PYTHONPATH, ...)
+    cmd.env_remove("PYTHONHOME")
+        .env_remove("PYTHONEXECUTABLE")
+        .env_remove("PYTHONUSERBASE");
+    launch_pythonpath(app, false, bundled_python.is_some()).apply(&mut cmd);
+    bootstrap::activate_venv(&mut cmd);
+
+    Ok(cmd)
+}
+
+/// PYTHONPATH resolve_command gives the backend
+enum LaunchPythonPath {
+    /// An explicit interpreter keeps the viewer's (or env_file's) value
+    Inherit,
+    Set(PathBuf),
+    /// Removed, so a launcher-injected value can't shadow the venv's packages
+    Clear,
+}
+
+impl LaunchPythonPath {
+    fn apply(&self, cmd: &mut Command) {
+        match self {
+            LaunchPythonPath::Inherit => {}
+            LaunchPythonPath::Set(path) => {
+                cmd.env("PYTHONPATH", path);
+            }
+            LaunchPythonPath::Clear => {
+                cmd.env_remove("PYTHONPATH");
+            }
+        }
+    }
+}
+
+/// The dev build imports televoodoo from the repo source and prefer_bundled from the
+/// bundled source (it is not pip-installed there); the runtime venv has it installed
+fn launch_pythonpath<R: tauri::Runtime>(app: &tauri::AppHandle<R>, explicit_interpreter: bool, prefer_bundled: bool) -> LaunchPythonPath {
+    if explicit_interpreter {
+        return LaunchPythonPath::Inherit;
+    }
+    if let Some(dir) = dev_python_dir() {
+        return LaunchPythonPath::Set(dir.join("televoodoo").join("src"));
+    }
+    match find_bundled_python_dir(app).filter(|_| prefer_bundled) {
+        Some(dir) => LaunchPythonPath::Set(dir.join("televoodoo").join("src")),
+        None => LaunchPythonPath::Clear,
+    }
+}
+
+/// PYTHONPATH entries the backend would start with, in order, resolved and without
+/// duplicates. Follows the same rules as a start with the options of the last one:
+/// resolve_command's value, else (explicit interpreter only) env_file's, else the viewer's.
+fn backend_pythonpath<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<PathBuf> {
+    let (explicit_interpreter, prefer_bundled) = LAST_START
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or((false, false), |c| (c.interpreter.is_some(), c.prefer_bundled));
+    let value = match launch_pythonpath(app, explicit_interpreter, prefer_bundled) {
+        LaunchPythonPath::Set(path) => Some(path.into_os_string()),
+        LaunchPythonPath::Clear => None,
+        LaunchPythonPath::Inherit => config::get(app)
+            .env_file
+            .and_then(|path| dotenv::load(&path).ok())
+            .and_then(|env| env.vars.into_iter().rev().find(|(key, _)| key == "PYTHONPATH"))
+            .map(|(_, value)| value.into())
+            .or_else(|| std::env::var_os("PYTHONPATH")),
+    };
+    // Python skips empty entries; relative ones are relative to the backend's working dir,
+    // which for an explicit interpreter is the viewer's
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut entries: Vec<PathBuf> = Vec::new();
+    for entry in value.iter().flat_map(std::env::split_paths).filter(|p| !p.as_os_str().is_empty()) {
+        let entry = cwd.join(entry);
+        let entry = entry.canonicalize().unwrap_or(entry);
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// Where the packaged backend's interpreter came from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PythonSource {
+    /// The venv shipped in the app resources (prefer_bundled)
+    Bundled,
+    /// The runtime venv under the app data dir
+    Runtime,
+    /// A system interpreter, when no venv is available
+    System,
+}
+
+/// Working dir for the packaged backend: the televoodoo copy that belongs to the chosen
+/// interpreter, so the runtime venv never runs against the bundled source or vice versa.
+/// A system interpreter has no copy of its own and uses whichever exists, bundled first.
+fn backend_cwd(source: PythonSource, bundled_py_dir: Option<&Path>, runtime_py_dir: Option<&Path>) -> Option<PathBuf> {
+    let candidates = match source {
+        PythonSource::Bundled => [bundled_py_dir, None],
+        PythonSource::Runtime => [runtime_py_dir, None],
+        PythonSource::System => [bundled_py_dir, runtime_py_dir],
+    };
+    candidates
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("televoodoo"))
+        .find(|dir| dir.join("pyproject.toml").exists())
+}
+
+/// Interpreter of the venv the backend runs in: the repo venv in dev builds,
+/// the app-data runtime venv when packaged
+fn venv_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    let python = if let Some(dir) = dev_python_dir() {
+        dir.join(".venv").join("bin").join("python")
+    } else {
+        bootstrap::runtime_python_dir(app)
+            .ok_or_else(|| "Could not determine app data directory".to_string())?
+            .join(".venv")
+            .join("bin")
+            .join("python")
+    };
+    if !python.exists() {
+        return Err(format!("Runtime venv python not found at: {}", python.display()));
+    }
+    Ok(python)
+}
+
+// pip flags that may be passed alongside a package to install_package
+const ALLOWED_PIP_FLAGS: &[&str] = &["--upgrade", "-U", "--pre", "--no-deps", "--force-reinstall"];
+
+/// Accepts a requirement such as `pyserial`, `pyserial==3.5` or `foo[bar]>=1.0`; rejects
+/// anything that could be read as a pip option or contains shell/path metacharacters
+fn validate_package_spec(spec: &str) -> Result<(), String> {
+    let starts_ok = spec.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+    let chars_ok = spec
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "._-[],<>=!~".contains(c));
+    if !starts_ok || !chars_ok {
+        return Err(format!("Invalid package specifier: {:?}", spec));
+    }
+    Ok(())
+}
+
+/// Payload of `python-package-install-done` / `python-package-install-failed`
+#[derive(Clone, serde::Serialize)]
+struct PackageInstallResult {
+    name: String,
+    error: Option<String>,
+}
+
+/// Installs a package into the backend's venv, streaming pip output as `python-log`
+/// lines tagged "pip"
+#[tauri::command]
+async fn install_package<R: tauri::Runtime>(app: tauri::AppHandle<R>, name: String, flags: Option<Vec<String>>) -> Result<(), String> {
+    validate_package_spec(&name)?;
+    let flags = flags.unwrap_or_default();
+    if let Some(bad) = flags.iter().find(|f| !ALLOWED_PIP_FLAGS.contains(&f.as_str())) {
+        return Err(format!("pip flag not allowed: {}", bad));
+    }
+    let python = venv_python(&app)?;
+
+    let mut cmd = Command::new(&python);
+    cmd.args(["-m", "pip", "install"])
+        .args(config::get(&app).pip_network_args())
+        .args(&flags)
+        .arg(&name);
+    let result = match logs::run_streamed(&app, &mut cmd, "pip") {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("pip install {} failed with {}", name, status)),
+        Err(e) => Err(format!("Could not run pip: {}", e)),
+    };
+
+    match &result {
+        Ok(()) => {
+            let _ = app.emit("python-package-install-done", PackageInstallResult { name, error: None });
+        }
+        Err(e) => {
+            let _ = app.emit("python-package-install-failed", PackageInstallResult { name, error: Some(e.clone()) });
+        }
+    }
+    result
+}
+
+/// Output kept per stream by run_python_snippet
+const SNIPPET_OUTPUT_LIMIT: usize = 64 * 1024;
+/// How long run_python_snippet waits for its output readers after the snippet ended
+const SNIPPET_READER_GRACE: Duration = Duration::from_millis(500);
+
+/// Returned by run_python_snippet
+#[derive(Clone, serde::Serialize)]
+struct SnippetResult {
+    stdout: String,
+    stderr: String,
+    /// None when killed by a signal or the timeout
+    exit_code: Option<i32>,
+    timed_out: bool,
+    duration_ms: u64,
+}
+
+/// Reads `pipe` to EOF on a thread, keeping the first SNIPPET_OUTPUT_LIMIT bytes in the
+/// returned buffer as they arrive
+fn read_capped<T: std::io::Read + Send + 'static>(pipe: Option<T>) -> (std::thread::JoinHandle<()>, std::sync::Arc<Mutex<Vec<u8>>>) {
+    let kept = std::sync::Arc::new(Mutex::new(Vec::new()));
+    let buffer = kept.clone();
+    let reader = std::thread::spawn(move || {
+        if let Some(mut pipe) = pipe {
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                let mut kept = kept.lock().unwrap_or_else(|e| e.into_inner());
+                let room = SNIPPET_OUTPUT_LIMIT.saturating_sub(kept.len());
+                kept.extend_from_slice(&chunk[..n.min(room)]);
+            }
+        }
+    });
+    (reader, buffer)
+}
+
+/// Runs `<venv python> -c <code>` for diagnostics (e.g. "does torch see the GPU") and
+/// returns its output, killing it after `timeout_ms` (max 5 min). `code` goes straight into
+/// argv, never through a shell. Only available in dev builds or with the
+/// `allow_python_snippets` config opt-in, since it runs arbitrary code.
+#[tauri::command]
+async fn run_python_snippet<R: tauri::Runtime>(app: tauri::AppHandle<R>, code: String, timeout_ms: u64) -> Result<SnippetResult, String> {
+    if !cfg!(debug_assertions) && !config::get(&app).allow_python_snippets {
+        return Err("Running python snippets is disabled; enable allow_python_snippets in the config".to_string());
+    }
+    let python = venv_python(&app)?;
+    let timeout = Duration::from_millis(timeout_ms.min(300_000));
+    let started = Instant::now();
+    let mut cmd = Command::new(&python);
+    cmd.arg("-c").arg(&code).env("PYTHONUNBUFFERED", "1").stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    // Own process group, so the timeout also reaches processes the snippet started
+    #[cfg(unix)]
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setpgid(0, 0);
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn().map_err(|e| format!("Could not run {}: {}", python.display(), e))?;
+
+    // Read both pipes concurrently so a chatty snippet can't block on a full pipe
+    let stdout = read_capped(child.stdout.take());
+    let stderr = read_capped(child.stderr.take());
+
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                timed_out = true;
+                #[cfg(unix)]
+                unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) };
+                let _ = child.kill();
+                break child.wait().ok();
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Could not wait for snippet: {}", e)),
+        }
+    };
+    // Something the snippet left running may still hold the pipes open; past the grace
+    // period the readers are detached and what they have read so far is returned
+    let deadline = Instant::now() + SNIPPET_READER_GRACE;
+    while !(stdout.0.is_finished() && stderr.0.is_finished()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let text = |kept: &std::sync::Arc<Mutex<Vec<u8>>>| String::from_utf8_lossy(&kept.lock().unwrap_or_else(|e| e.into_inner())).to_string();
+    Ok(SnippetResult {
+        stdout: text(&stdout.1),
+        stderr: text(&stderr.1),
+        exit_code: status.and_then(|s| s.code()).filter(|_| !timed_out),
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Runs the backend's self-test (`<entry> --selftest`, entry from the last start) with the
+/// venv python, streaming its output and parsing the pass/fail summary. Times out after
+/// `timeout_ms` (default 60 s, max 10 min). A backend without the flag reports
+/// `supported: false` instead of an error.
+#[tauri::command]
+async fn run_selftest<R: tauri::Runtime>(app: tauri::AppHandle<R>, timeout_ms: Option<u64>) -> Result<selftest::SelfTestResult, String> {
+    let python = venv_python(&app)?;
+    let entry = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|c| c.entry.clone()).unwrap_or_default();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(60_000).min(600_000));
+    selftest::run(&app, &python, &entry, timeout)
+}
+
+/// Bytes and lines per second read from the backend's pipes, plus queued and dropped
+/// line counts, as of the last `python-throughput` interval
+#[tauri::command]
+fn log_throughput() -> throughput::ThroughputStats {
+    throughput::latest()
+}
+
+/// Checks that a device is usable before a session: a device path (`/dev/ttyUSB0`,
+/// `COM3`) is opened, `host:port` connected to, and other ids are checked by the backend
+#[tauri::command]
+async fn check_device(path_or_id: String) -> device::DeviceStatus {
+    device::check(&path_or_id, backend_running())
+}
+
+/// Sets how many rotated backend logs are kept (1-1000) and their maximum age in days
+/// (0-3650, 0 for no limit), then prunes right away. Returns the number of files deleted.
+#[tauri::command]
+fn set_log_retention<R: tauri::Runtime>(app: tauri::AppHandle<R>, max_files: u32, max_age_days: u32) -> Result<usize, String> {
+    if !log_rotation::MAX_FILES_RANGE.contains(&max_files) {
+        return Err(format!("max_files must be between 1 and 1000, got {}", max_files));
+    }
+    if !log_rotation::MAX_AGE_DAYS_RANGE.contains(&max_age_days) {
+        return Err(format!("max_age_days must be between 0 and 3650, got {}", max_age_days));
+    }
+    config::update(&app, |c| {
+        c.log_max_files = max_files;
+        c.log_max_age_days = max_age_days;
+    })?;
+    Ok(log_rotation::prune_and_report(&app))
+}
+
+/// Checks the runtime venv, source copy and architecture now and repairs what it can;
+/// the same report startup_self_repair emits at launch
+#[tauri::command]
+async fn run_self_repair<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<self_repair::RepairReport, String> {
+    if dev_python_dir().is_some() {
+        return Err("Dev builds run from the repo checkout; there is no runtime copy to repair".to_string());
+    }
+    if backend_running() || START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Err("Stop the backend before repairing its runtime".to_string());
+    }
+    Ok(self_repair::run(&app))
+}
+
+/// Enables or disables quarantining broken runtime venvs instead of deleting them, and
+/// how many (1-20) and how much (at least 100 MB) to keep
+#[tauri::command]
+fn set_venv_quarantine<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool, keep: Option<u32>, max_bytes: Option<u64>) -> Result<(), String> {
+    if let Some(keep) = keep.filter(|k| !(1..=20).contains(k)) {
+        return Err(format!("Quarantine keep must be between 1 and 20, got {}", keep));
+    }
+    if let Some(bytes) = max_bytes.filter(|b| *b < 100 * 1024 * 1024) {
+        return Err(format!("Quarantine size limit must be at least 100 MB, got {} bytes", bytes));
+    }
+    config::update(&app, |c| {
+        c.quarantine_venvs = enabled;
+        c.quarantine_keep = keep.unwrap_or(c.quarantine_keep);
+        c.quarantine_max_bytes = max_bytes.unwrap_or(c.quarantine_max_bytes);
+    })
+}
+
+/// Broken runtime venvs kept by quarantine, newest first
+#[tauri::command]
+fn list_quarantined<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<quarantine::Quarantined> {
+    quarantine::list(&app)
+}
+
+/// Deletes all quarantined venvs and returns how many there were
+#[tauri::command]
+fn clear_quarantine<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<usize, String> {
+    quarantine::clear(&app)
+}
+
+/// Enables or disables the check-and-repair at app launch (packaged builds)
+#[tauri::command]
+fn set_startup_self_repair<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    config::update(&app, |c| c.startup_self_repair = enabled)
+}
+
+/// Serves the log as server-sent events on 127.0.0.1:`port` (0 picks a free port) for
+/// external tools; see log_http for the token it requires. Runs until disable_log_http
+/// or stop_python; restarts keep it up.
+#[tauri::command]
+fn enable_log_http(port: u16) -> Result<log_http::LogHttpInfo, String> {
+    log_http::enable(port)
+}
+
+#[tauri::command]
+fn disable_log_http() {
+    log_http::disable();
+}
+
+/// Port, token and URL of the running log stream server, if any
+#[tauri::command]
+fn log_http_status() -> Option<log_http::LogHttpInfo> {
+    log_http::status()
+}
+
+/// Content digest of the bundled televoodoo source; auto-update reinstalls when it differs
+/// from the one last installed, even without a version bump
+#[tauri::command]
+fn bundle_fingerprint<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<String, String> {
+    update::bundle_fingerprint(&app).ok_or_else(|| "No bundled televoodoo package found".to_string())
+}
+
+/// Parsed entries of the requirements.txt bootstrap installs (the repo's in dev builds,
+/// the bundled one when packaged), with `-r` includes expanded
+#[tauri::command]
+fn parse_requirements<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<Vec<requirements::Requirement>, String> {
+    let python_dir = dev_python_dir().or_else(|| bootstrap::find_bundled_python_dir(&app));
+    let req = python_dir.ok_or_else(|| "Bundled python directory not found".to_string())?.join("televoodoo").join("requirements.txt");
+    if !req.exists() {
+        return Err(format!("No requirements file at {}", req.display()));
+    }
+    requirements::parse_file(&req)
+}
+
+/// Starts a read-only observer backend next to the operator's (see monitor) and returns
+/// its pid. `config` defaults to the last start's.
+#[tauri::command]
+async fn start_monitor<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: Option<StartConfig>) -> Result<u32, PythonError> {
+    let config = config
+        .or_else(|| LAST_START.lock().unwrap_or_else(|e| e.into_inner()).clone())
+        .ok_or_else(|| PythonError::Other("No start config; start the backend first or pass one".to_string()))?;
+    monitor::start(&app, config)
+}
+
+/// Stops the monitor backend; false when none was running
+#[tauri::command]
+async fn stop_monitor() -> bool {
+    monitor::stop()
+}
+
+/// Options of the backend's `--help`, parsed for a launch-options form; `raw` carries
+/// the help text when parsing was incomplete
+#[tauri::command]
+async fn backend_help<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<cli_help::BackendHelp, String> {
+    let python = venv_python(&app)?;
+    let entry = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|c| c.entry.clone()).unwrap_or_default();
+    cli_help::run(&python, &entry)
+}
+
+fn backend_running() -> bool {
+    let child_running = match PYTHON_CHILD.lock() {
+        Ok(mut guard) => matches!(guard.as_mut().map(|c| c.try_wait()), Some(Ok(None))),
+        Err(_) => false,
+    };
+    child_running || detached::attached_alive()
+}
+
+/// Pid of the current backend: our child, or a reattached detached backend
+fn backend_pid() -> Option<u32> {
+    PYTHON_CHILD.lock().ok().and_then(|g| g.as_ref().map(|c| c.id())).or_else(detached::attached_pid)
+}
+
+/// Mutes or unmutes UI events for backend stdout/stderr without restarting. Muted lines
+/// still go to the log file.
+#[tauri::command]
+fn set_stream_forwarding(stdout: bool, stderr: bool) {
+    logs::set_forwarding(stdout, stderr);
+}
+
+/// Exit code, time and stderr tail of the last crash, if the backend has crashed since
+/// the last successful start
+#[tauri::command]
+fn get_last_crash() -> Option<CrashInfo> {
+    LAST_CRASH.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Writes a shell script (batch file on Windows) that launches the backend exactly as
+/// start_python would: same interpreter, working dir, environment and arguments.
+/// Resolving the interpreter may bootstrap the runtime venv first.
+#[tauri::command]
+async fn export_launch_script<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: StartConfig) -> Result<PathBuf, PythonError> {
+    let cmd = build_command(&app, &config, None)?;
+    Ok(launch_script::write(&app, &cmd)?)
+}
+
+/// Resolves what start_python would do with `config` (interpreter, argv, working dir,
+/// environment overrides, whether it would bootstrap or update, and the steps in order)
+/// without doing any of it
+#[tauri::command]
+async fn preview_launch<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: StartConfig) -> Result<launch_plan::LaunchPlan, PythonError> {
+    plan_start(&app, &config)
+}
+
+/// Checks write access and free space for the runtime venv on the app data volume
+#[tauri::command]
+fn check_disk_space<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> preflight::DiskSpace {
+    preflight::check_disk_space(&app)
+}
+
+/// Reports which of the configured native libraries (`system_libs`) can't be loaded
+#[tauri::command]
+fn check_system_deps<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<preflight::DepStatus> {
+    preflight::check_system_deps(&app)
+}
+
+/// Compares the CPU architecture of `path` (a python interpreter) with the app's
+#[tauri::command]
+async fn interpreter_arch(path: PathBuf) -> preflight::Arch {
+    preflight::interpreter_arch(&path)
+}
+
+/// Runs all environment checks ahead of a launch
+#[tauri::command]
+fn preflight<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> preflight::PreflightReport {
+    let interpreter = LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    preflight::run(&app, interpreter.or_else(|| venv_python(&app).ok().filter(|p| p.exists())), backend_pythonpath(&app))
+}
+
+/// The backend's PYTHONPATH entries in import order, each with whether it exists
+#[tauri::command]
+fn effective_pythonpath<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<preflight::PathEntry> {
+    preflight::path_entries(backend_pythonpath(&app))
+}
+
+/// Python/pip variables in the viewer's environment known to break the backend or its
+/// venv, each with a fix and whether the next start (with the last start's options) keeps
+/// it from the backend
+#[tauri::command]
+fn check_env_conflicts<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<env_conflicts::EnvConflict> {
+    let explicit_interpreter = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).as_ref().is_some_and(|c| c.interpreter.is_some());
+    env_conflicts::check(std::env::vars(), config::get(&app).neutralize_python_env, explicit_interpreter)
+}
+
+/// Turns removal of interfering global Python variables from the backend's environment on
+/// or off (on by default); applies from the next start
+#[tauri::command]
+fn set_neutralize_python_env<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    config::update(&app, |c| c.neutralize_python_env = enabled)
+}
+
+/// Writes a single line to the backend's stdin
+#[tauri::command]
+fn send_to_python(line: String) -> Result<(), String> {
+    control::send_line(&line)
+}
+
+/// Sends a JSON-RPC request over the control channel and returns its result. Times out
+/// after `timeout_ms` (default 5 s, max 60 s).
+#[tauri::command]
+async fn rpc_call(method: String, params: Option<serde_json::Value>, timeout_ms: Option<u64>) -> Result<serde_json::Value, String> {
+    let timeout = timeout_ms.map_or(control::DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms.min(60_000)));
+    control::call(&method, params.unwrap_or(serde_json::Value::Null), timeout)
+}
+
+/// Round-trip time in milliseconds of an RPC `ping`, to check the backend is responsive
+#[tauri::command]
+async fn ping() -> Result<u64, String> {
+    let started = Instant::now();
+    control::call("ping", serde_json::Value::Null, control::DEFAULT_TIMEOUT)?;
+    Ok(started.elapsed().as_millis() as u64)
+}
+
+/// The backend's own configuration (robot settings), via the `config.get` RPC
+#[tauri::command]
+async fn get_backend_config() -> Result<serde_json::Value, String> {
+    control::call("config.get", serde_json::Value::Null, control::DEFAULT_TIMEOUT)
+}
+
+/// Applies the given keys to the backend's configuration via the `config.set` RPC and
+/// returns the resulting config
+#[tauri::command]
+async fn set_backend_config(config: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !config.is_object() {
+        return Err("Backend config must be a JSON object".to_string());
+    }
+    control::call("config.set", serde_json::json!({ "config": config }), control::DEFAULT_TIMEOUT)
+}
+
+/// Sets the log level for both layers and persists it; None shows everything and leaves
+/// the backend at its default verbosity.
+///
+/// The backend receives it as TELEVOODOO_LOG_LEVEL on its next start, which decides what it
+/// produces at all. The viewer additionally stops emitting `python-log` for backend lines
+/// detected below the level right away, so a change is visible without a restart. Lines hidden
+/// by the viewer are still written to the log file and kept in the recent-lines buffer, and
+/// still sent as `python-line`/`python-error`, which carry the pose protocol.
+#[tauri::command]
+fn set_log_level<R: tauri::Runtime>(app: tauri::AppHandle<R>, level: Option<Level>) -> Result<(), String> {
+    config::update(&app, |c| c.log_level = level)?;
+    logs::set_min_level(level);
+    Ok(())
+}
+
+/// Stops the backend and starts it again with the last start options, adjusted by `f`
+async fn restart_backend<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    f: impl FnOnce(StartConfig) -> StartConfig,
+) -> Result<(), PythonError> {
+    let last = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let config = last.ok_or_else(|| "No previous start options to restart with".to_string())?;
+    cleanup_python();
+    drain_readers(config::get(app).reader_join_timeout());
+    start_python(app.clone(), f(config)).await
+}
+
+/// Payload of `python-config-reloaded`
+#[derive(Clone, serde::Serialize)]
+struct ConfigReloaded {
+    /// "sighup", "rpc" or "restart"
+    method: &'static str,
+}
+
+/// Makes the running backend re-read its config without dropping hardware connections:
+/// with SIGHUP when `reload_via_sighup` is set (Unix; the backend must handle it, as the
+/// default action terminates), otherwise via the `config.reload` RPC. A backend that
+/// supports neither is restarted if `reload_restart_fallback` is set.
+#[tauri::command]
+async fn reload_backend_config<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), PythonError> {
+    let pid = backend_pid();
+    let Some(pid) = pid.filter(|_| backend_running()) else {
+        return Err("Backend is not running".to_string().into());
+    };
+    let viewer_config = config::get(&app);
+    let method = if cfg!(unix) && viewer_config.reload_via_sighup {
+        #[cfg(unix)]
+        if unsafe { libc::kill(pid as i32, libc::SIGHUP) } != 0 {
+            return Err(format!("Could not signal backend: {}", std::io::Error::last_os_error()).into());
+        }
+        "sighup"
+    } else {
+        let _ = pid;
+        match control::call("config.reload", serde_json::Value::Null, control::DEFAULT_TIMEOUT) {
+            Ok(_) => "rpc",
+            Err(e) if control::unsupported(&e) && viewer_config.reload_restart_fallback => {
+                restart_backend(&app, |config| config).await?;
+                "restart"
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let _ = app.emit("python-config-reloaded", ConfigReloaded { method });
+    Ok(())
+}
+
+/// Payload of `python-profile-switched`
+#[derive(Clone, serde::Serialize)]
+struct ProfileSwitched {
+    name: String,
+    /// True when the backend had to be restarted with `--profile` because it can't switch
+    /// profiles live
+    restarted: bool,
+}
+
+/// Switches the running backend to another robot profile: live via the `profile.switch`
+/// RPC when the backend supports it, otherwise by restarting it with the last start
+/// options and `--profile <name>`. The profile is remembered as `last_profile`.
+#[tauri::command]
+async fn switch_profile<R: tauri::Runtime>(app: tauri::AppHandle<R>, name: String) -> Result<(), PythonError> {
+    validate_profile_name(&name)?;
+    if !backend_running() {
+        return Err("Backend is not running".to_string().into());
+    }
+    let restarted = match control::call("profile.switch", serde_json::json!({ "name": name }), control::DEFAULT_TIMEOUT) {
+        Ok(_) => {
+            if let Some(last) = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+                // A later restart must come back with the same profile
+                last.profile = Some(name.clone());
+            }
+            let _ = config::update(&app, |c| c.last_profile = Some(name.clone()));
+            false
+        }
+        Err(e) if control::unsupported(&e) => {
+            let profile = Some(name.clone());
+            restart_backend(&app, |config| StartConfig { profile, ..config }).await?;
+            true
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let _ = app.emit("python-profile-switched", ProfileSwitched { name, restarted });
+    Ok(())
+}
+
+/// Asks the running backend to collect garbage and return freed heap to the OS, and
+/// reports its resident set size before and after. A backend without `memory.trim`
+/// reports `supported: false`; it keeps running either way.
+#[tauri::command]
+async fn trim_backend_memory() -> Result<memory::TrimResult, String> {
+    if !backend_running() {
+        return Err("Backend is not running".to_string());
+    }
+    memory::trim(backend_pid())
+}
+
+/// Records a py-spy flamegraph of the running backend for `duration_secs` (1-600) and
+/// returns the SVG path. See profiler for where py-spy is looked up and the permissions
+/// it needs.
+#[tauri::command]
+async fn profile_backend<R: tauri::Runtime>(app: tauri::AppHandle<R>, duration_secs: u64) -> Result<PathBuf, String> {
+    let pid = backend_pid().filter(|_| backend_running()).ok_or_else(|| "Backend is not running".to_string())?;
+    let python = LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone().or_else(|| venv_python(&app).ok());
+    profiler::record(&app, pid, duration_secs, python.as_deref())
+}
+
+/// Starts recording every log line (backend and helpers) with timestamps to a `.tvlog`
+/// file, replacing a recording already in progress
+#[tauri::command]
+fn start_recording(path: PathBuf) -> Result<(), String> {
+    recording::start(&path)
+}
+
+/// Stops the log recording and returns the number of lines recorded
+#[tauri::command]
+fn stop_recording() -> Result<u64, String> {
+    recording::stop()
+}
+
+/// Replays a `.tvlog` recording as `python-log` events with `replayed` set, `speed` times
+/// faster than recorded (default 1). Emits `python-replay-finished` at the end.
+#[tauri::command]
+fn replay_log<R: tauri::Runtime>(app: tauri::AppHandle<R>, path: PathBuf, speed: Option<f64>) -> Result<(), String> {
+    let speed = speed.unwrap_or(1.0);
+    if !recording::SPEED_RANGE.contains(&speed) {
+        return Err(format!("Replay speed must be between {} and {}", recording::SPEED_RANGE.start(), recording::SPEED_RANGE.end()));
+    }
+    recording::replay(&app, &path, speed)
+}
+
+/// Temporarily stops emitting backend `python-log` events (e.g. during a recording) to cut
+/// IPC load. Output is still drained from the pipes and written to the log file, and
+/// `python-line`/`python-error` keep flowing so the session stays connected.
+#[tauri::command]
+fn pause_logging() {
+    logs::pause();
+}
+
+/// Payload of `python-log-resumed`
+#[derive(Clone, serde::Serialize)]
+struct LogResumed {
+    /// Lines read while paused that were not emitted
+    buffered_lines: u64,
+}
+
+/// Resumes log events after pause_logging and emits `python-log-resumed` with the number
+/// of lines that were skipped
+#[tauri::command]
+fn resume_logging<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> u64 {
+    let buffered_lines = logs::resume();
+    let _ = app.emit("python-log-resumed", LogResumed { buffered_lines });
+    buffered_lines
+}
+
+/// Open files, devices and listening sockets of the running backend (Linux and macOS;
+/// empty elsewhere), e.g. to find out which camera or port it is holding
+#[tauri::command]
+fn backend_open_files() -> Result<Vec<open_files::OpenFileInfo>, String> {
+    let pid = backend_pid();
+    match pid {
+        Some(pid) if backend_running() => Ok(open_files::list(pid)),
+        _ => Err("Backend is not running".to_string()),
+    }
+}
+
+/// Address the running backend announced it listens on (see the `endpoint_pattern`
+/// config), or None if it has not printed one
+#[tauri::command]
+fn backend_endpoint() -> Option<std::net::SocketAddr> {
+    endpoint::current()
+}
+
+/// Start latency milestones of the most recent launch
+#[tauri::command]
+fn last_start_timings() -> Option<start_timings::StartTimings> {
+    start_timings::last()
+}
+
+/// Start latency of the last few launches, oldest first, for spotting regressions
+#[tauri::command]
+fn start_timings_history() -> Vec<start_timings::StartTimings> {
+    start_timings::history()
+}
+
+/// Snapshot returned by python_status
+#[derive(Clone, serde::Serialize)]
+struct PythonStatus {
+    running: bool,
+    pid: Option<u32>,
+    /// Interpreter the current (or last) backend was launched with
+    interpreter: Option<PathBuf>,
+    /// System interpreter chosen by the most recent fallback discovery, if one ran
+    system_python: Option<bootstrap::ResolvedPython>,
+    /// Stdout/stderr encoding the current (or last) backend was launched with, if forced
+    encoding: Option<String>,
+}
+
+#[tauri::command]
+fn python_status() -> PythonStatus {
+    let pid = backend_pid();
+    PythonStatus {
+        running: backend_running(),
+        pid,
+        interpreter: LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        system_python: bootstrap::last_resolved_python(),
+        encoding: LAUNCH_ENCODING.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+/// Number of trailing log lines included in session_summary
+const SUMMARY_LOG_LINES: usize = 200;
+
+/// Everything a problem report needs, returned by session_summary
+#[derive(Clone, serde::Serialize)]
+struct SessionSummary {
+    viewer_version: String,
+    os: &'static str,
+    arch: &'static str,
+    status: PythonStatus,
+    last_crash: Option<CrashInfo>,
+    televoodoo: update::UpdateStatus,
+    /// Last SUMMARY_LOG_LINES buffered backend lines, oldest first
+    recent_logs: Vec<logs::BufferedLine>,
+}
+
+/// Status, versions, last crash and recent log lines in one consistent snapshot, for the
+/// "Report a problem" flow
+#[tauri::command]
+fn session_summary<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> SessionSummary {
+    let from_seq = logs::next_seq().saturating_sub(SUMMARY_LOG_LINES as u64);
+    SessionSummary {
+        viewer_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        status: python_status(),
+        last_crash: get_last_crash(),
+        televoodoo: update::check(&app),
+        recent_logs: logs::recent_since(from_seq, SUMMARY_LOG_LINES),
+    }
+}
+
+/// Redacted recent log tail as share_logs would upload it, with the token that confirms
+/// this exact text. The pairing code of the last start is redacted along with env secrets.
+#[tauri::command]
+fn preview_share_logs<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<share_logs::SharePreview, String> {
+    let code = LAST_START.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|c| c.code.clone());
+    share_logs::preview(&app, code.map(|code| ("code".to_string(), code)).into_iter().collect())
+}
+
+/// Uploads the text of the preview `token` came with to the paste service and returns
+/// its URL. Each share needs a fresh preview; on failure the error names the local copy.
+#[tauri::command]
+async fn share_logs<R: tauri::Runtime>(app: tauri::AppHandle<R>, token: String) -> Result<String, String> {
+    share_logs::share(&app, &token)
+}
+
+/// Sets the paste service endpoint used by share_logs (https only)
+#[tauri::command]
+fn set_paste_url<R: tauri::Runtime>(app: tauri::AppHandle<R>, url: String) -> Result<(), String> {
+    share_logs::validate_paste_url(&url)?;
+    config::update(&app, |c| c.paste_url = url)
+}
 
-// simple recursive copy helper for bootstrapping runtime python from resources
-fn copy_dir_all(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
-    if !src.exists() {
-        return Ok(());
-    }
-    std::fs::create_dir_all(dst)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        if ty.is_dir() {
-            copy_dir_all(&entry.path(), &dst.join(entry.file_name()))?;
-        } else {
-            std::fs::copy(entry.path(), dst.join(entry.file_name()))?;
+/// Collects backend output lines produced during the next `duration_ms` (max 30 s),
+/// returning early once `max_lines` (max 1000) have been seen. Requires a running backend.
+#[tauri::command]
+async fn capture_output(duration_ms: u64, max_lines: usize) -> Result<Vec<String>, String> {
+    if !backend_running() {
+        return Err("Backend is not running".to_string());
+    }
+    let duration = Duration::from_millis(duration_ms.min(30_000));
+    let max_lines = max_lines.min(logs::RECENT_CAPACITY);
+    let from_seq = logs::next_seq();
+    let started = Instant::now();
+
+    loop {
+        let lines = logs::recent_since(from_seq, max_lines);
+        if lines.len() >= max_lines || started.elapsed() >= duration || !backend_running() {
+            return Ok(lines.into_iter().map(|l| l.text).collect());
         }
+        std::thread::sleep(Duration::from_millis(50).min(duration.saturating_sub(started.elapsed())));
     }
-    Ok(())
 }
 
-fn find_bundled_python_dir(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
-    if let Ok(res_dir) = app.path().resource_dir() {
-        let candidate1 = res_dir.join("python");
-        if candidate1.exists() {
-            return Some(candidate1);
-        }
-        let candidate2 = res_dir.join("resources").join("python");
-        if candidate2.exists() {
-            return Some(candidate2);
-        }
+/// Waits until the backend prints a line matching the regex `pattern` and returns that
+/// line, or fails after `timeout_ms` (max 10 min). Only output after the call counts.
+#[tauri::command]
+async fn wait_for_line(pattern: String, timeout_ms: u64) -> Result<String, String> {
+    let regex = regex::Regex::new(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    let timeout = Duration::from_millis(timeout_ms.min(600_000));
+    logs::wait_for_match(regex, timeout)
+        .ok_or_else(|| format!("Timed out after {} ms waiting for a line matching {:?}", timeout.as_millis(), pattern))
+}
+
+/// Compares the bundled televoodoo version with the copy installed in the runtime venv
+#[tauri::command]
+fn check_televoodoo_update<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> update::UpdateStatus {
+    update::check(&app)
+}
+
+/// Re-copies the bundled televoodoo into the runtime dir and reinstalls it
+#[tauri::command]
+async fn update_televoodoo<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<update::UpdateStatus, String> {
+    if backend_running() || START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Err("Stop the backend before updating televoodoo".to_string());
     }
-    None
+    update::apply(&app)?;
+    Ok(update::check(&app))
 }
 
-/// Configuration for starting the Python sidecar
-#[derive(serde::Deserialize)]
-struct StartConfig {
-    /// Connection type: "wifi", "ble", or "usb"
-    connection: String,
-    /// Peripheral/server name (optional - uses random if not provided)
-    name: Option<String>,
-    /// Authentication code (optional - uses random if not provided)
-    code: Option<String>,
-    /// Upsample poses to target frequency (Hz) using linear extrapolation
-    upsample_hz: Option<f64>,
-    /// Rate limit pose output to maximum frequency (Hz)
-    rate_limit_hz: Option<f64>,
+/// Syncs the bundled televoodoo source into the runtime dir and reinstalls just the
+/// package, without a version check or venv rebuild
+#[tauri::command]
+async fn refresh_runtime_from_bundle<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    if backend_running() || START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Err("Stop the backend before refreshing televoodoo".to_string());
+    }
+    update::refresh_from_bundle(&app)
 }
 
+/// Asks the update manifest at the `update_url` config for a televoodoo newer than the
+/// runtime venv's. Returns None when up to date.
 #[tauri::command]
-async fn start_python(app: tauri::AppHandle, config: StartConfig) -> Result<(), String> {
-    // In dev builds, run directly from the repo's python dir and venv
-    if cfg!(debug_assertions) {
-        // Resolve repo root at compile time (this is the src-tauri dir); go up one to project root
-        let repo_root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
-            .parent()
-            .ok_or_else(|| "Could not determine repo root".to_string())?
-            .to_path_buf();
-
-        let python_dir = repo_root.join("python");
-        let dev_python = python_dir.join(".venv").join("bin").join("python");
-        let python = if dev_python.exists() {
-            dev_python.to_string_lossy().to_string()
-        } else {
-            "python3".to_string()
-        };
+async fn check_remote_update<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<Option<remote_update::UpdateInfo>, String> {
+    remote_update::check(&app)
+}
 
-        let televoodoo_dir = python_dir.join("televoodoo");
-        if !televoodoo_dir.exists() {
-            return Err(format!("televoodoo directory not found at: {}", televoodoo_dir.display()));
-        }
-        let televoodoo_src = televoodoo_dir.join("src");
-        if !televoodoo_src.exists() {
-            return Err(format!("televoodoo src directory not found at: {}", televoodoo_src.display()));
-        }
+/// Payload of `python-remote-update-done`
+#[derive(Clone, serde::Serialize)]
+struct RemoteUpdateDone {
+    version: String,
+    restarted: bool,
+}
 
-        let mut cmd = Command::new(&python);
-        cmd.arg("-m").arg("televoodoo")
-            .arg("--connection").arg(&config.connection)
-            .current_dir(&televoodoo_dir)
-            .env("PYTHONPATH", televoodoo_src.to_string_lossy().to_string());
-        
-        // Add optional name and code
-        if let Some(ref name) = config.name {
-            cmd.arg("--name").arg(name);
-        }
-        if let Some(ref code) = config.code {
-            cmd.arg("--code").arg(code);
-        }
-        // Add optional upsampling and rate limiting
-        if let Some(hz) = config.upsample_hz {
-            cmd.arg("--upsample-hz").arg(hz.to_string());
+/// Downloads and installs the update found by check_remote_update once its checksum and
+/// signature are verified. A running backend is stopped for the install and started again
+/// afterwards (also when the install failed, on the old version). Returns the new version.
+#[tauri::command]
+async fn apply_remote_update<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<String, PythonError> {
+    if START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Err("Wait for the backend start to finish before updating".to_string().into());
+    }
+    let was_running = backend_running();
+    if was_running {
+        cleanup_python();
+        drain_readers(config::get(&app).reader_join_timeout());
+    }
+    let result = remote_update::apply(&app);
+    let restarted = if was_running {
+        remote_update::emit_step(&app, remote_update::UpdateStep::Restarting);
+        restart_backend(&app, |config| config).await
+    } else {
+        Ok(())
+    };
+    // Neither error may hide the other
+    let version = match (result, restarted) {
+        (Ok(version), Ok(())) => version,
+        (Err(update), Ok(())) => return Err(update.into()),
+        (Ok(version), Err(restart)) => {
+            return Err(PythonError::Other(format!("Installed televoodoo {} but restarting the backend failed: {}", version, restart)));
         }
-        if let Some(hz) = config.rate_limit_hz {
-            cmd.arg("--rate-limit-hz").arg(hz.to_string());
+        (Err(update), Err(restart)) => {
+            return Err(PythonError::Other(format!("{}; restarting the backend on the old version also failed: {}", update, restart)));
         }
+    };
+    let _ = app.emit("python-remote-update-done", RemoteUpdateDone { version: version.clone(), restarted: was_running });
+    Ok(version)
+}
 
-        // Ensure pyobjc on macOS for dev
-        #[cfg(target_os = "macos")]
-        {
-            if let Ok(status) = Command::new(&cmd.get_program())
-                .args(["-c", "import objc"]) // simple import test
-                .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
-                .status()
-            {
-                if !status.success() {
-                    let _ = Command::new(&cmd.get_program())
-                        .args(["-m", "pip", "install", "pyobjc"])
-                        .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
-                        .status();
-                }
-            }
-        }
+/// Writes the runtime python dir (venv and televoodoo, without caches) to `dest` as a
+/// `.tar.gz`. The archive only works on machines with the same OS, architecture and base
+/// Python install.
+#[tauri::command]
+async fn export_venv<R: tauri::Runtime>(app: tauri::AppHandle<R>, dest: PathBuf) -> Result<(), String> {
+    venv_archive::export(&app, &dest)
+}
 
-        // AppImage and some launchers may inject Python-related env vars that break venvs.
-        // For dev, we KEEP PYTHONPATH (we set it above) but sanitize the rest.
-        cmd.env_remove("PYTHONHOME")
-            .env_remove("PYTHONEXECUTABLE")
-            .env_remove("PYTHONUSERBASE")
-            .env("PYTHONUNBUFFERED", "1");
+/// Replaces the runtime python dir with an archive made by export_venv, after checking it
+/// was made for this OS/architecture and its venv is usable here
+#[tauri::command]
+async fn import_venv<R: tauri::Runtime>(app: tauri::AppHandle<R>, src: PathBuf) -> Result<(), String> {
+    if backend_running() || START_IN_PROGRESS.load(Ordering::SeqCst) {
+        return Err("Stop the backend before importing a venv".to_string());
+    }
+    venv_archive::import(&app, &src)
+}
 
-        // On Unix, create new process group for cleaner termination
-        #[cfg(unix)]
-        unsafe {
-            cmd.pre_exec(|| {
-                // Create new process group with this process as leader
-                libc::setpgid(0, 0);
-                Ok(())
-            });
-        }
+/// Current step of the runtime venv bootstrap (Idle when none is running)
+#[tauri::command]
+fn bootstrap_phase() -> bootstrap::BootstrapPhase {
+    bootstrap::current_phase()
+}
 
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| e.to_string())?;
+/// Sets pip's `--timeout` (1-600 s) and `--retries` (0-50) used for all installs
+#[tauri::command]
+fn set_pip_network_opts<R: tauri::Runtime>(app: tauri::AppHandle<R>, timeout_secs: u32, retries: u32) -> Result<(), String> {
+    if !(1..=600).contains(&timeout_secs) {
+        return Err(format!("pip timeout must be between 1 and 600 seconds, got {}", timeout_secs));
+    }
+    if retries > 50 {
+        return Err(format!("pip retries must be at most 50, got {}", retries));
+    }
+    config::update(&app, |c| {
+        c.pip_timeout_secs = timeout_secs;
+        c.pip_retries = retries;
+    })
+}
 
-        let stdout = child.stdout.take();
-        let stderr = child.stderr.take();
-        
-        // Store child process for cleanup on exit
-        if let Ok(mut guard) = PYTHON_CHILD.lock() {
-            // Kill any existing Python process first
-            if let Some(mut old_child) = guard.take() {
-                let _ = old_child.kill();
-            }
-            *guard = Some(child);
-        }
-        
-        let app_handle = app.clone();
-        std::thread::spawn(move || {
-            if let Some(stdout) = stdout {
-                let reader = BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        let _ = app_handle.emit("python-line", line);
-                    }
-                }
-            }
-        });
-        let app_handle_err = app.clone();
-        std::thread::spawn(move || {
-            if let Some(stderr) = stderr {
-                let reader = BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        let _ = app_handle_err.emit("python-error", line);
-                    }
-                }
-            }
-        });
-        return Ok(());
-    }
-
-    // Always prefer a runtime venv under App Support and bootstrap it from bundled Resources if missing.
-    let mut python = "python3".to_string();
-    if let Ok(app_data_dir) = app.path().app_data_dir() {
-        let runtime_py_dir = app_data_dir.join("python");
-        let runtime_venv_bin = runtime_py_dir.join(".venv").join("bin");
-        let runtime_python = runtime_venv_bin.join("python");
-        let runtime_pip = runtime_venv_bin.join("pip");
-
-        if !runtime_python.exists() {
-            if let Some(bundled) = find_bundled_python_dir(&app) {
-                let televoodoo_dir = bundled.join("televoodoo");
-                let pyproject = televoodoo_dir.join("pyproject.toml");
-                if pyproject.exists() {
-                    let _ = std::fs::create_dir_all(&runtime_py_dir);
-                    let runtime_televoodoo = runtime_py_dir.join("televoodoo");
-                    let _ = copy_dir_all(&televoodoo_dir, &runtime_televoodoo);
-                    let _ = Command::new("python3").arg("-m").arg("venv").arg(runtime_py_dir.join(".venv")).status();
-                    if runtime_pip.exists() {
-                        let _ = Command::new(&runtime_python).arg("-m").arg("pip").arg("install").arg("-U").arg("pip").status();
-                        let req = televoodoo_dir.join("requirements.txt");
-                        if req.exists() {
-                            let _ = Command::new(&runtime_python).arg("-m").arg("pip").arg("install").arg("-r").arg(&req).status();
-                        }
-                        let _ = Command::new(&runtime_python).arg("-m").arg("pip").arg("install").arg(&runtime_televoodoo).status();
-                    }
-                }
-            }
-        }
-        if runtime_python.exists() {
-            python = runtime_python.to_string_lossy().to_string();
+/// Payload of `system-resumed`
+#[derive(Clone, serde::Serialize)]
+struct SystemResumed {
+    /// Approximate time the machine was asleep
+    slept_ms: u64,
+    /// Whether the backend is being restarted (see restart_on_resume)
+    restarting: bool,
+}
+
+/// Delay after a resume before the backend is restarted, so USB and Bluetooth devices
+/// have re-enumerated
+const RESUME_SETTLE: Duration = Duration::from_secs(3);
+
+/// Reports a resume from sleep and, with `restart_on_resume`, restarts a running backend
+/// whose hardware connections most likely broke while asleep
+fn handle_resume<R: tauri::Runtime>(app: &tauri::AppHandle<R>, slept: Duration) {
+    let restarting = config::get(app).restart_on_resume && backend_running() && !START_IN_PROGRESS.load(Ordering::SeqCst);
+    system_log::log(Level::Info, &format!("system resumed after ~{} s asleep", slept.as_secs()));
+    let _ = app.emit("system-resumed", SystemResumed { slept_ms: slept.as_millis() as u64, restarting });
+    if !restarting {
+        return;
+    }
+    std::thread::sleep(RESUME_SETTLE);
+    if let Err(e) = tauri::async_runtime::block_on(restart_backend(app, |config| config)) {
+        logs::forward_tool_line(app, "resume", Stream::Stderr, &format!("Restart after resume failed: {}", e));
+    }
+}
+
+/// Payload of `python-hotreload`
+#[derive(Clone, serde::Serialize)]
+struct HotReload {
+    /// Changed source files
+    changed: Vec<PathBuf>,
+    /// False when no backend was running, so there was nothing to restart
+    restarting: bool,
+}
+
+/// Restarts a running backend after its source changed (dev builds, `hot_reload`)
+fn handle_source_change<R: tauri::Runtime>(app: &tauri::AppHandle<R>, changed: Vec<PathBuf>) {
+    let restarting = backend_running() && !START_IN_PROGRESS.load(Ordering::SeqCst);
+    let _ = app.emit("python-hotreload", HotReload { changed, restarting });
+    if restarting {
+        if let Err(e) = tauri::async_runtime::block_on(restart_backend(app, |config| config)) {
+            logs::forward_tool_line(app, "hotreload", Stream::Stderr, &format!("Restart after source change failed: {}", e));
         }
     }
+}
 
-    let mut cmd = Command::new(python);
-    cmd.arg("-m").arg("televoodoo")
-        .arg("--connection").arg(&config.connection);
-    
-    // Add optional name and code
-    if let Some(ref name) = config.name {
-        cmd.arg("--name").arg(name);
+/// Enables or disables hot reload of the televoodoo source; only dev builds watch it
+#[tauri::command]
+fn set_hot_reload<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    if enabled && !cfg!(debug_assertions) {
+        return Err("Hot reload is only available in dev builds".to_string());
+    }
+    config::update(&app, |c| c.hot_reload = enabled)
+}
+
+/// Enables or disables debugging allocators for the backend (see debug_alloc); applies
+/// from the next start
+#[tauri::command]
+fn set_debug_alloc<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    config::update(&app, |c| c.debug_alloc = enabled)
+}
+
+/// Enables or disables restarting the backend after the machine wakes from sleep
+#[tauri::command]
+fn set_restart_on_resume<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    config::update(&app, |c| c.restart_on_resume = enabled)
+}
+
+/// Configures crash auto-restart: cooldown before each attempt (0-60000 ms) and at most
+/// `max_attempts` (1-100) restarts per `window_secs` (1-3600)
+#[tauri::command]
+fn set_auto_restart<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    enabled: bool,
+    cooldown_ms: u64,
+    max_attempts: u32,
+    window_secs: u64,
+) -> Result<(), String> {
+    if cooldown_ms > 60_000 {
+        return Err(format!("Restart cooldown must be at most 60000 ms, got {}", cooldown_ms));
     }
-    if let Some(ref code) = config.code {
-        cmd.arg("--code").arg(code);
+    if !(1..=100).contains(&max_attempts) {
+        return Err(format!("Restart attempts must be between 1 and 100, got {}", max_attempts));
     }
-    // Add optional upsampling and rate limiting
-    if let Some(hz) = config.upsample_hz {
-        cmd.arg("--upsample-hz").arg(hz.to_string());
+    if !(1..=3600).contains(&window_secs) {
+        return Err(format!("Restart window must be between 1 and 3600 seconds, got {}", window_secs));
     }
-    if let Some(hz) = config.rate_limit_hz {
-        cmd.arg("--rate-limit-hz").arg(hz.to_string());
+    config::update(&app, |c| {
+        c.auto_restart = enabled;
+        c.restart_cooldown_ms = cooldown_ms;
+        c.restart_max_attempts = max_attempts;
+        c.restart_window_secs = window_secs;
+    })
+}
+
+/// Sets how often `python-heartbeat` is emitted while the backend runs (100-60000 ms),
+/// or disables it with 0
+#[tauri::command]
+fn set_heartbeat_interval<R: tauri::Runtime>(app: tauri::AppHandle<R>, interval_ms: u64) -> Result<(), String> {
+    if interval_ms != 0 && !(100..=60_000).contains(&interval_ms) {
+        return Err(format!("Heartbeat interval must be 0 or between 100 and 60000 ms, got {}", interval_ms));
     }
+    config::update(&app, |c| c.heartbeat_interval_ms = interval_ms)
+}
 
-    // Packaged: prefer bundled Resources/python/televoodoo, else runtime app_data/python/televoodoo
-    if let Some(bundled_py) = find_bundled_python_dir(&app) {
-        let televoodoo_bundled = bundled_py.join("televoodoo");
-        if televoodoo_bundled.join("pyproject.toml").exists() {
-            cmd.current_dir(&televoodoo_bundled);
-        }
-    } else if let Ok(app_data_dir) = app.path().app_data_dir() {
-        let televoodoo_runtime = app_data_dir.join("python").join("televoodoo");
-        if televoodoo_runtime.join("pyproject.toml").exists() {
-            cmd.current_dir(&televoodoo_runtime);
-        }
+/// Turns unbuffered backend output (PYTHONUNBUFFERED, on by default) on or off; applies
+/// from the next start. Off saves a write per line but delays lines until a buffer fills.
+#[tauri::command]
+fn set_python_unbuffered<R: tauri::Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    config::update(&app, |c| c.python_unbuffered = enabled)
+}
+
+/// Sets how often `python-metrics` is emitted while the backend runs (100-60000 ms), or
+/// disables it with 0
+#[tauri::command]
+fn set_metrics_interval<R: tauri::Runtime>(app: tauri::AppHandle<R>, interval_ms: u64) -> Result<(), String> {
+    if interval_ms != 0 && !(100..=60_000).contains(&interval_ms) {
+        return Err(format!("Metrics interval must be 0 or between 100 and 60000 ms, got {}", interval_ms));
     }
+    config::update(&app, |c| c.metrics_interval_ms = interval_ms)
+}
 
-    // Only ensure pyobjc on macOS; Linux must not try to install it
-    #[cfg(target_os = "macos")]
-    {
-        if let Ok(status) = Command::new(&cmd.get_program())
-            .args(["-c", "import objc"]) // simple import test
-            .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
-            .status()
-        {
-            if !status.success() {
-                let _ = Command::new(&cmd.get_program())
-                    .args(["-m", "pip", "install", "pyobjc"])
-                    .current_dir(cmd.get_current_dir().unwrap_or_else(|| std::path::Path::new(".")))
-                    .status();
-            }
-        }
+/// Performance counters of the running backend (see metrics for the schema); None when
+/// the backend doesn't expose them
+#[tauri::command]
+async fn backend_metrics() -> Result<Option<metrics::BackendMetrics>, String> {
+    if !backend_running() {
+        return Err("Backend is not running".to_string());
     }
+    metrics::query()
+}
 
-    // Clean up Python-related env vars that AppImage sets (PYTHONHOME, PYTHONPATH, ...)
-    cmd.env_remove("PYTHONHOME")
-        .env_remove("PYTHONPATH")
-        .env_remove("PYTHONEXECUTABLE")
-        .env_remove("PYTHONUSERBASE")
-        .env("PYTHONUNBUFFERED", "1");
+/// Timings of the last runtime bootstrap, persisted across runs
+#[tauri::command]
+fn last_bootstrap_timings<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Option<bootstrap::BootstrapTimings> {
+    config::get(&app).last_bootstrap_timings
+}
 
-    // On Unix, create new process group for cleaner termination
-    #[cfg(unix)]
-    unsafe {
-        cmd.pre_exec(|| {
-            // Create new process group with this process as leader
-            libc::setpgid(0, 0);
-            Ok(())
-        });
+/// Sets the runtime python directory name used for the next start
+#[tauri::command]
+fn set_python_dir_name<R: tauri::Runtime>(app: tauri::AppHandle<R>, name: String) -> Result<(), String> {
+    config::validate_python_dir_name(&name)?;
+    config::update(&app, |c| c.python_dir_name = name)
+}
+
+/// Records installed packages, versions and config of the backend interpreter (the last
+/// launched one, else the venv python). One is also taken after every successful start.
+#[tauri::command]
+async fn snapshot_environment<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<env_snapshots::SnapshotInfo, String> {
+    let launched = LAUNCH_PYTHON.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let python = match launched {
+        Some(python) => python,
+        None => venv_python(&app)?,
+    };
+    env_snapshots::take(&app, &python)
+}
+
+/// Stored environment snapshots, newest first
+#[tauri::command]
+fn list_environment_snapshots<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<env_snapshots::SnapshotInfo> {
+    env_snapshots::list(&app)
+}
+
+/// What changed between snapshots `a` (older) and `b`
+#[tauri::command]
+fn diff_environment<R: tauri::Runtime>(app: tauri::AppHandle<R>, a: String, b: String) -> Result<env_snapshots::EnvDiff, String> {
+    env_snapshots::diff(&app, &a, &b)
+}
+
+/// Runtime python dirs under the app data dir with their Python/televoodoo versions
+#[tauri::command]
+fn list_venvs<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Vec<venvs::VenvInfo> {
+    venvs::list(&app)
+}
+
+/// Selects an existing, healthy runtime python dir for the next start
+#[tauri::command]
+fn use_venv<R: tauri::Runtime>(app: tauri::AppHandle<R>, name: String) -> Result<(), String> {
+    venvs::select(&app, &name)
+}
+
+/// Opens the folder containing the config file (selecting the file where supported)
+#[tauri::command]
+async fn reveal_config<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<String, String> {
+    let path = config::ensure_on_disk(&app)?;
+
+    #[cfg(target_os = "macos")]
+    let status = Command::new("open").arg("-R").arg(&path).status();
+    #[cfg(target_os = "windows")]
+    let status = Command::new("explorer").arg(format!("/select,{}", path.display())).status();
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let status = Command::new("xdg-open")
+        .arg(path.parent().unwrap_or_else(|| std::path::Path::new(".")))
+        .status();
+
+    // explorer.exe reports a non-zero exit even on success, so only spawn failures count
+    status.map_err(|e| format!("Could not open config location: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Current persisted config (e.g. to prefill the last-used robot config file), or why
+/// config.json couldn't be loaded; the viewer then runs on defaults and saves nothing
+#[tauri::command]
+fn get_config<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<config::ViewerConfig, String> {
+    match config::load_error(&app) {
+        Some(e) => Err(e),
+        None => Ok(config::get(&app)),
     }
+}
 
-    let mut child = cmd
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| e.to_string())?;
+/// Re-reads the config file after hand edits, returning the parsed config or the parse error
+#[tauri::command]
+fn reload_config<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<config::ViewerConfig, String> {
+    config::reload(&app)
+}
 
-    // take pipes before moving child into threads
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
+/// Checks a config document (e.g. a hand-edited config.json) without saving it
+#[tauri::command]
+fn validate_config(config: serde_json::Value) -> Vec<config_check::ConfigIssue> {
+    config_check::validate(&config)
+}
 
-    // Store child process for cleanup on exit
-    if let Ok(mut guard) = PYTHON_CHILD.lock() {
-        // Kill any existing Python process first
-        if let Some(mut old_child) = guard.take() {
-            let _ = old_child.kill();
-        }
-        *guard = Some(child);
+/// Saves a whole config, replacing the current one. Rejected with all issues found when
+/// any of them is an error, including a change to a protected setting (see
+/// config_check::PROTECTED); warnings alone don't block the save.
+#[tauri::command]
+fn save_config<R: tauri::Runtime>(app: tauri::AppHandle<R>, config: serde_json::Value) -> Result<config::ViewerConfig, Vec<config_check::ConfigIssue>> {
+    let issues = config_check::validate(&config);
+    if issues.iter().any(config_check::ConfigIssue::is_error) {
+        return Err(issues);
     }
+    let parsed: config::ViewerConfig = serde_json::from_value(config)
+        .map_err(|e| vec![config_check::ConfigIssue::error("", e.to_string())])?;
+    let protected = config_check::protected_changes(&config::get(&app), &parsed);
+    if !protected.is_empty() {
+        return Err(protected);
+    }
+    config::replace(&app, parsed.clone()).map_err(|e| vec![config_check::ConfigIssue::error("", e)])?;
+    Ok(parsed)
+}
 
-    let app_handle = app.clone();
-    std::thread::spawn(move || {
-        if let Some(stdout) = stdout {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let _ = app_handle.emit("python-line", line);
-                }
-            }
-        }
-    });
+/// Payload of `python-log-flush-complete`
+#[derive(Clone, serde::Serialize)]
+struct LogFlushComplete {
+    /// False when the reader threads were still busy when the join timeout ran out
+    drained: bool,
+}
 
-    // forward stderr too
-    let app_handle_err = app.clone();
-    std::thread::spawn(move || {
-        if let Some(stderr) = stderr {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let _ = app_handle_err.emit("python-error", line);
-                }
-            }
-        }
-    });
+/// Payload of `python-reader-join-timeout`
+#[derive(Clone, serde::Serialize)]
+struct ReaderJoinTimeout {
+    timeout_ms: u64,
+}
 
+#[tauri::command]
+async fn stop_python<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    run_stop(&app);
     Ok(())
 }
 
-#[tauri::command]
-async fn stop_python() -> Result<(), String> {
+/// Stops the backend (or a detached one left by an earlier run), lets the readers forward
+/// what was still in the pipes, runs the post_exit hook and flushes the log
+fn run_stop<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if !backend_running() {
+        // Also stops a detached backend that a start refused to reattach
+        if let Some(state) = detached::find_running(app) {
+            detached::attach(app, &state);
+        }
+    }
+    let pid = backend_pid();
     cleanup_python();
-    Ok(())
+    let timeout = config::get(app).reader_join_timeout();
+    let mut drained = drain_readers(timeout);
+    if !drained {
+        // A pipe stays open while anything in the backend's process group still holds its
+        // write end (e.g. a grandchild that ignored SIGTERM); killing the group closes them
+        #[cfg(unix)]
+        if let Some(pid) = pid {
+            unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+        drained = drain_readers(Duration::from_millis(200));
+    }
+    if !drained {
+        // Detach the readers so stop never hangs; they end on their own once the pipes close
+        READERS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        logs::forward_tool_line(app, "stop", Stream::Stderr, &format!("WARNING: backend output readers did not finish within {} ms; detached them", timeout.as_millis()));
+        let _ = app.emit("python-reader-join-timeout", ReaderJoinTimeout { timeout_ms: timeout.as_millis() as u64 });
+    }
+    hooks::run_post_exit(app);
+    log_http::disable();
+    logs::flush();
+    let _ = app.emit("python-log-flush-complete", LogFlushComplete { drained });
+}
+
+/// Waits (bounded) for the stdout/stderr readers of the terminated child to forward what
+/// was still buffered in the pipes, then joins them. Returns whether both reached EOF in
+/// time; readers still running are left in READERS.
+fn drain_readers(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        {
+            let mut readers = READERS.lock().unwrap_or_else(|e| e.into_inner());
+            if readers.iter().all(|r| r.is_finished()) {
+                for reader in readers.drain(..) {
+                    let _ = reader.join();
+                }
+                return true;
+            }
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
 }
 
 /// Cleanup function to gracefully terminate the Python child process
 fn cleanup_python() {
-    if let Ok(mut guard) = PYTHON_CHILD.lock() {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+    instance_lock::release();
+    control::set_stdin(None);
+    endpoint::clear();
+    if let Some(state) = detached::attached() {
+        system_log::log(Level::Info, &format!("stopping detached backend (pid {})", state.pid));
+        detached::terminate(&state);
+    }
+    detached::clear();
+    {
+        let mut guard = PYTHON_CHILD.lock().unwrap_or_else(|e| e.into_inner());
         if let Some(mut child) = guard.take() {
             let pid = child.id();
+            system_log::log(Level::Info, &format!("stopping backend (pid {})", pid));
+            // An elevated backend can't be signalled from here; its wrapper stops it
+            elevation::request_stop(&mut child);
             
             #[cfg(unix)]
             {
@@ -355,7 +2666,7 @@ fn cleanup_python() {
                 
                 // Check if it exited
                 match child.try_wait() {
-                    Ok(Some(_)) => return, // Already exited gracefully
+                    Ok(Some(_)) => {} // Already exited gracefully
                     _ => {
                         // Process didn't exit gracefully, escalate to SIGKILL
                         
@@ -390,31 +2701,94 @@ fn cleanup_python() {
     }
 }
 
+#[cfg(all(test, unix))]
+mod tests;
+
 fn main() {
+    // An elevated start runs this binary again as its broker and wrapper; see elevation
+    #[cfg(windows)]
+    if let Some(code) = elevation::run_helper() {
+        std::process::exit(code);
+    }
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![start_python, stop_python])
+        .setup(|app| {
+            log_rotation::prune_and_report(app.handle());
+            resume::watch(app.handle().clone(), handle_resume);
+            // Dev builds run from the repo; there is no runtime copy to repair
+            if config::get(app.handle()).startup_self_repair && dev_python_dir().is_none() {
+                let app = app.handle().clone();
+                std::thread::spawn(move || self_repair::run(&app));
+            }
+            if let Some(dir) = dev_python_dir() {
+                // The source tree resolve_command runs from in dev builds
+                hotreload::watch(app.handle().clone(), dir.join("televoodoo").join("src"), handle_source_change);
+            }
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            start_python, stop_python, last_bootstrap_timings, set_python_dir_name,
+            reveal_config, reload_config, get_config, install_package,
+            cancel_start, check_televoodoo_update, update_televoodoo,
+            bootstrap_phase, set_pip_network_opts, capture_output, python_status,
+            set_stream_forwarding, get_last_crash, ensure_running,
+            check_system_deps, preflight, export_launch_script,
+            set_auto_restart, backend_open_files, pause_logging, resume_logging,
+            send_to_python, rpc_call, ping, get_backend_config, set_backend_config,
+            set_oom_score_adj, session_summary,
+            backend_endpoint, export_venv, import_venv, set_log_level,
+            last_start_timings, start_timings_history, set_heartbeat_interval,
+            switch_profile, run_python_snippet, wait_for_line,
+            check_disk_space, list_venvs, use_venv,
+            reload_backend_config, start_recording, stop_recording, replay_log,
+            interpreter_arch, snapshot_environment, list_environment_snapshots, diff_environment,
+            run_selftest, refresh_runtime_from_bundle, set_cpu_affinity,
+            check_remote_update, apply_remote_update, set_env_file,
+            set_restart_on_resume, backend_help, log_throughput, set_hot_reload,
+            parse_requirements, start_python_elevated,
+            bundle_fingerprint, enable_log_http, disable_log_http, log_http_status,
+            set_log_retention, check_device, effective_pythonpath, set_debug_alloc,
+            preview_launch, trim_backend_memory, run_self_repair,
+            set_startup_self_repair, backend_metrics, set_metrics_interval,
+            set_venv_quarantine, list_quarantined, clear_quarantine,
+            set_python_unbuffered, profile_backend, validate_config, save_config,
+            start_monitor, stop_monitor, preview_share_logs, share_logs,
+            set_paste_url, check_env_conflicts, set_neutralize_python_env
+        ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|_app_handle, event| {
+        .run(|app_handle, event| {
+            // The monitor is never detached
+            if matches!(event, tauri::RunEvent::Exit | tauri::RunEvent::ExitRequested { .. }) {
+                monitor::stop();
+            }
             match event {
                 // RunEvent::Exit is called when the app is about to exit
                 // This is more reliable than window events for cleanup
+                // A detached backend is left running for the next viewer to reattach
+                tauri::RunEvent::Exit if detached::is_detached() => log_http::disable(),
                 tauri::RunEvent::Exit => {
                     cleanup_python();
+                    hooks::run_post_exit_on_quit(app_handle);
+                    log_http::disable();
                 }
                 // Also handle ExitRequested to catch early quit attempts
+                tauri::RunEvent::ExitRequested { .. } if detached::is_detached() => {}
                 tauri::RunEvent::ExitRequested { .. } => {
                     cleanup_python();
+                    hooks::run_post_exit_on_quit(app_handle);
                 }
                 _ => {}
             }
         });
     
     // Final cleanup as last resort (this runs after run() returns)
-    cleanup_python();
+    monitor::stop();
+    if !detached::is_detached() {
+        cleanup_python();
+    }
 }
 
 
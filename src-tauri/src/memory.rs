@@ -0,0 +1,81 @@
+//! Manual memory reclaim for long sessions: the backend is asked over the control channel
+//! (`memory.trim`, see control) to run `gc.collect()` and, where the C library has it,
+//! `malloc_trim(0)`; the resident set size is measured around the request.
+
+use std::time::Duration;
+
+use crate::control;
+
+/// A full collection of a large heap can take a while
+const TRIM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returned by trim_backend_memory
+#[derive(Clone, serde::Serialize)]
+pub struct TrimResult {
+    /// False when the backend doesn't implement `memory.trim`; nothing was reclaimed
+    pub supported: bool,
+    /// Resident set size in bytes; None where it can't be measured
+    pub rss_before: Option<u64>,
+    pub rss_after: Option<u64>,
+    /// rss_before - rss_after; negative when the backend grew in the meantime
+    pub freed_bytes: Option<i64>,
+    /// Objects gc.collect() found unreachable, as reported by the backend
+    pub collected: Option<u64>,
+    /// Whether the backend's malloc_trim released memory to the OS, if it ran
+    pub malloc_trimmed: Option<bool>,
+}
+
+/// Asks the backend with pid `pid` to collect garbage and trim its heap
+pub fn trim(pid: Option<u32>) -> Result<TrimResult, String> {
+    let rss_before = pid.and_then(rss_bytes);
+    let response = match control::call("memory.trim", serde_json::Value::Null, TRIM_TIMEOUT) {
+        Ok(response) => Some(response),
+        Err(e) if control::unsupported(&e) => None,
+        Err(e) => return Err(e),
+    };
+    let rss_after = pid.and_then(rss_bytes);
+    Ok(TrimResult {
+        supported: response.is_some(),
+        rss_before,
+        rss_after,
+        freed_bytes: rss_before.zip(rss_after).map(|(before, after)| before as i64 - after as i64),
+        collected: response.as_ref().and_then(|r| r.get("collected")).and_then(|v| v.as_u64()),
+        malloc_trimmed: response.as_ref().and_then(|r| r.get("malloc_trimmed")).and_then(|v| v.as_bool()),
+    })
+}
+
+/// Resident set size of `pid` in bytes
+#[cfg(target_os = "linux")]
+pub fn rss_bytes(pid: u32) -> Option<u64> {
+    // Second field of statm: resident pages
+    let statm = std::fs::read_to_string(format!("/proc/{}/statm", pid)).ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn rss_bytes(pid: u32) -> Option<u64> {
+    use std::process::{Command, Stdio};
+
+    // ps reports RSS in KiB
+    let output = Command::new("ps").args(["-o", "rss=", "-p", &pid.to_string()]).stderr(Stdio::null()).output().ok()?;
+    let kib: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(unix))]
+pub fn rss_bytes(pid: u32) -> Option<u64> {
+    use std::process::{Command, Stdio};
+
+    // WorkingSetSize from tasklist's CSV output, e.g. "python.exe","1234","Console","1","52,340 K"
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let field = text.lines().next()?.rsplit("\",\"").next()?;
+    let kib: u64 = field.chars().filter(char::is_ascii_digit).collect::<String>().parse().ok()?;
+    Some(kib * 1024)
+}
@@ -0,0 +1,80 @@
+//! Performance counters of the backend, read over the control channel with
+//! `metrics.get` (see control). The result is a JSON object with any of these keys:
+//!
+//! | key                  | type  | meaning                                            |
+//! |----------------------|-------|----------------------------------------------------|
+//! | `frame_rate_hz`      | float | pose frames received per second, recent average   |
+//! | `output_rate_hz`     | float | poses emitted per second after upsampling/limiting |
+//! | `command_latency_ms` | float | median latency from phone to robot command         |
+//! | `latency_p95_ms`     | float | 95th percentile of the same                        |
+//! | `frames_total`       | int   | frames received since the backend started          |
+//! | `frames_dropped`     | int   | frames dropped (late, malformed or overrun)        |
+//! | `uptime_s`           | float | seconds since the backend started                  |
+//!
+//! Missing keys are None; keys not listed are passed through untouched in `extra`.
+//! `metrics_interval_ms` (config) polls it as `python-metrics` while the backend runs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::control;
+
+/// Metrics are for a live HUD; an answer slower than this is useless
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// Cleared when the current backend answers `metrics.get` with "method not found"
+static SUPPORTED: AtomicBool = AtomicBool::new(true);
+
+/// Result of `metrics.get`, returned by backend_metrics and emitted as `python-metrics`
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct BackendMetrics {
+    pub frame_rate_hz: Option<f64>,
+    pub output_rate_hz: Option<f64>,
+    pub command_latency_ms: Option<f64>,
+    pub latency_p95_ms: Option<f64>,
+    pub frames_total: Option<u64>,
+    pub frames_dropped: Option<u64>,
+    pub uptime_s: Option<f64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Called on spawn: a new backend may support metrics even if the last one didn't
+pub fn reset() {
+    SUPPORTED.store(true, Ordering::SeqCst);
+}
+
+/// Queries the backend. None when it doesn't expose metrics.
+pub fn query() -> Result<Option<BackendMetrics>, String> {
+    if !SUPPORTED.load(Ordering::SeqCst) {
+        return Ok(None);
+    }
+    match control::call("metrics.get", serde_json::Value::Null, TIMEOUT) {
+        Ok(value) => serde_json::from_value(value).map(Some).map_err(|e| format!("Invalid metrics from backend: {}", e)),
+        Err(e) if control::unsupported(&e) => {
+            SUPPORTED.store(false, Ordering::SeqCst);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_and_extra_keys() {
+        let metrics: BackendMetrics = serde_json::from_value(serde_json::json!({
+            "frame_rate_hz": 59.8,
+            "frames_dropped": 3,
+            "gripper_state": "open",
+        }))
+        .unwrap();
+        assert_eq!(metrics.frame_rate_hz, Some(59.8));
+        assert_eq!(metrics.frames_dropped, Some(3));
+        assert_eq!(metrics.command_latency_ms, None);
+        assert_eq!(metrics.extra.get("gripper_state"), Some(&serde_json::json!("open")));
+    }
+}
@@ -0,0 +1,121 @@
+//! A second, read-only backend next to the operator's: televoodoo started with `--observe`,
+//! which only watches the session (telemetry, logging) and never opens the robot hardware
+//! exclusively. It has its own process slot, so start/stop of the operator's backend don't
+//! touch it, and no control channel. Its output goes out as tool lines tagged `monitor` (or
+//! the start config's log_tag), which the UI can show in a window of its own.
+
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::Emitter;
+
+use crate::error::PythonError;
+use crate::logs::{self, Stream};
+use crate::{cli_help, StartConfig};
+
+/// Backend flag for observe-only mode
+pub const OBSERVE_FLAG: &str = "--observe";
+
+static MONITOR: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Payload of `python-monitor-exited`
+#[derive(Clone, serde::Serialize)]
+struct MonitorExited {
+    pid: u32,
+    code: Option<i32>,
+}
+
+/// Pid of the monitor backend, if one is running
+pub fn pid() -> Option<u32> {
+    let mut guard = MONITOR.lock().unwrap_or_else(|e| e.into_inner());
+    let child = guard.as_mut()?;
+    matches!(child.try_wait(), Ok(None)).then(|| child.id())
+}
+
+/// Launches the monitor with `config`'s interpreter, entry and options plus `--observe`,
+/// after checking from `--help` that the installed backend has that mode. Refuses when
+/// the runtime would first need a bootstrap or package update.
+pub fn start<R: tauri::Runtime>(app: &tauri::AppHandle<R>, config: StartConfig) -> Result<u32, PythonError> {
+    if let Some(pid) = pid() {
+        return Err(PythonError::Other(format!("A monitor backend is already running (pid {})", pid)));
+    }
+    crate::validate_start(&config)?;
+    // Resolved like a launch preview, so nothing is bootstrapped, updated or installed for
+    // the monitor underneath a running backend
+    let mut plan = crate::launch_plan::LaunchPlan::default();
+    let mut cmd = crate::build_command(app, &config, Some(&mut plan))?;
+    if plan.bootstrap || plan.package_update {
+        return Err(PythonError::Other("The runtime environment needs a bootstrap or package update first; start the backend once to run it".to_string()));
+    }
+    let python = PathBuf::from(cmd.get_program());
+    let help = cli_help::run(&python, &config.entry).map_err(|e| PythonError::Other(format!("Could not check for observe mode: {}", e)))?;
+    if !help.options.iter().any(|option| option.flags.iter().any(|flag| flag == OBSERVE_FLAG)) {
+        return Err(PythonError::Other(format!("The installed televoodoo has no {} option; update it to run a monitor", OBSERVE_FLAG)));
+    }
+    cmd.arg(OBSERVE_FLAG).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = cmd.spawn().map_err(|e| PythonError::from_spawn_error(&python, cmd.get_current_dir(), e))?;
+    let pid = child.id();
+    let tag = config.log_tag.unwrap_or_else(|| "monitor".to_string());
+    let buffer_capacity = crate::config::get(app).reader_buffer_capacity();
+    if let Some(stdout) = child.stdout.take() {
+        forward(app, tag.clone(), Stream::Stdout, BufReader::with_capacity(buffer_capacity, stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward(app, tag.clone(), Stream::Stderr, BufReader::with_capacity(buffer_capacity, stderr));
+    }
+    *MONITOR.lock().unwrap_or_else(|e| e.into_inner()) = Some(child);
+    logs::forward_tool_line(app, &tag, Stream::Stdout, &format!("Monitor backend started (pid {})", pid));
+    let app = app.clone();
+    std::thread::spawn(move || watch_exit(&app, pid));
+    Ok(pid)
+}
+
+fn forward<R: tauri::Runtime>(app: &tauri::AppHandle<R>, tag: String, stream: Stream, reader: impl BufRead + Send + 'static) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for line in reader.lines().map_while(Result::ok) {
+            logs::forward_tool_line(&app, &tag, stream, &line);
+        }
+    });
+}
+
+/// Reaps the monitor once it exits and emits `python-monitor-exited`; ends quietly when
+/// stop took it first
+fn watch_exit<R: tauri::Runtime>(app: &tauri::AppHandle<R>, pid: u32) {
+    loop {
+        std::thread::sleep(Duration::from_millis(200));
+        let mut guard = MONITOR.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(child) = guard.as_mut().filter(|child| child.id() == pid) else { return };
+        match child.try_wait() {
+            Ok(None) => continue,
+            Ok(Some(status)) => {
+                guard.take();
+                let _ = app.emit("python-monitor-exited", MonitorExited { pid, code: status.code() });
+                return;
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+/// Stops the monitor: SIGTERM, then a kill if it hasn't exited after two seconds. Returns
+/// whether one was running.
+pub fn stop() -> bool {
+    let Some(mut child) = MONITOR.lock().unwrap_or_else(|e| e.into_inner()).take() else { return false };
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(child.id() as i32, libc::SIGTERM) };
+        for _ in 0..20 {
+            if !matches!(child.try_wait(), Ok(None)) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    true
+}
@@ -0,0 +1,130 @@
+/// One open file descriptor of the backend, returned by backend_open_files
+#[derive(Clone, serde::Serialize)]
+pub struct OpenFileInfo {
+    pub fd: Option<i32>,
+    /// "file", "device", "socket", "pipe" or "other"
+    pub kind: String,
+    /// Path, or the kernel's description for non-file descriptors (e.g. `socket:[1234]`)
+    pub target: String,
+    /// Local port for sockets bound for listening (TCP LISTEN or bound UDP)
+    pub listening_port: Option<u16>,
+}
+
+/// Lists the open files and listening sockets of `pid`. Empty on platforms without support
+/// or when the process can't be inspected.
+pub fn list(pid: u32) -> Vec<OpenFileInfo> {
+    platform_list(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn platform_list(pid: u32) -> Vec<OpenFileInfo> {
+    use std::collections::HashMap;
+
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+    // socket inode -> listening port
+    let mut listening: HashMap<u64, u16> = HashMap::new();
+    for (table, state) in [("tcp", "0A"), ("tcp6", "0A"), ("udp", "07"), ("udp6", "07")] {
+        if let Ok(text) = std::fs::read_to_string(proc_dir.join("net").join(table)) {
+            listening.extend(text.lines().skip(1).filter_map(|l| parse_proc_net_line(l, state)));
+        }
+    }
+
+    let Ok(entries) = std::fs::read_dir(proc_dir.join("fd")) else { return Vec::new() };
+    let mut files: Vec<OpenFileInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_string_lossy().parse::<i32>().ok()?;
+            let target = std::fs::read_link(entry.path()).ok()?.to_string_lossy().to_string();
+            let (kind, listening_port) = if let Some(inode) = target.strip_prefix("socket:[").and_then(|t| t.strip_suffix(']')) {
+                ("socket", inode.parse().ok().and_then(|i: u64| listening.get(&i).copied()))
+            } else if target.starts_with("pipe:[") {
+                ("pipe", None)
+            } else if target.starts_with("/dev/") {
+                ("device", None)
+            } else if target.starts_with('/') {
+                ("file", None)
+            } else {
+                ("other", None)
+            };
+            Some(OpenFileInfo { fd: Some(fd), kind: kind.to_string(), target, listening_port })
+        })
+        .collect();
+    files.sort_by_key(|f| f.fd);
+    files
+}
+
+/// Parses a `/proc/<pid>/net/{tcp,udp}[6]` row, returning (inode, local port) when the
+/// socket is in `state` (hex, e.g. `0A` for TCP LISTEN)
+#[cfg(target_os = "linux")]
+fn parse_proc_net_line(line: &str, state: &str) -> Option<(u64, u16)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.get(3)? != &state {
+        return None;
+    }
+    let port = u16::from_str_radix(fields.get(1)?.rsplit(':').next()?, 16).ok()?;
+    let inode = fields.get(9)?.parse().ok()?;
+    Some((inode, port))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_list(pid: u32) -> Vec<OpenFileInfo> {
+    // lsof field output: `f<fd>`, `t<type>`, `n<name>` lines per descriptor
+    let Ok(output) = std::process::Command::new("lsof").args(["-nP", "-F", "ftn", "-p", &pid.to_string()]).output() else {
+        return Vec::new();
+    };
+    let mut files: Vec<OpenFileInfo> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (tag, value) = line.split_at(line.len().min(1));
+        match tag {
+            "f" => files.push(OpenFileInfo {
+                fd: value.parse().ok(),
+                kind: "other".to_string(),
+                target: String::new(),
+                listening_port: None,
+            }),
+            "t" => {
+                if let Some(file) = files.last_mut() {
+                    file.kind = match value {
+                        "REG" | "DIR" => "file",
+                        "CHR" | "BLK" => "device",
+                        "IPv4" | "IPv6" | "unix" => "socket",
+                        "PIPE" | "FIFO" => "pipe",
+                        _ => "other",
+                    }
+                    .to_string();
+                }
+            }
+            "n" => {
+                if let Some(file) = files.last_mut() {
+                    // Listening sockets look like `*:8765` (no `->` peer)
+                    if file.kind == "socket" && !value.contains("->") {
+                        file.listening_port = value.rsplit(':').next().and_then(|p| p.parse().ok());
+                    }
+                    file.target = value.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+    // cwd/txt/mem entries have no numeric fd
+    files.retain(|f| f.fd.is_some());
+    files
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn platform_list(_pid: u32) -> Vec<OpenFileInfo> {
+    Vec::new()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_listening_proc_net_rows() {
+        let listen = "   0: 00000000:2225 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 48213 1 0000000000000000 100 0 0 10 0";
+        let established = "   1: 0100007F:2225 0100007F:D4E2 01 00000000:00000000 00:00000000 00000000  1000        0 48999 1 0000000000000000 20 4 30 10 -1";
+        assert_eq!(parse_proc_net_line(listen, "0A"), Some((48213, 8741)));
+        assert_eq!(parse_proc_net_line(established, "0A"), None);
+    }
+}
@@ -0,0 +1,105 @@
+/// Cause of a failed bootstrap step, guessed from pip's output
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapErrorKind {
+    DiskFull,
+    PermissionDenied,
+    Ssl,
+    NoMatchingDistribution,
+    Network,
+    BuildToolsMissing,
+    Unknown,
+}
+
+// Checked in order: a build can fail because the disk is full, not the other way round
+const PATTERNS: &[(BootstrapErrorKind, &[&str])] = &[
+    (BootstrapErrorKind::DiskFull, &["no space left on device", "[errno 28]"]),
+    (BootstrapErrorKind::PermissionDenied, &["permission denied", "[errno 13]", "access is denied"]),
+    (BootstrapErrorKind::Ssl, &["certificate_verify_failed", "sslerror", "ssl: "]),
+    (
+        BootstrapErrorKind::NoMatchingDistribution,
+        &["no matching distribution found", "could not find a version that satisfies"],
+    ),
+    (
+        BootstrapErrorKind::Network,
+        &["temporary failure in name resolution", "name or service not known", "connection timed out", "max retries exceeded"],
+    ),
+    (
+        BootstrapErrorKind::BuildToolsMissing,
+        &[
+            "microsoft visual c++",
+            "unable to execute 'gcc'",
+            "command 'gcc' failed",
+            "command 'cc' failed",
+            "python.h: no such file",
+            "xcrun: error",
+            "failed building wheel",
+            "failed to build",
+        ],
+    ),
+];
+
+/// Classifies pip output by the first known error pattern found
+pub fn classify(output: &[String]) -> BootstrapErrorKind {
+    let output: Vec<String> = output.iter().map(|l| l.to_ascii_lowercase()).collect();
+    PATTERNS
+        .iter()
+        .find(|(_, needles)| output.iter().any(|line| needles.iter().any(|n| line.contains(n))))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(BootstrapErrorKind::Unknown)
+}
+
+/// What the user can do about it
+pub fn hint(kind: BootstrapErrorKind) -> &'static str {
+    match kind {
+        BootstrapErrorKind::DiskFull => "The disk is full: free up space and start again.",
+        BootstrapErrorKind::PermissionDenied => {
+            "Permission denied: make sure the app data directory is writable by your user."
+        }
+        BootstrapErrorKind::Ssl => {
+            "SSL verification failed: check the system clock and any proxy that intercepts HTTPS."
+        }
+        BootstrapErrorKind::NoMatchingDistribution => {
+            "A required package has no release for this Python version or platform."
+        }
+        BootstrapErrorKind::Network => "Could not reach the package index: check the network connection or proxy.",
+        BootstrapErrorKind::BuildToolsMissing => build_tools_hint(),
+        BootstrapErrorKind::Unknown => "See the pip output for details.",
+    }
+}
+
+fn build_tools_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Build tools missing: install the Xcode command line tools (`xcode-select --install`)."
+    } else if cfg!(windows) {
+        "Build tools missing: install the Microsoft C++ Build Tools."
+    } else {
+        "Build tools missing: install a compiler and Python headers (e.g. `sudo apt install build-essential python3-dev`)."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn classifies_common_pip_failures() {
+        assert_eq!(
+            classify(&lines("ERROR: Could not find a version that satisfies the requirement torch==9.9\nERROR: No matching distribution found for torch==9.9")),
+            BootstrapErrorKind::NoMatchingDistribution
+        );
+        assert_eq!(
+            classify(&lines("  error: command 'gcc' failed: No such file or directory\nERROR: Failed building wheel for bleak")),
+            BootstrapErrorKind::BuildToolsMissing
+        );
+        assert_eq!(
+            classify(&lines("ERROR: Failed building wheel for numpy\nOSError: [Errno 28] No space left on device")),
+            BootstrapErrorKind::DiskFull
+        );
+        assert_eq!(classify(&lines("Collecting numpy\nKilled")), BootstrapErrorKind::Unknown);
+    }
+}
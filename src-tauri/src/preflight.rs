@@ -0,0 +1,189 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::bootstrap;
+use crate::config;
+
+/// Availability of one shared library from the `system_libs` config list
+#[derive(Clone, serde::Serialize)]
+pub struct DepStatus {
+    /// Library file name as passed to the dynamic loader (e.g. `libGL.so.1`)
+    pub name: String,
+    pub found: bool,
+    /// How to install it, for missing libraries
+    pub hint: Option<String>,
+}
+
+/// Free space on the app data volume versus what a bootstrap needs
+#[derive(Clone, serde::Serialize)]
+pub struct DiskSpace {
+    /// None when the free space could not be determined
+    pub available: Option<u64>,
+    pub required: u64,
+    pub writable: bool,
+    pub ok: bool,
+}
+
+/// CPU architecture of an interpreter versus the one the app was built for
+#[derive(Clone, serde::Serialize)]
+pub struct Arch {
+    pub interpreter: PathBuf,
+    /// `platform.machine()` as reported by the interpreter; None if it didn't run
+    pub machine: Option<String>,
+    /// `std::env::consts::ARCH` of the app build
+    pub app: &'static str,
+    /// False only when the interpreter ran and reported a different architecture
+    pub matches: bool,
+    pub warning: Option<String>,
+}
+
+/// One PYTHONPATH entry of the backend
+#[derive(Clone, serde::Serialize)]
+pub struct PathEntry {
+    pub path: PathBuf,
+    /// A missing entry usually means a stale env_file or a moved checkout
+    pub exists: bool,
+}
+
+pub fn path_entries(paths: Vec<PathBuf>) -> Vec<PathEntry> {
+    paths.into_iter().map(|path| PathEntry { exists: path.exists(), path }).collect()
+}
+
+/// Environment checks run before a launch, returned by the preflight command
+#[derive(Clone, serde::Serialize)]
+pub struct PreflightReport {
+    /// True when no check found a problem
+    pub ok: bool,
+    pub system_deps: Vec<DepStatus>,
+    pub disk: DiskSpace,
+    /// Architecture check of the interpreter the backend would run with, if known
+    pub arch: Option<Arch>,
+    /// PYTHONPATH the backend would start with; any missing entry fails the check
+    pub pythonpath: Vec<PathEntry>,
+}
+
+pub fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>, interpreter: Option<PathBuf>, pythonpath: Vec<PathBuf>) -> PreflightReport {
+    let system_deps = check_system_deps(app);
+    let disk = check_disk_space(app);
+    let arch = interpreter.map(|p| interpreter_arch(&p));
+    let pythonpath = path_entries(pythonpath);
+    let ok = system_deps.iter().all(|d| d.found)
+        && disk.ok
+        && arch.as_ref().is_none_or(|a| a.matches)
+        && pythonpath.iter().all(|e| e.exists);
+    PreflightReport { ok, system_deps, disk, arch, pythonpath }
+}
+
+/// Asks `python` for `platform.machine()` and compares it to the app's architecture. An
+/// x86_64 Python under Rosetta next to an arm64 app (or the reverse) runs, but its native
+/// extensions fail in confusing ways.
+pub fn interpreter_arch(python: &Path) -> Arch {
+    let app = std::env::consts::ARCH;
+    let machine = Command::new(python)
+        .args(["-c", "import platform; print(platform.machine())"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|m| !m.is_empty());
+    let matches = machine.as_deref().is_none_or(|m| normalize_arch(m) == app);
+    let warning = (!matches).then(|| {
+        let mut warning = format!(
+            "{} is a {} interpreter but the viewer is built for {}; native extensions may fail to load",
+            python.display(),
+            machine.as_deref().unwrap_or_default(),
+            app
+        );
+        if cfg!(target_os = "macos") {
+            warning.push_str(" (is it an x86_64 Python running under Rosetta?)");
+        }
+        warning
+    });
+    Arch { interpreter: python.to_path_buf(), machine, app, matches, warning }
+}
+
+/// Maps `platform.machine()` spellings to `std::env::consts::ARCH` names
+fn normalize_arch(machine: &str) -> String {
+    match machine.to_ascii_lowercase().as_str() {
+        "arm64" | "aarch64" => "aarch64".to_string(),
+        "x86_64" | "amd64" | "x64" => "x86_64".to_string(),
+        "i386" | "i686" | "x86" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Checks that the runtime python dir is writable and its volume has the configured
+/// `bootstrap_required_bytes` free
+pub fn check_disk_space<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> DiskSpace {
+    let required = config::get(app).bootstrap_required_bytes;
+    let Some(dir) = bootstrap::runtime_python_dir(app) else {
+        return DiskSpace { available: None, required, writable: false, ok: false };
+    };
+    let writable = bootstrap::ensure_writable(&dir).is_ok();
+    let available = bootstrap::available_space(&dir);
+    let ok = writable && available.is_none_or(|a| a >= required);
+    DiskSpace { available, required, writable, ok }
+}
+
+/// Probes each library in the `system_libs` config list with the dynamic loader. These are
+/// native dependencies pip cannot install, whose absence otherwise shows up as an obscure
+/// ImportError deep inside the backend.
+pub fn check_system_deps<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<DepStatus> {
+    config::get(app)
+        .system_libs
+        .into_iter()
+        .map(|name| {
+            let found = library_loads(&name);
+            let hint = (!found).then(|| install_hint(&name));
+            DepStatus { name, found, hint }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn library_loads(name: &str) -> bool {
+    let Ok(c_name) = std::ffi::CString::new(name) else { return false };
+    unsafe {
+        let handle = libc::dlopen(c_name.as_ptr(), libc::RTLD_LAZY | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            return false;
+        }
+        libc::dlclose(handle);
+    }
+    true
+}
+
+#[cfg(not(unix))]
+fn library_loads(_name: &str) -> bool {
+    true
+}
+
+fn install_hint(name: &str) -> String {
+    let package = match name.split(".so").next().unwrap_or(name) {
+        "libGL" => Some("libgl1"),
+        "libEGL" => Some("libegl1"),
+        "libv4l2" => Some("libv4l-0"),
+        "libglib-2.0" => Some("libglib2.0-0"),
+        "libbluetooth" => Some("libbluetooth3"),
+        "libusb-1.0" => Some("libusb-1.0-0"),
+        _ => None,
+    };
+    match package {
+        Some(package) => format!("Install it with your package manager, e.g. `sudo apt install {}`", package),
+        None => format!("Install the package that provides {} with your package manager", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_machine_names() {
+        assert_eq!(normalize_arch("arm64"), "aarch64");
+        assert_eq!(normalize_arch("AMD64"), "x86_64");
+        assert_eq!(normalize_arch("i686"), "x86");
+        assert_eq!(normalize_arch("riscv64"), "riscv64");
+    }
+}
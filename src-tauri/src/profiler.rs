@@ -0,0 +1,134 @@
+//! Flamegraphs of the running backend with py-spy, for performance bug reports. py-spy is
+//! looked up next to the venv python first (`pip install py-spy` into the runtime venv),
+//! then on PATH. It samples with `--nonblocking`, so the backend is never paused mid-teleop.
+//!
+//! py-spy reads another process's memory: on Linux that needs ptrace permission (ptrace
+//! scope 0, `CAP_SYS_PTRACE` on the py-spy binary, or root), on macOS root.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use tauri::Emitter;
+
+use crate::bootstrap;
+use crate::logs::{self, Stream};
+
+/// Accepted recording lengths, in seconds
+pub const DURATION_RANGE: std::ops::RangeInclusive<u64> = 1..=600;
+
+// py-spy's stderr kept for the error message; the rest is read and dropped
+const STDERR_LIMIT: usize = 64 * 1024;
+
+// Only one recording at a time; two samplers on one process skew each other
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Payload of `python-profile-progress`
+#[derive(Clone, serde::Serialize)]
+struct ProfileProgress {
+    elapsed_secs: u64,
+    duration_secs: u64,
+}
+
+/// py-spy in the bin dir of `venv_python`, else on PATH
+fn find_py_spy(venv_python: Option<&Path>) -> Option<PathBuf> {
+    let name = if cfg!(windows) { "py-spy.exe" } else { "py-spy" };
+    let beside_venv = venv_python.and_then(Path::parent).map(|dir| dir.join(name)).filter(|p| p.is_file());
+    beside_venv.or_else(|| std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join(name)).find(|p| p.is_file()))
+}
+
+/// Records `pid` (and its children) for `duration_secs` into an SVG flamegraph under the
+/// app data dir and returns its path. Emits `python-profile-progress` once a second.
+pub fn record<R: tauri::Runtime>(app: &tauri::AppHandle<R>, pid: u32, duration_secs: u64, venv_python: Option<&Path>) -> Result<PathBuf, String> {
+    if !DURATION_RANGE.contains(&duration_secs) {
+        return Err(format!("Profile duration must be between {} and {} seconds", DURATION_RANGE.start(), DURATION_RANGE.end()));
+    }
+    let py_spy = find_py_spy(venv_python).ok_or_else(|| {
+        "py-spy not found; install it into the runtime venv (install_package \"py-spy\") or put it on PATH".to_string()
+    })?;
+    if RECORDING.swap(true, Ordering::SeqCst) {
+        return Err("A profile is already being recorded".to_string());
+    }
+    let result = run(app, &py_spy, pid, duration_secs);
+    RECORDING.store(false, Ordering::SeqCst);
+    result
+}
+
+fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>, py_spy: &Path, pid: u32, duration_secs: u64) -> Result<PathBuf, String> {
+    let dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?.join("profiles");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let stamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let output = dir.join(format!("backend-{}-{}.svg", pid, stamp));
+
+    logs::forward_tool_line(app, "profile", Stream::Stdout, &format!("Recording {} s of pid {} with {}", duration_secs, pid, py_spy.display()));
+    let mut child = Command::new(py_spy)
+        .args(["record", "--nonblocking", "--subprocesses", "--format", "flamegraph"])
+        .arg("--pid").arg(pid.to_string())
+        .arg("--duration").arg(duration_secs.to_string())
+        .arg("--output").arg(&output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", py_spy.display(), e))?;
+    // Drained while py-spy runs so a chatty recording can't fill the pipe and stall it
+    let stderr = child.stderr.take().map(|pipe| std::thread::spawn(move || read_limited(pipe)));
+    // py-spy also needs a moment to attach and to write the SVG
+    let deadline = Duration::from_secs(duration_secs + 30);
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() > deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("py-spy did not finish within {} s", deadline.as_secs()));
+            }
+            Ok(None) => {
+                let elapsed_secs = started.elapsed().as_secs().min(duration_secs);
+                let _ = app.emit("python-profile-progress", ProfileProgress { elapsed_secs, duration_secs });
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            Err(e) => return Err(format!("Could not wait for py-spy: {}", e)),
+        }
+    };
+    let stderr = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    if !status.success() || !output.is_file() {
+        return Err(describe_failure(stderr.trim()));
+    }
+    let _ = app.emit("python-profile-progress", ProfileProgress { elapsed_secs: duration_secs, duration_secs });
+    Ok(output)
+}
+
+/// Reads `pipe` to EOF, keeping the first STDERR_LIMIT bytes
+fn read_limited(mut pipe: impl Read) -> String {
+    let mut kept = Vec::new();
+    let mut chunk = [0u8; 8192];
+    while let Ok(n) = pipe.read(&mut chunk) {
+        if n == 0 {
+            break;
+        }
+        let room = STDERR_LIMIT.saturating_sub(kept.len());
+        kept.extend_from_slice(&chunk[..n.min(room)]);
+    }
+    String::from_utf8_lossy(&kept).into_owned()
+}
+
+/// Turns py-spy's permission errors into instructions
+fn describe_failure(stderr: &str) -> String {
+    let lower = stderr.to_ascii_lowercase();
+    let denied = ["permission denied", "operation not permitted", "requires root", "ptrace"].iter().any(|s| lower.contains(s));
+    if !denied {
+        return format!("py-spy failed: {}", stderr);
+    }
+    let hint = if cfg!(target_os = "macos") {
+        "py-spy must run as root on macOS"
+    } else if cfg!(target_os = "linux") {
+        "allow ptrace with `sudo sysctl kernel.yama.ptrace_scope=0`, or grant it to py-spy with `sudo setcap cap_sys_ptrace+ep $(which py-spy)`"
+    } else {
+        "run the viewer as administrator"
+    };
+    format!("py-spy is not allowed to read the backend's memory; {} ({})", hint, stderr)
+}
@@ -0,0 +1,128 @@
+//! Broken runtime venvs kept for post-mortem instead of deleted (`quarantine_venvs`
+//! config). ensure_runtime_python moves a venv it is about to rebuild to
+//! `<runtime python dir>/.quarantine/<unix ms>/`, with a `quarantine-reason.txt` saying why.
+//! Only the newest `quarantine_keep` are kept, and older ones go first once together
+//! they exceed `quarantine_max_bytes`.
+
+use std::path::{Path, PathBuf};
+
+use crate::{bootstrap, config};
+
+pub const DIR_NAME: &str = ".quarantine";
+const REASON_FILE: &str = "quarantine-reason.txt";
+
+/// A quarantined venv, returned by list_quarantined
+#[derive(Clone, serde::Serialize)]
+pub struct Quarantined {
+    pub path: PathBuf,
+    /// Unix timestamp in milliseconds of when it was moved aside
+    pub quarantined_at_ms: u64,
+    pub size_bytes: u64,
+    pub reason: Option<String>,
+}
+
+fn quarantine_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    bootstrap::runtime_python_dir(app).map(|dir| dir.join(DIR_NAME))
+}
+
+/// Gets a broken venv out of the way for a rebuild: quarantined when enabled, deleted
+/// otherwise (or when moving it fails)
+pub fn discard_venv<R: tauri::Runtime>(app: &tauri::AppHandle<R>, venv_root: &Path, reason: &str) {
+    let cfg = config::get(app);
+    if cfg.quarantine_venvs {
+        if let Some(dir) = quarantine_dir(app) {
+            let now_ms = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+            let target = dir.join(now_ms.to_string());
+            // A rename within the runtime dir is cheap, whatever the venv's size
+            if std::fs::create_dir_all(&dir).is_ok() && std::fs::rename(venv_root, &target).is_ok() {
+                let _ = std::fs::write(target.join(REASON_FILE), reason);
+                prune(&dir, cfg.quarantine_keep as usize, cfg.quarantine_max_bytes);
+                return;
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(venv_root);
+}
+
+/// Quarantined venvs under `dir`, newest first
+fn entries(dir: &Path) -> Vec<Quarantined> {
+    let Ok(read) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut entries: Vec<Quarantined> = read
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let quarantined_at_ms = entry.file_name().to_str()?.parse().ok()?;
+            let path = entry.path();
+            Some(Quarantined {
+                size_bytes: tree_size(&path),
+                reason: std::fs::read_to_string(path.join(REASON_FILE)).ok(),
+                path,
+                quarantined_at_ms,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.quarantined_at_ms));
+    entries
+}
+
+fn tree_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return 0 };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|read| read.filter_map(Result::ok).map(|entry| tree_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Deletes all but the newest `keep` entries, then the oldest remaining ones while the
+/// total exceeds `max_bytes` (the newest is always kept). Returns how many were deleted.
+fn prune(dir: &Path, keep: usize, max_bytes: u64) -> usize {
+    let mut total = 0u64;
+    let mut removed = 0;
+    for (index, entry) in entries(dir).into_iter().enumerate() {
+        let over_budget = index > 0 && total + entry.size_bytes > max_bytes;
+        if index >= keep.max(1) || over_budget {
+            removed += usize::from(std::fs::remove_dir_all(&entry.path).is_ok());
+        } else {
+            total += entry.size_bytes;
+        }
+    }
+    removed
+}
+
+pub fn list<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<Quarantined> {
+    quarantine_dir(app).map(|dir| entries(&dir)).unwrap_or_default()
+}
+
+/// Deletes every quarantined venv, returning how many there were
+pub fn clear<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<usize, String> {
+    let Some(dir) = quarantine_dir(app) else { return Ok(0) };
+    let count = entries(&dir).len();
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(count),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(format!("Could not remove {}: {}", dir.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prunes_by_count_then_size() {
+        let dir = std::env::temp_dir().join(format!("tv-quarantine-{}", std::process::id()));
+        for (name, bytes) in [("1000", 10), ("2000", 10), ("3000", 10), ("4000", 10), ("junk", 1)] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("file"), vec![0u8; bytes]).unwrap();
+        }
+        assert_eq!(prune(&dir, 3, u64::MAX), 1);
+        let left: Vec<u64> = entries(&dir).iter().map(|e| e.quarantined_at_ms).collect();
+        assert_eq!(left, vec![4000, 3000, 2000]);
+        // Over budget: only the newest survives, however large
+        assert_eq!(prune(&dir, 3, 15), 2);
+        assert_eq!(entries(&dir).len(), 1);
+        assert!(dir.join("junk").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
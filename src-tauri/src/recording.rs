@@ -0,0 +1,125 @@
+//! Session recording: the full `python-log` stream written to a `.tvlog` file so a
+//! problematic session can be shared and replayed into the UI later.
+//!
+//! A `.tvlog` file is JSON lines: a header `{"tvlog":1,..}` followed by one
+//! `{"t_ms":<offset since start>,"line":<LogLine>}` entry per log line.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::Emitter;
+
+use crate::logs::LogLine;
+
+/// Format version written to and accepted from the header
+const FORMAT_VERSION: u64 = 1;
+
+/// Accepted replay speed factors (1.0 = original timing)
+pub const SPEED_RANGE: std::ops::RangeInclusive<f64> = 1.0..=1000.0;
+
+struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+    lines: u64,
+}
+
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+// Bumped by every replay so an older one stops emitting
+static REPLAY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Header {
+    tvlog: u64,
+    viewer_version: String,
+    started_at_ms: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    t_ms: u64,
+    line: LogLine,
+}
+
+/// Starts recording to `path` (created or truncated), replacing any running recording
+pub fn start(path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    let mut file = BufWriter::new(file);
+    let header = Header {
+        tvlog: FORMAT_VERSION,
+        viewer_version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+    };
+    writeln!(file, "{}", serde_json::to_string(&header).map_err(|e| e.to_string())?)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    let previous = RECORDER.lock().unwrap_or_else(|e| e.into_inner()).replace(Recorder { file, started: Instant::now(), lines: 0 });
+    if let Some(mut previous) = previous {
+        let _ = previous.file.flush();
+    }
+    Ok(())
+}
+
+/// Stops the running recording, returning the number of lines written
+pub fn stop() -> Result<u64, String> {
+    let recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let mut recorder = recorder.ok_or_else(|| "No recording in progress".to_string())?;
+    recorder.file.flush().map_err(|e| format!("Could not write recording: {}", e))?;
+    Ok(recorder.lines)
+}
+
+/// Appends `line` to the running recording, if any. Called for every line of the log
+/// pipeline, before muting, pausing or level filtering.
+pub fn record(line: &LogLine) {
+    let mut guard = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(recorder) = guard.as_mut() else { return };
+    let entry = serde_json::json!({ "t_ms": recorder.started.elapsed().as_millis() as u64, "line": line });
+    if writeln!(recorder.file, "{}", entry).is_ok() {
+        recorder.lines += 1;
+    }
+}
+
+/// Payload of `python-replay-finished`
+#[derive(Clone, serde::Serialize)]
+struct ReplayFinished {
+    lines: u64,
+    /// A newer replay took over before this one reached the end
+    cancelled: bool,
+}
+
+/// Emits the entries of a `.tvlog` file as `python-log` events marked `replayed`, keeping
+/// their original spacing divided by `speed`. Runs in the background; a new replay
+/// cancels the previous one. Fails up front if the file isn't a recording.
+pub fn replay<R: tauri::Runtime>(app: &tauri::AppHandle<R>, path: &Path, speed: f64) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header = lines.next().and_then(Result::ok).and_then(|l| serde_json::from_str::<Header>(&l).ok());
+    match header {
+        Some(h) if h.tvlog == FORMAT_VERSION => {}
+        Some(h) => return Err(format!("Unsupported recording format version {}", h.tvlog)),
+        None => return Err(format!("{} is not a .tvlog recording", path.display())),
+    }
+
+    let generation = REPLAY_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let begun = Instant::now();
+        let mut emitted = 0;
+        for entry in lines.map_while(Result::ok).filter_map(|l| serde_json::from_str::<Entry>(&l).ok()) {
+            let due = Duration::from_secs_f64(entry.t_ms as f64 / 1000.0 / speed);
+            if let Some(wait) = due.checked_sub(begun.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            if REPLAY_GENERATION.load(Ordering::SeqCst) != generation {
+                let _ = app.emit("python-replay-finished", ReplayFinished { lines: emitted, cancelled: true });
+                return;
+            }
+            let _ = app.emit("python-log", LogLine { replayed: true, ..entry.line });
+            emitted += 1;
+        }
+        let _ = app.emit("python-replay-finished", ReplayFinished { lines: emitted, cancelled: false });
+    });
+    Ok(())
+}
@@ -0,0 +1,288 @@
+//! Field updates of the televoodoo package from a remote manifest, independent of app
+//! updates. The manifest endpoint (`update_url`) returns JSON like
+//!
+//! ```json
+//! { "version": "1.4.0", "url": "https://…/televoodoo-1.4.0-py3-none-any.whl",
+//!   "sha256": "<hex>", "signature": "<hex>", "size": 123456 }
+//! ```
+//!
+//! where `signature` is an Ed25519 signature, made with the key whose public half is
+//! configured as `update_public_key` (PEM), of the manifest fields as signed_message lays
+//! them out:
+//!
+//! ```text
+//! televoodoo-update
+//! version=1.4.0
+//! sha256=<lowercase hex>
+//! url=https://…/televoodoo-1.4.0-py3-none-any.whl
+//! ```
+//!
+//! Signing the version along with the checksum means an old signed package can't be served
+//! under a newer version label, and an old manifest replayed as-is offers no newer version.
+//! Packages are only installed after both the signature and the checksum check out;
+//! without a configured key nothing is installed. Downloads use the system `curl` and signature checks the system
+//! `openssl` (1.1.1 or later), so a machine without them fails closed.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tauri::Emitter;
+
+use crate::logs::{self, Stream};
+use crate::update::compare_versions;
+use crate::{bootstrap, config};
+
+/// An available update, returned by check_remote_update
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub url: String,
+    pub sha256: String,
+    pub signature: String,
+    /// Package size in bytes, for download progress
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Version installed in the runtime venv
+    #[serde(default)]
+    pub current_version: Option<String>,
+}
+
+/// Step of apply, reported as `python-remote-update-progress`
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateStep {
+    Downloading,
+    Verifying,
+    Installing,
+    Restarting,
+}
+
+/// Payload of `python-remote-update-progress`
+#[derive(Clone, serde::Serialize)]
+struct UpdateProgress {
+    step: UpdateStep,
+    downloaded_bytes: Option<u64>,
+    total_bytes: Option<u64>,
+}
+
+// Result of the last check_remote_update, applied by apply_remote_update
+static AVAILABLE: Mutex<Option<UpdateInfo>> = Mutex::new(None);
+
+/// Emits a progress event for a step without byte counts
+pub fn emit_step<R: tauri::Runtime>(app: &tauri::AppHandle<R>, step: UpdateStep) {
+    let _ = app.emit("python-remote-update-progress", UpdateProgress { step, downloaded_bytes: None, total_bytes: None });
+}
+
+fn runtime_python<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    let python = bootstrap::runtime_python_dir(app)
+        .ok_or_else(|| "Could not determine app data directory".to_string())?
+        .join(".venv")
+        .join("bin")
+        .join("python");
+    if !python.exists() {
+        return Err("Runtime venv not bootstrapped yet; start the backend once first".to_string());
+    }
+    Ok(python)
+}
+
+/// Only https is accepted, so the manifest and package can't be swapped in transit
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if !url.starts_with("https://") || url.contains(char::is_whitespace) {
+        return Err(format!("Update URLs must be https: {:?}", url));
+    }
+    Ok(())
+}
+
+/// Fetches the manifest at the `update_url` config and returns it when it offers a newer
+/// version than the one installed in the runtime venv
+pub fn check<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<Option<UpdateInfo>, String> {
+    let url = config::get(app).update_url.ok_or_else(|| "No update_url configured".to_string())?;
+    validate_url(&url)?;
+    let output = curl().args(["--max-filesize", "1048576", &url]).output().map_err(|e| format!("Could not run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Fetching {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let mut info: UpdateInfo = serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid update manifest: {}", e))?;
+    validate_url(&info.url)?;
+    package_file_name(&info.url)?;
+    info.current_version = installed_version(&runtime_python(app)?);
+    let newer = info.current_version.as_deref().is_none_or(|current| compare_versions(&info.version, current).is_gt());
+    let info = newer.then_some(info);
+    *AVAILABLE.lock().unwrap_or_else(|e| e.into_inner()) = info.clone();
+    Ok(info)
+}
+
+/// Downloads the update found by the last check, verifies its checksum and signature and
+/// installs it into the runtime venv. Returns the installed version. The caller stops and
+/// restarts the backend around it.
+pub fn apply<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<String, String> {
+    let info = AVAILABLE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let info = info.ok_or_else(|| "No update available; run check_remote_update first".to_string())?;
+    let public_key = config::get(app).update_public_key.ok_or_else(|| "No update_public_key configured; refusing to install unsigned updates".to_string())?;
+    let python = runtime_python(app)?;
+
+    let dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?.join("updates");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let result = verify_manifest(&dir, &info, &public_key).and_then(|()| download(app, &info, &dir)).and_then(|package| {
+        emit_step(app, UpdateStep::Verifying);
+        verify_sha256(&package, &info.sha256)?;
+        emit_step(app, UpdateStep::Installing);
+        let mut cmd = Command::new(&python);
+        cmd.args(["-m", "pip", "install", "--upgrade"]).args(config::get(app).pip_network_args()).arg(&package);
+        match logs::run_streamed(app, &mut cmd, "pip") {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("pip install failed with {}", status)),
+            Err(e) => Err(format!("Could not run pip: {}", e)),
+        }
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+    result?;
+    AVAILABLE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    logs::forward_tool_line(app, "update", Stream::Stdout, &format!("Installed televoodoo {} from {}", info.version, info.url));
+    Ok(info.version)
+}
+
+fn curl() -> Command {
+    let mut cmd = Command::new("curl");
+    cmd.args(["--fail", "--silent", "--show-error", "--location", "--proto", "=https", "--max-time", "600"]);
+    cmd
+}
+
+/// Last path segment of the package URL; pip needs the original wheel/sdist name
+fn package_file_name(url: &str) -> Result<String, String> {
+    let name = url.split(['?', '#']).next().unwrap_or_default().rsplit('/').next().unwrap_or_default();
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || "._-+".contains(c)) && !name.starts_with('.');
+    if !valid || !(name.ends_with(".whl") || name.ends_with(".tar.gz")) {
+        return Err(format!("Update package must be a .whl or .tar.gz file, got {:?}", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Downloads the package into `dir`, emitting progress every 250 ms
+fn download<R: tauri::Runtime>(app: &tauri::AppHandle<R>, info: &UpdateInfo, dir: &Path) -> Result<PathBuf, String> {
+    let package = dir.join(package_file_name(&info.url)?);
+    let mut child = curl()
+        .arg("--output")
+        .arg(&package)
+        .arg(&info.url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run curl: {}", e))?;
+    let status = loop {
+        let downloaded_bytes = std::fs::metadata(&package).map(|m| m.len()).ok();
+        let _ = app.emit("python-remote-update-progress", UpdateProgress { step: UpdateStep::Downloading, downloaded_bytes, total_bytes: info.size });
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(Duration::from_millis(250)),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Could not wait for curl: {}", e));
+            }
+        }
+    };
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        return Err(format!("Downloading {} failed: {}", info.url, stderr.trim()));
+    }
+    Ok(package)
+}
+
+fn verify_sha256(package: &Path, expected: &str) -> Result<(), String> {
+    let bytes = std::fs::read(package).map_err(|e| format!("Could not read {}: {}", package.display(), e))?;
+    let actual: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!("Checksum mismatch: expected {}, got {}", expected, actual));
+    }
+    Ok(())
+}
+
+/// The manifest fields covered by its signature, see the module doc
+fn signed_message(info: &UpdateInfo) -> String {
+    format!("televoodoo-update\nversion={}\nsha256={}\nurl={}\n", info.version, info.sha256.trim().to_ascii_lowercase(), info.url)
+}
+
+/// Checks the manifest's Ed25519 signature against `public_key` (PEM) with openssl
+fn verify_manifest(dir: &Path, info: &UpdateInfo, public_key: &str) -> Result<(), String> {
+    let message = dir.join("manifest.signed");
+    std::fs::write(&message, signed_message(info)).map_err(|e| e.to_string())?;
+    verify_signature(dir, &message, &info.signature, public_key)
+}
+
+/// Checks the Ed25519 `signature` (hex) of the file `signed` against `public_key` (PEM)
+fn verify_signature(dir: &Path, signed: &Path, signature: &str, public_key: &str) -> Result<(), String> {
+    let signature = decode_hex(signature.trim()).ok_or_else(|| "Update signature is not valid hex".to_string())?;
+    let (key_path, sig_path) = (dir.join("update-key.pem"), dir.join("manifest.sig"));
+    std::fs::write(&key_path, public_key).map_err(|e| e.to_string())?;
+    std::fs::write(&sig_path, signature).map_err(|e| e.to_string())?;
+    let output = Command::new("openssl")
+        .args(["pkeyutl", "-verify", "-pubin", "-rawin", "-inkey"])
+        .arg(&key_path)
+        .arg("-in")
+        .arg(signed)
+        .arg("-sigfile")
+        .arg(&sig_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run openssl to verify the update signature: {}", e))?;
+    if !output.status.success() {
+        return Err("Update signature verification failed; not installing".to_string());
+    }
+    Ok(())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// televoodoo version installed in the venv of `python`, per its package metadata
+fn installed_version(python: &Path) -> Option<String> {
+    Command::new(python)
+        .args(["-c", "import importlib.metadata as m; print(m.version('televoodoo'))"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_package_file_names() {
+        assert_eq!(package_file_name("https://x.io/dl/televoodoo-1.4.0-py3-none-any.whl?sig=1").unwrap(), "televoodoo-1.4.0-py3-none-any.whl");
+        assert!(package_file_name("https://x.io/dl/televoodoo.sh").is_err());
+        assert!(package_file_name("https://x.io/dl/").is_err());
+        assert_eq!(decode_hex("00ff10"), Some(vec![0, 255, 16]));
+        assert_eq!(decode_hex("0g"), None);
+    }
+
+    #[test]
+    fn signature_covers_version_checksum_and_url() {
+        let info = UpdateInfo {
+            version: "1.4.0".to_string(),
+            url: "https://x.io/dl/televoodoo-1.4.0-py3-none-any.whl".to_string(),
+            sha256: " ABcd\n".to_string(),
+            signature: String::new(),
+            size: None,
+            current_version: None,
+        };
+        assert_eq!(signed_message(&info), "televoodoo-update\nversion=1.4.0\nsha256=abcd\nurl=https://x.io/dl/televoodoo-1.4.0-py3-none-any.whl\n");
+    }
+}
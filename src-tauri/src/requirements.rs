@@ -0,0 +1,288 @@
+//! Parser for pip requirements files, so a malformed `requirements.txt` is reported with
+//! its file and line before bootstrap hands it to pip, and the pins can be reviewed
+//! before installing.
+//!
+//! Handles PEP 508 requirements (`name[extras] specifiers ; markers` and `name @ url`),
+//! comments, `\` line continuations, per-requirement `--hash` options, `-r`/`--requirement`
+//! includes (relative to the including file), `-e`/`--editable` installs and direct
+//! archive paths or URLs. Other global options (`--index-url`, `-c`, ...) are skipped.
+
+use std::path::{Path, PathBuf};
+
+/// One requirement, returned by parse_requirements
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Requirement {
+    /// Distribution name, or the `#egg=` name / archive name for editable and direct installs
+    pub name: String,
+    pub extras: Vec<String>,
+    /// Version specifiers as written, e.g. `>=1.24,<2`
+    pub specifier: Option<String>,
+    /// Direct reference (`name @ url`), editable target or archive path
+    pub url: Option<String>,
+    /// Environment markers after `;`
+    pub markers: Option<String>,
+    /// True for exactly one `==` specifier without a wildcard, or `===`
+    pub pinned: bool,
+    pub editable: bool,
+    /// File and 1-based line the requirement starts on
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+const OPERATORS: [&str; 8] = ["===", "~=", "==", "!=", "<=", ">=", "<", ">"];
+
+/// Options that take a value and don't name a requirement
+const GLOBAL_OPTIONS: [&str; 12] = [
+    "-i", "--index-url", "--extra-index-url", "-f", "--find-links", "-c", "--constraint",
+    "--no-binary", "--only-binary", "--trusted-host", "--use-feature", "--config-settings",
+];
+
+/// Parses `path` and everything it includes, in file order
+pub fn parse_file(path: &Path) -> Result<Vec<Requirement>, String> {
+    let mut requirements = Vec::new();
+    parse_into(path, &mut Vec::new(), &mut requirements)?;
+    Ok(requirements)
+}
+
+fn parse_into(path: &Path, stack: &mut Vec<PathBuf>, out: &mut Vec<Requirement>) -> Result<(), String> {
+    let canonical = path.canonicalize().map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    if stack.contains(&canonical) {
+        return Err(format!("{} includes itself", path.display()));
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    stack.push(canonical);
+    for (line_no, line) in logical_lines(&text) {
+        let located = |message: String| format!("{}:{}: {}", path.display(), line_no, message);
+        match parse_line(&line).map_err(located)? {
+            Line::Empty => {}
+            Line::Include(target) => {
+                let target = path.parent().unwrap_or(Path::new(".")).join(target);
+                parse_into(&target, stack, out).map_err(|e| located(format!("in -r {}: {}", target.display(), e)))?;
+            }
+            Line::Requirement(mut requirement) => {
+                requirement.file = path.to_path_buf();
+                requirement.line = line_no;
+                out.push(*requirement);
+            }
+        }
+    }
+    stack.pop();
+    Ok(())
+}
+
+/// Lines with comments stripped and continuations joined, numbered by their first line
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, raw) in text.lines().enumerate() {
+        // pip only treats `#` as a comment at the start or after whitespace (URLs use it)
+        let line = if raw.trim_start().starts_with('#') {
+            ""
+        } else {
+            match raw.find(" #").or_else(|| raw.find("\t#")) {
+                Some(comment) => &raw[..comment],
+                None => raw,
+            }
+        };
+        let (line, continued) = match line.trim_end().strip_suffix('\\') {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        let (start, mut joined) = pending.take().unwrap_or((index + 1, String::new()));
+        joined.push_str(line);
+        if continued {
+            pending = Some((start, joined));
+        } else {
+            lines.push((start, joined));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+enum Line {
+    Empty,
+    Include(String),
+    Requirement(Box<Requirement>),
+}
+
+fn parse_line(line: &str) -> Result<Line, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(Line::Empty);
+    }
+    if line.starts_with('-') {
+        let (option, value) = match line.split_once(['=', ' ', '\t']) {
+            Some((option, value)) => (option, value.trim()),
+            None => (line, ""),
+        };
+        return match option {
+            "-r" | "--requirement" if !value.is_empty() => Ok(Line::Include(value.to_string())),
+            "-e" | "--editable" if !value.is_empty() => Ok(Line::Requirement(Box::new(editable(value)))),
+            "-r" | "--requirement" | "-e" | "--editable" => Err(format!("{} needs a value", option)),
+            _ if GLOBAL_OPTIONS.contains(&option) && value.is_empty() => Err(format!("{} needs a value", option)),
+            _ => Ok(Line::Empty),
+        };
+    }
+    // Per-requirement options follow the requirement itself
+    let spec = match line.find(" --") {
+        Some(options) => line[..options].trim(),
+        None => line,
+    };
+    parse_requirement(spec).map(|r| Line::Requirement(Box::new(r)))
+}
+
+fn empty_requirement(name: String) -> Requirement {
+    Requirement {
+        name,
+        extras: Vec::new(),
+        specifier: None,
+        url: None,
+        markers: None,
+        pinned: false,
+        editable: false,
+        file: PathBuf::new(),
+        line: 0,
+    }
+}
+
+fn editable(target: &str) -> Requirement {
+    let name = target
+        .split_once("#egg=")
+        .map(|(_, egg)| egg.split('&').next().unwrap_or_default().to_string())
+        .unwrap_or_else(|| target.trim_end_matches('/').rsplit('/').next().unwrap_or(target).to_string());
+    Requirement { url: Some(target.to_string()), editable: true, ..empty_requirement(name) }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._-".contains(c)
+}
+
+/// Parses one PEP 508 requirement, or a direct archive path/URL
+fn parse_requirement(spec: &str) -> Result<Requirement, String> {
+    if (spec.contains("://") && !spec.contains('@')) || spec.starts_with(['.', '/']) {
+        // Direct archive: the name is the file name up to its version
+        let file = spec.split(['#', '?']).next().unwrap_or_default().rsplit('/').next().unwrap_or_default();
+        let name = file.split('-').next().unwrap_or_default();
+        if name.is_empty() {
+            return Err(format!("cannot determine the package name of {:?}", spec));
+        }
+        return Ok(Requirement { url: Some(spec.to_string()), ..empty_requirement(name.to_string()) });
+    }
+
+    let name_len = spec.find(|c: char| !is_name_char(c)).unwrap_or(spec.len());
+    let name = &spec[..name_len];
+    if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_alphanumeric()) || !name.ends_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err(format!("invalid requirement {:?}", spec));
+    }
+    let mut requirement = empty_requirement(name.to_string());
+    let mut rest = spec[name_len..].trim_start();
+
+    if let Some(after) = rest.strip_prefix('[') {
+        let (extras, after) = after.split_once(']').ok_or_else(|| format!("unclosed extras in {:?}", spec))?;
+        for extra in extras.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            if !extra.chars().all(is_name_char) {
+                return Err(format!("invalid extra {:?}", extra));
+            }
+            requirement.extras.push(extra.to_string());
+        }
+        rest = after.trim_start();
+    }
+
+    // Markers start at `;`; after a URL only when preceded by whitespace
+    let (body, markers) = if let Some(url) = rest.strip_prefix('@') {
+        let url = url.trim_start();
+        match url.find(" ;").or_else(|| url.find("\t;")) {
+            Some(at) => (&rest[..rest.len() - url.len() + at], Some(url[at..].trim_start()[1..].trim())),
+            None => (rest, None),
+        }
+    } else {
+        match rest.split_once(';') {
+            Some((body, markers)) => (body, Some(markers.trim())),
+            None => (rest, None),
+        }
+    };
+    if let Some(markers) = markers {
+        if markers.is_empty() {
+            return Err(format!("empty environment marker in {:?}", spec));
+        }
+        requirement.markers = Some(markers.to_string());
+    }
+
+    let body = body.trim();
+    if let Some(url) = body.strip_prefix('@') {
+        let url = url.trim();
+        if url.is_empty() {
+            return Err(format!("missing URL after @ in {:?}", spec));
+        }
+        requirement.url = Some(url.to_string());
+        return Ok(requirement);
+    }
+    // `name (>=1.0)` is still accepted by pip
+    let body = body.strip_prefix('(').and_then(|b| b.strip_suffix(')')).unwrap_or(body).trim();
+    if body.is_empty() {
+        return Ok(requirement);
+    }
+    let clauses: Vec<&str> = body.split(',').map(str::trim).collect();
+    for clause in &clauses {
+        let operator = OPERATORS.iter().find(|op| clause.starts_with(*op)).ok_or_else(|| format!("invalid version specifier {:?} in {:?}", clause, spec))?;
+        let version = clause[operator.len()..].trim();
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_alphanumeric() || ".*+!_-".contains(c)) {
+            return Err(format!("invalid version in {:?}", clause));
+        }
+    }
+    requirement.pinned = clauses.len() == 1 && (clauses[0].starts_with("===") || (clauses[0].starts_with("==") && !clauses[0].contains('*')));
+    requirement.specifier = Some(clauses.join(","));
+    Ok(requirement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requirements_with_includes() {
+        let dir = std::env::temp_dir().join(format!("televoodoo-requirements-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("extra")).unwrap();
+        std::fs::write(
+            dir.join("requirements.txt"),
+            concat!(
+                "# runtime deps\n",
+                "--index-url https://pypi.org/simple\n",
+                "numpy==1.26.4 --hash=sha256:abc\n",
+                "opencv-python>=4.8, <5 ; platform_system != \"Darwin\"\n",
+                "rich[jupyter] \\\n",
+                "    ~=13.0\n",
+                "-r extra/dev.txt\n",
+                "-e git+https://github.com/x/y.git#egg=ytool\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join("extra").join("dev.txt"), "pytest @ https://x.io/pytest.whl ; python_version >= \"3.9\"\n").unwrap();
+        let parsed = parse_file(&dir.join("requirements.txt")).unwrap();
+        let summary: Vec<(&str, Option<&str>, bool, usize)> =
+            parsed.iter().map(|r| (r.name.as_str(), r.specifier.as_deref(), r.pinned, r.line)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("numpy", Some("==1.26.4"), true, 3),
+                ("opencv-python", Some(">=4.8,<5"), false, 4),
+                ("rich", Some("~=13.0"), false, 5),
+                ("pytest", None, false, 1),
+                ("ytool", None, false, 8),
+            ]
+        );
+        assert_eq!(parsed[1].markers.as_deref(), Some("platform_system != \"Darwin\""));
+        assert_eq!(parsed[2].extras, vec!["jupyter".to_string()]);
+        assert_eq!(parsed[3].url.as_deref(), Some("https://x.io/pytest.whl"));
+        assert_eq!(parsed[3].markers.as_deref(), Some("python_version >= \"3.9\""));
+        assert!(parsed[4].editable);
+
+        std::fs::write(dir.join("bad.txt"), "numpy\nscipy=1.0\n").unwrap();
+        let err = parse_file(&dir.join("bad.txt")).unwrap_err();
+        assert!(err.contains("bad.txt:2:"), "{}", err);
+        std::fs::write(dir.join("loop.txt"), "-r loop.txt\n").unwrap();
+        assert!(parse_file(&dir.join("loop.txt")).unwrap_err().contains("includes itself"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,102 @@
+//! Optional CPU/memory caps for the backend. On Linux the backend runs in a transient
+//! systemd scope (cgroup v2) with CPUQuota/MemoryMax; without a usable systemd user
+//! manager only the memory cap is applied, as an address-space rlimit. Other platforms
+//! log a warning and run the backend unrestricted.
+
+use std::process::Command;
+
+use crate::logs::{self, Stream};
+
+/// Checks the requested caps: CPUs in (0, 1024], memory at least 16 MiB
+pub fn validate(cpu_quota: Option<f64>, mem_limit: Option<u64>) -> Result<(), String> {
+    if let Some(cpus) = cpu_quota {
+        if !(cpus > 0.0 && cpus <= 1024.0) {
+            return Err(format!("cpu_quota must be between 0 and 1024 CPUs, got {}", cpus));
+        }
+    }
+    if let Some(bytes) = mem_limit {
+        if bytes < 16 * 1024 * 1024 {
+            return Err(format!("mem_limit must be at least 16 MiB, got {} bytes", bytes));
+        }
+    }
+    Ok(())
+}
+
+/// Returns `cmd` set up to run under the limits, wrapped in `systemd-run` when needed
+#[cfg(target_os = "linux")]
+pub fn apply<R: tauri::Runtime>(app: &tauri::AppHandle<R>, mut cmd: Command, cpu_quota: Option<f64>, mem_limit: Option<u64>) -> Command {
+    if cpu_quota.is_none() && mem_limit.is_none() {
+        return cmd;
+    }
+    if systemd_scope_available() {
+        return wrap_in_scope(&cmd, cpu_quota, mem_limit);
+    }
+    if cpu_quota.is_some() {
+        warn(app, "cpu_quota needs a systemd user session (cgroup v2); running without a CPU cap");
+    }
+    if let Some(bytes) = mem_limit {
+        warn(app, "no systemd user session; capping memory with RLIMIT_AS instead of a cgroup");
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            cmd.pre_exec(move || {
+                let limit = libc::rlimit { rlim_cur: bytes as libc::rlim_t, rlim_max: bytes as libc::rlim_t };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    cmd
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: Command, cpu_quota: Option<f64>, mem_limit: Option<u64>) -> Command {
+    if cpu_quota.is_some() || mem_limit.is_some() {
+        warn(app, "cpu_quota/mem_limit are only supported on Linux; running without limits");
+    }
+    cmd
+}
+
+// Whether `systemd-run --user --scope` works here (cached after the first probe)
+#[cfg(target_os = "linux")]
+fn systemd_scope_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        std::fs::metadata("/sys/fs/cgroup/cgroup.controllers").is_ok()
+            && Command::new("systemd-run")
+                .args(["--user", "--scope", "--quiet", "--collect", "true"])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+    })
+}
+
+/// `systemd-run --scope` execs the command itself, so the backend keeps the spawned pid
+#[cfg(target_os = "linux")]
+fn wrap_in_scope(cmd: &Command, cpu_quota: Option<f64>, mem_limit: Option<u64>) -> Command {
+    let mut scope = Command::new("systemd-run");
+    scope.args(["--user", "--scope", "--quiet", "--collect"]);
+    if let Some(cpus) = cpu_quota {
+        scope.arg("-p").arg(format!("CPUQuota={}%", (cpus * 100.0).round() as u64));
+    }
+    if let Some(bytes) = mem_limit {
+        scope.arg("-p").arg(format!("MemoryMax={}", bytes));
+    }
+    scope.arg("--").arg(cmd.get_program()).args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => scope.env(key, value),
+            None => scope.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        scope.current_dir(dir);
+    }
+    scope
+}
+
+fn warn<R: tauri::Runtime>(app: &tauri::AppHandle<R>, message: &str) {
+    logs::forward_tool_line(app, "limits", Stream::Stderr, &format!("WARNING: {}", message));
+}
@@ -0,0 +1,37 @@
+//! Detection of the machine resuming from sleep. Threads don't run while the system is
+//! suspended, so a ticker whose wall-clock time jumps far past its tick interval was
+//! frozen; this works the same on every platform without power-notification APIs. A
+//! large forward clock adjustment looks the same, hence the debounce.
+
+use std::time::{Duration, SystemTime};
+
+use crate::config;
+
+/// Interval of the wall-clock ticker
+const TICK: Duration = Duration::from_secs(2);
+
+/// At most one resume is reported per this window
+const DEBOUNCE: Duration = Duration::from_secs(60);
+
+/// Starts the ticker; `on_resume` runs on its thread with the approximate time asleep
+pub fn watch<R: tauri::Runtime>(app: tauri::AppHandle<R>, on_resume: impl Fn(&tauri::AppHandle<R>, Duration) + Send + 'static) {
+    std::thread::spawn(move || {
+        let mut last_tick = SystemTime::now();
+        let mut last_resume: Option<SystemTime> = None;
+        loop {
+            std::thread::sleep(TICK);
+            let now = SystemTime::now();
+            // A clock set backwards reads as no gap
+            let gap = now.duration_since(last_tick).unwrap_or_default().saturating_sub(TICK);
+            last_tick = now;
+            if gap < Duration::from_millis(config::get(&app).resume_gap_ms) {
+                continue;
+            }
+            if last_resume.is_some_and(|t| now.duration_since(t).unwrap_or_default() < DEBOUNCE) {
+                continue;
+            }
+            last_resume = Some(now);
+            on_resume(&app, gap);
+        }
+    });
+}
@@ -0,0 +1,139 @@
+//! Opt-in check-and-repair of the packaged runtime at app launch (`startup_self_repair`
+//! config), so an operator's first Start finds a working environment:
+//!
+//! - `venv`: the runtime venv exists, its `pyvenv.cfg` is sound and its python runs;
+//!   otherwise it is rebuilt from the bundle
+//! - `source`: the runtime televoodoo copy matches the bundled source (bundle_fingerprint
+//!   checksums); otherwise it is re-synced and reinstalled
+//! - `arch`: the venv python matches the app's CPU architecture; reported only, since a
+//!   rebuild would use the same base interpreter
+//!
+//! The checks hold the instance lock; when another viewer instance holds it nothing is
+//! checked and the report says so. The result is emitted as `python-self-repair`.
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tauri::Emitter;
+
+use crate::bootstrap::{self, VenvHealth};
+use crate::logs::{self, Stream};
+use crate::{instance_lock, preflight, update};
+
+// Held while a check runs; run_start waits for it so a start never races a rebuild
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Outcome of one check
+#[derive(Clone, serde::Serialize)]
+pub struct RepairCheck {
+    /// "venv", "source" or "arch"
+    pub name: &'static str,
+    /// Whether the check passed before any repair
+    pub ok: bool,
+    pub detail: String,
+    /// A repair was attempted and succeeded
+    pub repaired: bool,
+    /// Why a repair failed, if one was attempted
+    pub error: Option<String>,
+}
+
+/// Payload of `python-self-repair`, returned by run_self_repair
+#[derive(Clone, serde::Serialize)]
+pub struct RepairReport {
+    pub checks: Vec<RepairCheck>,
+    /// True when every check passed or was repaired; false when they were skipped
+    pub healthy: bool,
+    /// Why no check ran (another instance holds the runtime lock)
+    pub skipped: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Blocks until a running check has finished
+pub fn wait_idle() {
+    drop(RUNNING.lock().unwrap_or_else(|e| e.into_inner()));
+}
+
+/// Runs all checks, repairing what fails, and emits the report
+pub fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> RepairReport {
+    let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let started = Instant::now();
+    // Kept if a backend start already holds it
+    let held = instance_lock::held();
+    if let Err(e) = instance_lock::acquire(app) {
+        let report = RepairReport { checks: Vec::new(), healthy: false, skipped: Some(e.to_string()), duration_ms: started.elapsed().as_millis() as u64 };
+        logs::forward_tool_line(app, "self-repair", Stream::Stderr, &format!("WARNING: self-repair skipped: {}", e));
+        let _ = app.emit("python-self-repair", &report);
+        return report;
+    }
+    let mut checks = vec![check_venv(app)];
+    // The other checks need a venv to look at
+    if checks[0].ok || checks[0].repaired {
+        checks.push(check_source(app));
+        checks.extend(check_arch(app));
+    }
+    if !held {
+        instance_lock::release();
+    }
+    let healthy = checks.iter().all(|c| c.ok || c.repaired);
+    let report = RepairReport { checks, healthy, skipped: None, duration_ms: started.elapsed().as_millis() as u64 };
+    for check in report.checks.iter().filter(|c| !c.ok) {
+        let outcome = match (&check.error, check.repaired) {
+            (Some(error), _) => format!("repair failed: {}", error),
+            (None, true) => "repaired".to_string(),
+            (None, false) => "not repairable".to_string(),
+        };
+        logs::forward_tool_line(app, "self-repair", Stream::Stderr, &format!("WARNING: {} check: {}; {}", check.name, check.detail, outcome));
+    }
+    let _ = app.emit("python-self-repair", &report);
+    report
+}
+
+fn check_venv<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> RepairCheck {
+    let Some(dir) = bootstrap::runtime_python_dir(app) else {
+        return RepairCheck { name: "venv", ok: false, detail: "Could not determine app data directory".to_string(), repaired: false, error: None };
+    };
+    let venv_root = dir.join(".venv");
+    let python = venv_root.join("bin").join("python");
+    let problem = if !python.exists() {
+        Some("runtime venv is missing".to_string())
+    } else {
+        match bootstrap::validate_venv(&venv_root) {
+            VenvHealth::Healthy if bootstrap::interpreter_works(&python) => None,
+            VenvHealth::Healthy => Some("runtime venv python does not start".to_string()),
+            health => Some(health.describe()),
+        }
+    };
+    let Some(detail) = problem else {
+        return RepairCheck { name: "venv", ok: true, detail: "healthy".to_string(), repaired: false, error: None };
+    };
+    // ensure_runtime_python removes a broken venv and bootstraps a fresh one
+    let result = match bootstrap::ensure_runtime_python(app) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err("no runtime venv could be built from the bundle".to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    RepairCheck { name: "venv", ok: false, detail, repaired: result.is_ok(), error: result.err() }
+}
+
+fn check_source<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> RepairCheck {
+    let Some(bundled) = update::bundle_fingerprint(app) else {
+        return RepairCheck { name: "source", ok: true, detail: "no bundled source to compare with".to_string(), repaired: false, error: None };
+    };
+    let runtime = update::runtime_televoodoo_dir(app).and_then(|dir| update::fingerprint_dir(app, &dir).ok());
+    if runtime.as_deref() == Some(bundled.as_str()) {
+        return RepairCheck { name: "source", ok: true, detail: "matches the bundle".to_string(), repaired: false, error: None };
+    }
+    let detail = match runtime {
+        Some(_) => "runtime televoodoo source differs from the bundle".to_string(),
+        None => "runtime televoodoo source is missing".to_string(),
+    };
+    let result = update::apply(app);
+    RepairCheck { name: "source", ok: false, detail, repaired: result.is_ok(), error: result.err() }
+}
+
+fn check_arch<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<RepairCheck> {
+    let python = bootstrap::runtime_python_dir(app)?.join(".venv").join("bin").join("python");
+    let arch = preflight::interpreter_arch(&python);
+    let detail = arch.warning.unwrap_or_else(|| format!("interpreter matches the app ({})", arch.app));
+    Some(RepairCheck { name: "arch", ok: arch.matches, detail, repaired: false, error: None })
+}
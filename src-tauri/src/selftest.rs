@@ -0,0 +1,157 @@
+//! Runs televoodoo's own self-test (`--selftest`) outside a normal launch, so the backend
+//! and its hardware access can be checked independently of the UI.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::bootstrap;
+use crate::logs::{self, Stream};
+use crate::EntryPoint;
+
+/// Flag passed to the backend entry point
+const SELFTEST_FLAG: &str = "--selftest";
+
+/// Trailing output lines kept in SelfTestResult
+const TAIL_LINES: usize = 50;
+
+/// Outcome of run_selftest
+#[derive(Clone, serde::Serialize)]
+pub struct SelfTestResult {
+    /// False when the backend rejected `--selftest` as an unknown argument
+    pub supported: bool,
+    /// The summary's verdict when one was found, else whether it exited 0
+    pub passed: bool,
+    pub tests_passed: Option<u32>,
+    pub tests_failed: Option<u32>,
+    /// Output line the summary was parsed from
+    pub summary: Option<String>,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Last TAIL_LINES lines of stdout and stderr, interleaved as read
+    pub output_tail: Vec<String>,
+}
+
+/// Runs `<python> <entry> --selftest`, streaming its output through the log pipeline
+/// (source "selftest") and killing it after `timeout`
+pub fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>, python: &Path, entry: &EntryPoint, timeout: Duration) -> Result<SelfTestResult, String> {
+    let started = Instant::now();
+    let mut cmd = Command::new(python);
+    cmd.args(entry.args()).arg(SELFTEST_FLAG).env("PYTHONUNBUFFERED", "1");
+    bootstrap::activate_venv(&mut cmd);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", python.display(), e))?;
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        bootstrap::pipe_lines(stdout, Stream::Stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        bootstrap::pipe_lines(stderr, Stream::Stderr, tx);
+    }
+
+    let mut tail = VecDeque::new();
+    let mut handle = |(stream, line): (Stream, String)| {
+        logs::forward_tool_line(app, "selftest", stream, &line);
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    };
+    let mut timed_out = false;
+    let status = loop {
+        while let Ok(item) = rx.try_recv() {
+            handle(item);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                timed_out = true;
+                let _ = child.kill();
+                break child.wait().ok();
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Could not wait for self-test: {}", e)),
+        }
+    };
+    // Lines still in flight when the process exited
+    while let Ok(item) = rx.recv_timeout(Duration::from_millis(200)) {
+        handle(item);
+    }
+
+    let output_tail: Vec<String> = tail.into_iter().collect();
+    let exit_code = status.and_then(|s| s.code()).filter(|_| !timed_out);
+    let supported = !output_tail.iter().any(|l| l.contains("unrecognized arguments") && l.contains(SELFTEST_FLAG));
+    let summary = parse_summary(&output_tail);
+    let passed = supported
+        && !timed_out
+        && summary.as_ref().map_or(exit_code == Some(0), |s| s.passed);
+    Ok(SelfTestResult {
+        supported,
+        passed,
+        tests_passed: summary.as_ref().and_then(|s| s.tests_passed),
+        tests_failed: summary.as_ref().and_then(|s| s.tests_failed),
+        summary: summary.map(|s| s.line),
+        exit_code,
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+        output_tail,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    line: String,
+    passed: bool,
+    tests_passed: Option<u32>,
+    tests_failed: Option<u32>,
+}
+
+/// Finds the last pass/fail summary line: pytest style (`3 passed, 1 failed in 0.2s`),
+/// unittest style (`OK`, `FAILED (failures=1)`) or a plain `SELFTEST: PASS`/`FAIL`
+fn parse_summary(lines: &[String]) -> Option<Summary> {
+    let count = |line: &str, word: &str| {
+        let words: Vec<&str> = line.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect();
+        words.windows(2).find(|w| w[1].eq_ignore_ascii_case(word)).and_then(|w| w[0].parse::<u32>().ok())
+    };
+    lines.iter().rev().find_map(|line| {
+        let trimmed = line.trim();
+        let upper = trimmed.to_ascii_uppercase();
+        let (tests_passed, tests_failed) = (count(trimmed, "passed"), count(trimmed, "failed"));
+        let passed = if tests_passed.is_some() || tests_failed.is_some() {
+            tests_failed.unwrap_or(0) == 0
+        } else if upper == "OK" || upper.starts_with("OK (") || upper.ends_with(": PASS") || upper.ends_with(" PASSED") {
+            true
+        } else if upper.starts_with("FAILED (") || upper.ends_with(": FAIL") || upper.ends_with(" FAILED") {
+            false
+        } else {
+            return None;
+        };
+        Some(Summary { line: trimmed.to_string(), passed, tests_passed, tests_failed })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_selftest_summaries() {
+        let pytest = parse_summary(&lines("collected 4 items\n==== 3 passed, 1 failed in 0.21s ====")).unwrap();
+        assert_eq!((pytest.passed, pytest.tests_passed, pytest.tests_failed), (false, Some(3), Some(1)));
+        assert!(parse_summary(&lines("Ran 5 tests in 0.010s\n\nOK")).unwrap().passed);
+        assert!(!parse_summary(&lines("FAILED (failures=2)")).unwrap().passed);
+        assert!(parse_summary(&lines("camera: ok\nSELFTEST: PASS\nbye")).unwrap().passed);
+        assert_eq!(parse_summary(&lines("just logging")), None);
+    }
+}
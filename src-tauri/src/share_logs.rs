@@ -0,0 +1,199 @@
+//! Uploading the recent log tail to a paste service, for getting logs to maintainers in a
+//! support channel. Sharing takes two steps so the user opts in every time: preview
+//! redacts the tail and returns the exact text with a single-use token, and share uploads
+//! that text only when handed the token. The endpoint (`paste_url` config, paste.rs by
+//! default) gets the text as a raw POST body and must answer with the paste's URL, which is
+//! what paste.rs and most self-hosted services (e.g. `rustypaste`, `microbin` raw mode) do.
+//!
+//! Redaction replaces values of secret-looking environment variables (the viewer's own and
+//! the env_file's), `NAME=value` assignments of such names, and any extra secrets the caller
+//! passes (the pairing code). A copy of the redacted text is always written under
+//! `<app data>/shared-logs/` first, so a failed upload still leaves something to attach.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{bootstrap, config, dotenv, logs, token};
+
+/// Log lines included, counted back from the newest
+const SHARE_LOG_LINES: usize = 500;
+/// A preview older than this can't be shared anymore
+const TOKEN_TTL: Duration = Duration::from_secs(600);
+const UPLOAD_TIMEOUT_SECS: u64 = 30;
+/// Name fragments of environment variables whose values are redacted
+const SECRET_WORDS: &[&str] = &["TOKEN", "SECRET", "PASSWORD", "PASSWD", "APIKEY", "API_KEY", "PRIVATE_KEY", "ACCESS_KEY", "CREDENTIAL", "COOKIE", "AUTH"];
+
+// The last preview; share takes it, so a token works once
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+struct Pending {
+    token: String,
+    text: String,
+    created: Instant,
+}
+
+/// Returned by preview_share_logs
+#[derive(Clone, serde::Serialize)]
+pub struct SharePreview {
+    /// Pass to share_logs to upload exactly `text`
+    pub token: String,
+    pub text: String,
+    /// Where it would be uploaded
+    pub paste_url: String,
+    pub lines: usize,
+    /// Number of redacted values and assignments
+    pub redactions: usize,
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    SECRET_WORDS.iter().any(|word| upper.contains(word))
+}
+
+/// `(name, value)` of secret-looking variables; values so short they'd match random text
+/// are left out
+fn secret_values(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let mut secrets: Vec<(String, String)> = vars.filter(|(name, value)| is_secret_name(name) && value.len() >= 4).collect();
+    // Longest first, so a value containing another is replaced whole
+    secrets.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+    secrets
+}
+
+/// Redacts `secrets` values and secret-named assignments in `text`, returning the result
+/// and the number of redactions
+fn redact(text: &str, secrets: &[(String, String)]) -> (String, usize) {
+    let mut count = 0;
+    let mut text = text.to_string();
+    for (name, value) in secrets {
+        count += text.matches(value.as_str()).count();
+        text = text.replace(value.as_str(), &format!("[REDACTED:{}]", name));
+    }
+    let assignment = regex::Regex::new(&format!(
+        r#"(?i)\b([A-Z0-9_.-]*(?:{})[A-Z0-9_.-]*)(\s*[=:]\s*)("[^"]*"|'[^']*'|[^\s,;&]+)"#,
+        SECRET_WORDS.join("|")
+    ))
+    .expect("valid redaction pattern");
+    let text = assignment
+        .replace_all(&text, |caps: &regex::Captures| {
+            if caps[3].starts_with("[REDACTED") {
+                return caps[0].to_string();
+            }
+            count += 1;
+            format!("{}{}[REDACTED]", &caps[1], &caps[2])
+        })
+        .into_owned();
+    (text, count)
+}
+
+/// Checks a paste endpoint: https only, as logs can contain hostnames and paths
+pub fn validate_paste_url(url: &str) -> Result<(), String> {
+    if !url.starts_with("https://") || url.contains(char::is_whitespace) {
+        return Err(format!("Paste URL must be https: {:?}", url));
+    }
+    Ok(())
+}
+
+/// Redacts the recent log tail and keeps it for share. `extra_secrets` are redacted too.
+pub fn preview<R: tauri::Runtime>(app: &tauri::AppHandle<R>, extra_secrets: Vec<(String, String)>) -> Result<SharePreview, String> {
+    let cfg = config::get(app);
+    let mut vars: Vec<(String, String)> = std::env::vars().collect();
+    if let Some(env) = cfg.env_file.as_deref().and_then(|path| dotenv::load(path).ok()) {
+        vars.extend(env.vars);
+    }
+    let mut secrets = secret_values(vars.into_iter());
+    secrets.extend(extra_secrets.into_iter().filter(|(_, value)| !value.is_empty()));
+    let from_seq = logs::next_seq().saturating_sub(SHARE_LOG_LINES as u64);
+    let lines = logs::recent_since(from_seq, SHARE_LOG_LINES);
+    let mut raw = format!(
+        "televoodoo-viewer {} ({} {}), last {} backend lines\n\n",
+        app.package_info().version,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        lines.len()
+    );
+    for line in &lines {
+        let marker = if line.stream == logs::Stream::Stderr { "!" } else { " " };
+        raw.push_str(&format!("{}{} {}\n", line.timestamp_ms, marker, line.text));
+    }
+    let (text, redactions) = redact(&raw, &secrets);
+    let token = token::random()?;
+    let preview = SharePreview { token: token.clone(), text: text.clone(), paste_url: cfg.paste_url, lines: lines.len(), redactions };
+    *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Pending { token, text, created: Instant::now() });
+    Ok(preview)
+}
+
+/// Uploads the previewed text and returns the paste URL. On failure the error names the
+/// local copy to attach instead.
+pub fn share<R: tauri::Runtime>(app: &tauri::AppHandle<R>, token: &str) -> Result<String, String> {
+    let pending = {
+        let mut guard = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        match guard.take() {
+            Some(pending) if token::matches(token, &pending.token) && pending.created.elapsed() < TOKEN_TTL => pending,
+            other => {
+                // A wrong token doesn't use up the real one
+                *guard = other.filter(|p| !token::matches(token, &p.token));
+                return Err("No matching log preview; preview the logs again and confirm sharing them".to_string());
+            }
+        }
+    };
+    let file = save_local(app, &pending.text)?;
+    let paste_url = config::get(app).paste_url;
+    upload(&paste_url, &file).map_err(|e| format!("Could not upload the logs ({}); they were saved to {}", e, file.display()))
+}
+
+fn save_local<R: tauri::Runtime>(app: &tauri::AppHandle<R>, text: &str) -> Result<PathBuf, String> {
+    let dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?.join("shared-logs");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let stamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let file = dir.join(format!("logs-{}.txt", stamp));
+    std::fs::write(&file, text).map_err(|e| format!("Could not write {}: {}", file.display(), e))?;
+    Ok(file)
+}
+
+fn upload(paste_url: &str, file: &std::path::Path) -> Result<String, String> {
+    validate_paste_url(paste_url)?;
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--proto", "=https", "--max-time", &UPLOAD_TIMEOUT_SECS.to_string()])
+        .args(["--header", "Content-Type: text/plain; charset=utf-8", "--data-binary"])
+        .arg(format!("@{}", file.display()))
+        .arg(paste_url)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let url = stdout.trim();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(format!("unexpected response from {}: {:?}", paste_url, url.chars().take(200).collect::<String>()));
+    }
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_env_values_and_assignments() {
+        let secrets = secret_values(
+            [
+                ("HF_TOKEN".to_string(), "hf_abcdef123".to_string()),
+                ("HOME".to_string(), "/home/op".to_string()),
+                ("DB_PASSWORD".to_string(), "abc".to_string()),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(secrets.len(), 1);
+        let (text, count) = redact("using hf_abcdef123 from /home/op\napi_key=xyz password: \"a b\" auth=ok", &secrets);
+        assert_eq!(text, "using [REDACTED:HF_TOKEN] from /home/op\napi_key=[REDACTED] password: [REDACTED] auth=[REDACTED]");
+        assert_eq!(count, 4);
+        // Already redacted values aren't counted twice
+        let (text, count) = redact("HF_TOKEN=hf_abcdef123", &secrets);
+        assert_eq!(text, "HF_TOKEN=[REDACTED:HF_TOKEN]");
+        assert_eq!(count, 1);
+    }
+}
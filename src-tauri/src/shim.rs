@@ -0,0 +1,129 @@
+//! Interpreter shims (pyenv, asdf, mise, the Windows Store `python.exe` alias) re-exec the
+//! real interpreter, so the pid the viewer spawned may exit while the backend keeps
+//! running under another pid. A suspected shim is resolved to the interpreter it would run
+//! (its `sys.executable`, asked with the backend's own working dir and environment, which
+//! pick the version) and that interpreter is spawned directly.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tauri::Emitter;
+
+use crate::logs::{self, Stream};
+
+/// Path fragments of the shim directories of common version managers
+const SHIM_DIRS: &[&str] = &[
+    "/.pyenv/shims/",
+    "/pyenv-win/shims/",
+    "/.asdf/shims/",
+    "/mise/shims/",
+    "/rtx/shims/",
+    "/WindowsApps/",
+];
+
+/// Payload of `python-shim-resolved`
+#[derive(Clone, serde::Serialize)]
+struct ShimResolved {
+    shim: PathBuf,
+    interpreter: PathBuf,
+}
+
+/// Whether `path` lives in a known shim dir
+fn in_shim_dir(path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    SHIM_DIRS.iter().any(|dir| path.to_ascii_lowercase().contains(&dir.to_ascii_lowercase()))
+}
+
+/// A script (`#!` first line) rather than a native executable; real interpreters never are
+fn is_script(path: &Path) -> bool {
+    use std::io::Read;
+    let mut magic = [0u8; 2];
+    std::fs::File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == *b"#!"
+}
+
+/// `program` as found on PATH when it is a bare name
+fn locate(program: &Path, path_var: Option<&std::ffi::OsStr>) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return Some(program.to_path_buf());
+    }
+    std::env::split_paths(path_var?).map(|dir| dir.join(program)).find(|candidate| candidate.is_file())
+}
+
+fn is_shim(program: &Path, path_var: Option<&std::ffi::OsStr>) -> bool {
+    locate(program, path_var).is_some_and(|path| in_shim_dir(&path) || is_script(&path))
+}
+
+/// Replaces a shim program of `cmd` with the interpreter it resolves to. Anything that
+/// isn't a shim, or can't be resolved, is returned unchanged. `planning` (a launch
+/// preview) resolves the same way but logs and emits nothing.
+pub fn resolve<R: tauri::Runtime>(app: &tauri::AppHandle<R>, cmd: Command, planning: bool) -> Command {
+    let program = PathBuf::from(cmd.get_program());
+    let path_var = match cmd.get_envs().find(|(key, _)| *key == "PATH") {
+        Some((_, value)) => value.map(|v| v.to_os_string()),
+        None => std::env::var_os("PATH"),
+    };
+    if !is_shim(&program, path_var.as_deref()) {
+        return cmd;
+    }
+    let mut probe = Command::new(&program);
+    probe.args(["-c", "import sys; print(sys.executable)"]).stdin(Stdio::null()).stderr(Stdio::null());
+    copy_env_and_cwd(&cmd, &mut probe);
+    let executable = probe
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()))
+        .filter(|p| p.is_absolute() && p.is_file() && !in_shim_dir(p));
+    let Some(interpreter) = executable else {
+        // A wrapper script that isn't a version manager's may just not be a shim
+        if planning || !locate(&program, path_var.as_deref()).is_some_and(|path| in_shim_dir(&path)) {
+            return cmd;
+        }
+        logs::forward_tool_line(app, "shim", Stream::Stderr, &format!(
+            "WARNING: {} looks like an interpreter shim but could not be resolved; the backend's pid may not be tracked",
+            program.display()
+        ));
+        return cmd;
+    };
+    if !planning {
+        logs::forward_tool_line(app, "shim", Stream::Stdout, &format!("Resolved interpreter shim {} to {}", program.display(), interpreter.display()));
+        let _ = app.emit("python-shim-resolved", ShimResolved { shim: program, interpreter: interpreter.clone() });
+    }
+    let mut resolved = Command::new(interpreter);
+    resolved.args(cmd.get_args());
+    copy_env_and_cwd(&cmd, &mut resolved);
+    resolved
+}
+
+fn copy_env_and_cwd(from: &Command, to: &mut Command) {
+    for (key, value) in from.get_envs() {
+        match value {
+            Some(value) => to.env(key, value),
+            None => to.env_remove(key),
+        };
+    }
+    if let Some(dir) = from.get_current_dir() {
+        to.current_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_shims_by_dir_and_shebang() {
+        assert!(in_shim_dir(Path::new("/home/u/.pyenv/shims/python3")));
+        assert!(in_shim_dir(Path::new(r"C:\Users\u\AppData\Local\Microsoft\WindowsApps\python.exe")));
+        assert!(!in_shim_dir(Path::new("/home/u/.pyenv/versions/3.12.1/bin/python3")));
+
+        let dir = std::env::temp_dir().join(format!("tv-shim-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("python3"), "#!/bin/sh\nexec /usr/bin/python3 \"$@\"\n").unwrap();
+        std::fs::write(dir.join("native"), [0x7f, b'E', b'L', b'F']).unwrap();
+        assert!(is_shim(Path::new("python3"), Some(dir.as_os_str())));
+        assert!(!is_shim(&dir.join("native"), None));
+        assert!(!is_shim(Path::new("missing"), Some(dir.as_os_str())));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
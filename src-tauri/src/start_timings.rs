@@ -0,0 +1,96 @@
+//! Lightweight start latency instrumentation: offsets from the start command to the key
+//! points of a launch, kept for the last few runs so regressions are easy to spot.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::Emitter;
+
+/// Number of past runs kept by history()
+const HISTORY_LEN: usize = 10;
+
+/// Milestones of one backend start, in milliseconds since the start command was invoked.
+/// Emitted as `python-start-timings` once the backend is ready.
+#[derive(Clone, Default, serde::Serialize)]
+pub struct StartTimings {
+    /// Unix timestamp in milliseconds of the start command
+    pub started_at_ms: u64,
+    /// Runtime venv check/bootstrap (packaged builds only)
+    pub bootstrap_start_ms: Option<u64>,
+    pub bootstrap_end_ms: Option<u64>,
+    pub spawn_ms: Option<u64>,
+    /// First output line on either stream
+    pub first_line_ms: Option<u64>,
+    /// Backend announced its endpoint (see endpoint_pattern); None if it never did
+    pub ready_ms: Option<u64>,
+}
+
+pub enum Mark {
+    BootstrapStart,
+    BootstrapEnd,
+    Spawn,
+    FirstLine,
+}
+
+static CURRENT: Mutex<Option<(Instant, StartTimings)>> = Mutex::new(None);
+static HISTORY: Mutex<VecDeque<StartTimings>> = Mutex::new(VecDeque::new());
+// Lets the readers skip the lock once the first line was recorded
+static SAW_FIRST_LINE: AtomicBool = AtomicBool::new(false);
+
+/// Starts timing a new launch; an unfinished previous one goes to the history as is
+pub fn begin() {
+    let started_at_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let previous = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).replace((Instant::now(), StartTimings { started_at_ms, ..Default::default() }));
+    SAW_FIRST_LINE.store(false, Ordering::SeqCst);
+    if let Some((_, timings)) = previous.filter(|(_, t)| t.ready_ms.is_none()) {
+        push_history(timings);
+    }
+}
+
+/// Records `mark` for the current launch (first occurrence wins)
+pub fn mark(mark: Mark) {
+    if matches!(mark, Mark::FirstLine) && SAW_FIRST_LINE.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((begun, timings)) = current.as_mut() else { return };
+    let field = match mark {
+        Mark::BootstrapStart => &mut timings.bootstrap_start_ms,
+        Mark::BootstrapEnd => &mut timings.bootstrap_end_ms,
+        Mark::Spawn => &mut timings.spawn_ms,
+        Mark::FirstLine => &mut timings.first_line_ms,
+    };
+    field.get_or_insert(begun.elapsed().as_millis() as u64);
+}
+
+/// Records readiness, stores the run in the history and emits `python-start-timings`
+pub fn ready<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    let timings = {
+        let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((begun, timings)) = current.as_mut().filter(|(_, t)| t.ready_ms.is_none()) else { return };
+        timings.ready_ms = Some(begun.elapsed().as_millis() as u64);
+        timings.clone()
+    };
+    push_history(timings.clone());
+    let _ = app.emit("python-start-timings", timings);
+}
+
+/// Timings of the most recent launch, finished or not
+pub fn last() -> Option<StartTimings> {
+    CURRENT.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|(_, t)| t.clone())
+}
+
+/// Past runs, oldest first
+pub fn history() -> Vec<StartTimings> {
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+}
+
+fn push_history(timings: StartTimings) {
+    let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(timings);
+}
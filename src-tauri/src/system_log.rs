@@ -0,0 +1,69 @@
+//! Optional mirror of lifecycle events and backend errors to the platform system log
+//! (syslog on Unix, the Event Log on Windows), for unattended deployments where nobody
+//! watches the UI console. Enabled via the `system_log` config flag.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::logs::Level;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Writes `message` to the system log if enabled; failures are ignored
+pub fn log(level: Level, message: &str) {
+    if ENABLED.load(Ordering::SeqCst) {
+        write(level, message);
+    }
+}
+
+#[cfg(unix)]
+fn write(level: Level, message: &str) {
+    use std::ffi::CString;
+    use std::sync::Once;
+
+    static OPEN: Once = Once::new();
+    OPEN.call_once(|| unsafe {
+        // openlog keeps the pointer, so the ident must live for the whole process
+        libc::openlog(c"televoodoo-viewer".as_ptr(), libc::LOG_PID, libc::LOG_USER);
+    });
+    let priority = match level {
+        Level::Debug => libc::LOG_DEBUG,
+        Level::Info => libc::LOG_INFO,
+        Level::Warning => libc::LOG_WARNING,
+        Level::Error => libc::LOG_ERR,
+    };
+    let Ok(message) = CString::new(message.replace('\0', "")) else { return };
+    unsafe { libc::syslog(priority, c"%s".as_ptr(), message.as_ptr()) };
+}
+
+#[cfg(windows)]
+fn write(level: Level, message: &str) {
+    use windows_sys::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE,
+        EVENTLOG_WARNING_TYPE,
+    };
+
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let kind = match level {
+        Level::Debug | Level::Info => EVENTLOG_INFORMATION_TYPE,
+        Level::Warning => EVENTLOG_WARNING_TYPE,
+        Level::Error => EVENTLOG_ERROR_TYPE,
+    };
+    let source = wide("televoodoo-viewer");
+    let message = wide(message);
+    unsafe {
+        let handle = RegisterEventSourceW(std::ptr::null(), source.as_ptr());
+        if handle.is_null() {
+            return;
+        }
+        let strings = [message.as_ptr()];
+        ReportEventW(handle, kind, 0, 0, std::ptr::null_mut(), 1, 0, strings.as_ptr(), std::ptr::null());
+        DeregisterEventSource(handle);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn write(_level: Level, _message: &str) {}
@@ -0,0 +1,443 @@
+//! Lifecycle tests driving start_python/stop_python/python_status against
+//! `tests/fixtures/fake-python` on a mock Tauri runtime.
+
+use super::*;
+use std::sync::{Arc, MutexGuard};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::Listener;
+
+// The backend state is global, so lifecycle tests must not overlap
+static SERIAL: Mutex<()> = Mutex::new(());
+
+const EVENTS: &[&str] = &[
+    "python-line",
+    "python-error",
+    "python-exited",
+    "python-connection-lost",
+    "python-log-flush-complete",
+    "python-spawned",
+    "python-log",
+    "python-replay-finished",
+    "python-runtime-expired",
+];
+
+struct Harness {
+    app: tauri::App<MockRuntime>,
+    events: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+    _serial: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        // Keep config.json and backend.log out of the real user dirs
+        let root = std::env::temp_dir().join(format!("televoodoo-viewer-tests-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", root.join("config"));
+        std::env::set_var("XDG_DATA_HOME", root.join("data"));
+        std::env::remove_var("FAKE_PYTHON_SLEEP");
+        std::env::remove_var("FAKE_PYTHON_EXIT");
+        std::env::remove_var("FAKE_PYTHON_RPC");
+
+        let mut context = mock_context(noop_assets());
+        context.config_mut().identifier = "com.mana-robotics.televoodoo-viewer.tests".to_string();
+        let app = mock_builder().build(context).expect("failed to build mock app");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        for name in EVENTS {
+            let events = events.clone();
+            app.listen_any(*name, move |event| {
+                let payload = serde_json::from_str(event.payload()).unwrap_or(serde_json::Value::Null);
+                events.lock().unwrap().push((name.to_string(), payload));
+            });
+        }
+        Self { app, events, _serial: serial }
+    }
+
+    fn handle(&self) -> tauri::AppHandle<MockRuntime> {
+        self.app.handle().clone()
+    }
+
+    fn start(&self, config: StartConfig) -> Result<(), PythonError> {
+        tauri::async_runtime::block_on(start_python(self.handle(), config))
+    }
+
+    fn stop(&self) {
+        tauri::async_runtime::block_on(stop_python(self.handle())).unwrap();
+    }
+
+    fn payloads(&self, name: &str) -> Vec<serde_json::Value> {
+        let events = self.events.lock().unwrap();
+        events.iter().filter(|(n, _)| n == name).map(|(_, p)| p.clone()).collect()
+    }
+
+    /// Waits up to 5 s for the first `name` event
+    fn wait_for(&self, name: &str) -> serde_json::Value {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(payload) = self.payloads(name).into_iter().next() {
+                return payload;
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for {}", name);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        cleanup_python();
+    }
+}
+
+fn fake_python() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("fake-python")
+}
+
+fn fake_config() -> StartConfig {
+    StartConfig {
+        connection: "wifi".to_string(),
+        name: None,
+        code: None,
+        upsample_hz: None,
+        rate_limit_hz: None,
+        config_file: None,
+        log_tag: None,
+        priority: None,
+        prefer_bundled: false,
+        interpreter: Some(fake_python()),
+        profile: None,
+        entry: EntryPoint::default(),
+        cpu_quota: None,
+        mem_limit: None,
+        elevated: false,
+        detached: false,
+        max_runtime_secs: None,
+    }
+}
+
+#[test]
+fn forwards_output_and_reports_exit_code() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_EXIT", "3");
+    harness.start(fake_config()).unwrap();
+
+    let exited = harness.wait_for("python-exited");
+    assert_eq!(exited["code"], 3);
+    assert_eq!(exited["success"], false);
+    assert_eq!(
+        harness.payloads("python-line"),
+        vec![serde_json::json!("fake televoodoo started: -m televoodoo --connection wifi")]
+    );
+    assert_eq!(harness.payloads("python-error"), vec![serde_json::json!("fake televoodoo warning")]);
+    // Both streams share one receive-order sequence
+    let seqs: Vec<u64> = harness.payloads("python-log").iter().filter_map(|l| l["seq"].as_u64()).collect();
+    assert_eq!(seqs.len(), 2);
+    assert_eq!(seqs[1], seqs[0] + 1);
+    let throughput = log_throughput();
+    assert_eq!((throughput.stdout.lines, throughput.stderr.lines, throughput.queued_lines), (1, 1, 0));
+
+    let status = python_status();
+    assert!(!status.running);
+    assert_eq!(status.interpreter, Some(fake_python()));
+    assert_eq!(status.encoding.as_deref(), Some("utf-8"));
+}
+
+#[test]
+fn log_level_filters_log_view_but_not_protocol() {
+    let harness = Harness::new();
+    config::update(&harness.handle(), |c| c.log_level = Some(Level::Error)).unwrap();
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-exited");
+    config::update(&harness.handle(), |c| c.log_level = None).unwrap();
+    logs::set_min_level(None);
+
+    assert_eq!(harness.payloads("python-line").len(), 1);
+    assert_eq!(harness.payloads("python-error").len(), 1);
+    assert!(harness.payloads("python-log").iter().all(|l| l["source"] != "backend"));
+}
+
+#[test]
+fn unparsable_config_is_reported_and_never_overwritten() {
+    let harness = Harness::new();
+    let app = harness.handle();
+    let path = config::config_path(&app).unwrap();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let broken = "{ \"log_level\": \"error\", }";
+    std::fs::write(&path, broken).unwrap();
+
+    assert!(config::reload(&app).err().is_some_and(|e| e.contains("line 1")));
+    assert!(get_config(app.clone()).is_err());
+    assert!(config::update(&app, |c| c.last_profile = Some("lab".to_string())).is_err());
+    assert!(config::replace(&app, config::ViewerConfig::default()).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+
+    std::fs::remove_file(&path).unwrap();
+    config::reload(&app).unwrap();
+    assert!(get_config(app).is_ok());
+}
+
+#[test]
+fn pausing_keeps_protocol_events() {
+    let harness = Harness::new();
+    pause_logging();
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-exited");
+    drain_readers(Duration::from_secs(2));
+
+    assert_eq!(harness.payloads("python-line").len(), 1);
+    assert_eq!(harness.payloads("python-error").len(), 1);
+    assert!(harness.payloads("python-log").iter().all(|l| l["source"] != "backend"));
+    assert_eq!(logs::resume(), 2);
+}
+
+#[test]
+fn crash_records_stderr_tail_until_next_start() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_EXIT", "1");
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-exited");
+
+    let crash = get_last_crash().expect("crash should be recorded");
+    assert_eq!(crash.code, Some(1));
+    assert_eq!(crash.stderr_tail, vec!["fake televoodoo warning".to_string()]);
+
+    std::env::set_var("FAKE_PYTHON_EXIT", "0");
+    harness.start(fake_config()).unwrap();
+    assert!(get_last_crash().is_none());
+}
+
+#[test]
+fn connection_lost_comes_after_the_last_lines() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_EXIT", "1");
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-exited");
+    drain_readers(Duration::from_secs(2));
+
+    // Not reported when the exit watcher reaps the backend first
+    let events = harness.events.lock().unwrap();
+    let position = |name: &str| events.iter().position(|(n, _)| n == name);
+    if let Some(lost) = position("python-connection-lost") {
+        assert!(lost > position("python-line").unwrap());
+        assert!(lost > position("python-error").unwrap());
+    }
+}
+
+#[test]
+fn passes_optional_args_to_backend() {
+    let harness = Harness::new();
+    let config = StartConfig { name: Some("robot".to_string()), upsample_hz: Some(200.0), ..fake_config() };
+    harness.start(config).unwrap();
+
+    harness.wait_for("python-exited");
+    assert_eq!(
+        harness.payloads("python-line"),
+        vec![serde_json::json!("fake televoodoo started: -m televoodoo --connection wifi --name robot --upsample-hz 200")]
+    );
+}
+
+#[test]
+fn runs_configured_entry_module() {
+    let harness = Harness::new();
+    let config = StartConfig { entry: EntryPoint::Module("televoodoo_fork.cli".to_string()), ..fake_config() };
+    harness.start(config).unwrap();
+
+    assert_eq!(harness.wait_for("python-spawned")["entry"], serde_json::json!({ "module": "televoodoo_fork.cli" }));
+    harness.wait_for("python-exited");
+    assert_eq!(
+        harness.payloads("python-line"),
+        vec![serde_json::json!("fake televoodoo started: -m televoodoo_fork.cli --connection wifi")]
+    );
+    let bad = StartConfig { entry: EntryPoint::Module("rm -rf".to_string()), ..fake_config() };
+    assert!(harness.start(bad).is_err());
+}
+
+#[test]
+fn preview_resolves_without_spawning() {
+    let harness = Harness::new();
+    let config = StartConfig { name: Some("robot".to_string()), ..fake_config() };
+    let plan = plan_start(&harness.handle(), &config).unwrap();
+
+    assert_eq!(plan.interpreter, fake_python());
+    assert_eq!(plan.args, ["-m", "televoodoo", "--connection", "wifi", "--name", "robot"]);
+    assert!(plan.env.iter().any(|e| e.name == "PYTHONUNBUFFERED" && e.value.as_deref() == Some("1")));
+    assert!(!plan.bootstrap && !plan.reattach);
+    assert_eq!(plan.steps, [launch_plan::LaunchStep::Spawn]);
+    assert!(!backend_running());
+    assert!(harness.payloads("python-spawned").is_empty());
+}
+
+#[test]
+fn stop_terminates_running_backend() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_SLEEP", "30");
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-line");
+
+    let status = python_status();
+    assert!(status.running);
+    assert!(status.pid.is_some());
+
+    harness.stop();
+    assert!(!python_status().running);
+    assert_eq!(harness.wait_for("python-log-flush-complete")["drained"], true);
+
+    // A requested stop is not reported as an exit or a lost connection
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(harness.payloads("python-exited").is_empty());
+    assert!(harness.payloads("python-connection-lost").is_empty());
+}
+
+#[test]
+fn max_runtime_stops_backend() {
+    let harness = Harness::new();
+    assert!(harness.start(StartConfig { max_runtime_secs: Some(0), ..fake_config() }).is_err());
+    std::env::set_var("FAKE_PYTHON_SLEEP", "30");
+    harness.start(StartConfig { max_runtime_secs: Some(1), ..fake_config() }).unwrap();
+    harness.wait_for("python-line");
+
+    assert_eq!(harness.wait_for("python-runtime-expired")["max_runtime_secs"], 1);
+    assert!(!python_status().running);
+    assert_eq!(harness.payloads("python-log-flush-complete").len(), 1);
+    assert!(harness.payloads("python-exited").is_empty());
+}
+
+#[test]
+fn ensure_running_starts_only_once() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_SLEEP", "30");
+    let handle = harness.handle();
+    let ensure = || tauri::async_runtime::block_on(ensure_running(handle.clone(), fake_config())).unwrap();
+
+    // Concurrent calls, e.g. two reconnection timers firing together
+    let started: Vec<bool> = std::thread::scope(|scope| {
+        let calls: Vec<_> = (0..2).map(|_| scope.spawn(ensure)).collect();
+        calls.into_iter().map(|call| call.join().unwrap()).collect()
+    });
+    assert_eq!(started.iter().filter(|started| **started).count(), 1);
+    harness.wait_for("python-line");
+    let pid = python_status().pid;
+    assert!(!ensure());
+    assert_eq!(python_status().pid, pid);
+}
+
+#[test]
+fn start_is_refused_while_another_is_in_progress() {
+    let harness = Harness::new();
+    START_IN_PROGRESS.store(true, Ordering::SeqCst);
+    let result = harness.start(fake_config());
+    START_IN_PROGRESS.store(false, Ordering::SeqCst);
+
+    assert!(result.unwrap_err().to_string().contains("already in progress"));
+    assert!(harness.payloads("python-spawned").is_empty());
+}
+
+#[test]
+fn rpc_responses_are_routed_to_the_caller() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_RPC", "1");
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-line");
+
+    let result = control::call("ping", serde_json::Value::Null, Duration::from_secs(5)).unwrap();
+    assert_eq!(result, "pong");
+    // The response is consumed by the control channel, not logged
+    assert_eq!(harness.payloads("python-line").len(), 1);
+}
+
+#[test]
+fn wait_for_line_returns_the_matching_line() {
+    let harness = Harness::new();
+    let waiter = std::thread::spawn(|| tauri::async_runtime::block_on(wait_for_line("warn(ing)?$".to_string(), 5000)));
+    // Let the matcher register before the backend prints
+    std::thread::sleep(Duration::from_millis(100));
+    harness.start(fake_config()).unwrap();
+    assert_eq!(waiter.join().unwrap().unwrap(), "fake televoodoo warning");
+
+    let timeout = tauri::async_runtime::block_on(wait_for_line("never".to_string(), 50));
+    assert!(timeout.unwrap_err().starts_with("Timed out"));
+}
+
+#[test]
+fn recorded_session_replays_marked_as_replayed() {
+    let harness = Harness::new();
+    let path = std::env::temp_dir().join(format!("tv-session-{}.tvlog", std::process::id()));
+    start_recording(path.clone()).unwrap();
+    harness.start(fake_config()).unwrap();
+    harness.wait_for("python-exited");
+    harness.stop();
+    assert_eq!(stop_recording().unwrap(), 2);
+
+    let live = harness.payloads("python-log").len();
+    replay_log(harness.handle(), path.clone(), Some(1000.0)).unwrap();
+    assert_eq!(harness.wait_for("python-replay-finished")["lines"], 2);
+    let logs = harness.payloads("python-log");
+    assert!(logs[live..].iter().all(|l| l["replayed"] == true));
+    assert_eq!(logs[live]["text"], logs[0]["text"]);
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Zombie processes whose parent is this test binary
+#[cfg(target_os = "linux")]
+fn zombie_children() -> usize {
+    let me = std::process::id().to_string();
+    std::fs::read_dir("/proc")
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+        .filter(|stat| {
+            // `pid (comm) state ppid ...`; comm may itself contain spaces or parens
+            let fields: Vec<&str> = stat.rsplit_once(')').map(|(_, rest)| rest.split_whitespace().collect()).unwrap_or_default();
+            fields.first() == Some(&"Z") && fields.get(1) == Some(&me.as_str())
+        })
+        .count()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn short_lived_children_are_reaped() {
+    let harness = Harness::new();
+    std::env::set_var("FAKE_PYTHON_SLEEP", "30");
+    // Rapid restarts replace the running child without a stop in between
+    for _ in 0..5 {
+        harness.start(fake_config()).unwrap();
+    }
+    harness.stop();
+    for _ in 0..20 {
+        logs::run_streamed(&harness.handle(), &mut Command::new("true"), "test").unwrap();
+        preflight::interpreter_arch(&fake_python());
+    }
+    // Exit watchers and snapshot threads reap asynchronously
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while zombie_children() > 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(zombie_children(), 0);
+}
+
+#[test]
+fn missing_interpreter_is_reported() {
+    let harness = Harness::new();
+    let config = StartConfig { interpreter: Some(PathBuf::from("/nonexistent/python")), ..fake_config() };
+    let err = harness.start(config).unwrap_err();
+    assert_eq!(err.kind(), "interpreter_not_found");
+}
+
+#[test]
+fn packaged_cwd_matches_interpreter_source() {
+    let root = std::env::temp_dir().join(format!("tv-cwd-{}", std::process::id()));
+    let (bundled, runtime) = (root.join("bundled"), root.join("runtime"));
+    for dir in [&bundled, &runtime] {
+        std::fs::create_dir_all(dir.join("televoodoo")).unwrap();
+        std::fs::write(dir.join("televoodoo").join("pyproject.toml"), "").unwrap();
+    }
+    let cwd = |source| backend_cwd(source, Some(&bundled), Some(&runtime));
+
+    assert_eq!(cwd(PythonSource::Runtime), Some(runtime.join("televoodoo")));
+    assert_eq!(cwd(PythonSource::Bundled), Some(bundled.join("televoodoo")));
+    assert_eq!(cwd(PythonSource::System), Some(bundled.join("televoodoo")));
+    // A runtime interpreter never falls back to the bundled source
+    std::fs::remove_file(runtime.join("televoodoo").join("pyproject.toml")).unwrap();
+    assert_eq!(cwd(PythonSource::Runtime), None);
+    let _ = std::fs::remove_dir_all(&root);
+}
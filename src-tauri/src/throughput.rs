@@ -0,0 +1,128 @@
+//! Pipe read throughput of the backend's stdout and stderr, for telling whether the
+//! viewer or the backend is the bottleneck under heavy telemetry. Readers and the emitter
+//! only bump relaxed atomics; rates are derived once per interval by the reporter.
+//!
+//! The readers never drop lines: the channel to the emitter is unbounded, so a viewer
+//! that can't keep up shows as a growing `queued_lines`. `dropped_lines` counts lines
+//! that were read but not emitted to the UI (paused, muted, or below `log_level`).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::Emitter;
+
+use crate::logs::Stream;
+
+/// Interval of `python-throughput` events and of the rates in ThroughputStats
+const INTERVAL: Duration = Duration::from_secs(1);
+
+struct Counters {
+    bytes: AtomicU64,
+    lines: AtomicU64,
+}
+
+static STDOUT: Counters = Counters { bytes: AtomicU64::new(0), lines: AtomicU64::new(0) };
+static STDERR: Counters = Counters { bytes: AtomicU64::new(0), lines: AtomicU64::new(0) };
+static FORWARDED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+// Stats as of the reporter's last tick, returned by log_throughput
+static LATEST: Mutex<Option<ThroughputStats>> = Mutex::new(None);
+
+/// Totals since the backend started and rates over the last interval, for one pipe
+#[derive(Clone, Default, serde::Serialize)]
+pub struct StreamThroughput {
+    pub bytes: u64,
+    pub lines: u64,
+    pub bytes_per_sec: f64,
+    pub lines_per_sec: f64,
+}
+
+/// Returned by log_throughput and emitted as `python-throughput`
+#[derive(Clone, Default, serde::Serialize)]
+pub struct ThroughputStats {
+    pub stdout: StreamThroughput,
+    pub stderr: StreamThroughput,
+    /// Lines read but not yet processed by the emitter
+    pub queued_lines: u64,
+    /// Lines read but not emitted to the UI
+    pub dropped_lines: u64,
+    pub interval_ms: u64,
+}
+
+fn counters(stream: Stream) -> &'static Counters {
+    match stream {
+        Stream::Stdout => &STDOUT,
+        Stream::Stderr => &STDERR,
+    }
+}
+
+/// Zeroes all counters; called when a backend is spawned
+pub fn reset() {
+    for counters in [&STDOUT, &STDERR] {
+        counters.bytes.store(0, Ordering::Relaxed);
+        counters.lines.store(0, Ordering::Relaxed);
+    }
+    FORWARDED.store(0, Ordering::Relaxed);
+    DROPPED.store(0, Ordering::Relaxed);
+    *LATEST.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Counts a line of `len` bytes (without its newline) read from `stream`
+pub fn record_read(stream: Stream, len: usize) {
+    let counters = counters(stream);
+    counters.bytes.fetch_add(len as u64 + 1, Ordering::Relaxed);
+    counters.lines.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a line handled by the emitter
+pub fn record_forwarded() {
+    FORWARDED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a line withheld from the UI
+pub fn record_dropped() {
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Stats as of the last interval, or totals without rates before the first one
+pub fn latest() -> ThroughputStats {
+    LATEST.lock().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_else(|| snapshot(&[(0, 0), (0, 0)], None))
+}
+
+/// `previous` holds (bytes, lines) per stream at the start of the interval
+fn snapshot(previous: &[(u64, u64); 2], elapsed: Option<Duration>) -> ThroughputStats {
+    let secs = elapsed.map(|e| e.as_secs_f64()).filter(|s| *s > 0.0);
+    let stream = |counters: &Counters, (bytes_before, lines_before): (u64, u64)| {
+        let (bytes, lines) = (counters.bytes.load(Ordering::Relaxed), counters.lines.load(Ordering::Relaxed));
+        let rate = |now: u64, before: u64| secs.map_or(0.0, |s| now.saturating_sub(before) as f64 / s);
+        StreamThroughput { bytes, lines, bytes_per_sec: rate(bytes, bytes_before), lines_per_sec: rate(lines, lines_before) }
+    };
+    let (stdout, stderr) = (stream(&STDOUT, previous[0]), stream(&STDERR, previous[1]));
+    let queued_lines = (stdout.lines + stderr.lines).saturating_sub(FORWARDED.load(Ordering::Relaxed));
+    ThroughputStats {
+        stdout,
+        stderr,
+        queued_lines,
+        dropped_lines: DROPPED.load(Ordering::Relaxed),
+        interval_ms: elapsed.map_or(0, |e| e.as_millis() as u64),
+    }
+}
+
+/// Emits `python-throughput` every INTERVAL while `running` holds
+pub fn report<R: tauri::Runtime>(app: &tauri::AppHandle<R>, running: impl Fn() -> bool) {
+    let mut previous = [(0, 0); 2];
+    let mut last_tick = Instant::now();
+    loop {
+        std::thread::sleep(INTERVAL);
+        if !running() {
+            return;
+        }
+        let stats = snapshot(&previous, Some(last_tick.elapsed()));
+        last_tick = Instant::now();
+        previous = [(stats.stdout.bytes, stats.stdout.lines), (stats.stderr.bytes, stats.stderr.lines)];
+        *LATEST.lock().unwrap_or_else(|e| e.into_inner()) = Some(stats.clone());
+        let _ = app.emit("python-throughput", stats);
+    }
+}
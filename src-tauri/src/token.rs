@@ -0,0 +1,35 @@
+//! Random tokens that gate local access (the log stream, a detached backend, a log share
+//! preview). They come from the OS random number generator and are compared in constant
+//! time, so neither guessing nor timing a wrong guess narrows them down.
+
+/// Bytes of randomness per token, written as twice as many hex digits
+const TOKEN_BYTES: usize = 16;
+
+/// A fresh token of 32 lowercase hex digits
+pub fn random() -> Result<String, String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Could not generate a random token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Whether `given` equals `token`, taking the same time wherever they differ
+pub fn matches(given: &str, token: &str) -> bool {
+    let (given, token) = (given.as_bytes(), token.as_bytes());
+    given.len() == token.len() && given.iter().zip(token).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let (a, b) = (random().unwrap(), random().unwrap());
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+        assert!(matches(&a, &a.clone()));
+        assert!(!matches(&b, &a));
+        assert!(!matches(&a[..31], &a));
+    }
+}
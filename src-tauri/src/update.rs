@@ -0,0 +1,345 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+use tauri::Emitter;
+
+use crate::bootstrap::{self, find_bundled_python_dir};
+use crate::config;
+use crate::logs::{self, Stream};
+
+/// Bundled vs runtime televoodoo versions, returned by check_televoodoo_update
+#[derive(Clone, serde::Serialize)]
+pub struct UpdateStatus {
+    pub bundled_version: Option<String>,
+    pub runtime_version: Option<String>,
+    /// True when the bundle ships a newer package than the runtime copy
+    pub update_available: bool,
+    /// True when the bundled source differs from what was last installed, whatever the
+    /// versions say
+    pub bundle_changed: bool,
+}
+
+/// Reads the package version from `pyproject.toml` (`[project] version = "..."`),
+/// falling back to `__version__` in the package's `__init__.py`
+pub fn read_package_version(televoodoo_dir: &Path) -> Option<String> {
+    if let Ok(text) = std::fs::read_to_string(televoodoo_dir.join("pyproject.toml")) {
+        let mut in_project = false;
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_project = line == "[project]";
+            } else if in_project {
+                if let Some(version) = parse_assignment(line, "version") {
+                    return Some(version);
+                }
+            }
+        }
+    }
+    for init in [
+        televoodoo_dir.join("src").join("televoodoo").join("__init__.py"),
+        televoodoo_dir.join("televoodoo").join("__init__.py"),
+    ] {
+        if let Ok(text) = std::fs::read_to_string(init) {
+            if let Some(version) = text.lines().find_map(|l| parse_assignment(l.trim(), "__version__")) {
+                return Some(version);
+            }
+        }
+    }
+    None
+}
+
+// Parses `key = "value"` (or single quotes), returning the unquoted value
+fn parse_assignment(line: &str, key: &str) -> Option<String> {
+    let rest = line.strip_prefix(key)?.trim_start().strip_prefix('=')?.trim();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = rest[1..].split(quote).next()?;
+    Some(value.to_string())
+}
+
+/// Compares dotted versions numerically component by component (`1.10.0 > 1.9.2`).
+/// Non-numeric components (e.g. `0rc1`) compare by their leading digits, then as text.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| -> Vec<(u64, String)> {
+        v.trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map(|part| {
+                let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+                (digits.parse().unwrap_or(0), part[digits.len()..].to_string())
+            })
+            .collect()
+    };
+    let (a, b) = (split(a), split(b));
+    for i in 0..a.len().max(b.len()) {
+        let pa = a.get(i).cloned().unwrap_or_default();
+        let pb = b.get(i).cloned().unwrap_or_default();
+        let ord = pa.0.cmp(&pb.0).then_with(|| match (pa.1.is_empty(), pb.1.is_empty()) {
+            // A pre-release suffix sorts before the plain release
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            _ => pa.1.cmp(&pb.1),
+        });
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+fn bundled_televoodoo_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    find_bundled_python_dir(app).map(|dir| dir.join("televoodoo"))
+}
+
+/// Interpreter of the runtime venv under `runtime_py_dir`
+fn venv_python(runtime_py_dir: &Path) -> PathBuf {
+    runtime_py_dir.join(".venv").join("bin").join("python")
+}
+
+pub fn runtime_televoodoo_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    bootstrap::runtime_python_dir(app).map(|dir| dir.join("televoodoo"))
+}
+
+pub fn check<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> UpdateStatus {
+    let bundled_version = bundled_televoodoo_dir(app).and_then(|d| read_package_version(&d));
+    let runtime_version = runtime_televoodoo_dir(app).and_then(|d| read_package_version(&d));
+    let update_available = match (&bundled_version, &runtime_version) {
+        (Some(bundled), Some(runtime)) => compare_versions(bundled, runtime) == Ordering::Greater,
+        _ => false,
+    };
+    let fingerprint = bundle_fingerprint(app);
+    let bundle_changed = fingerprint.is_some() && fingerprint != config::get(app).last_applied_bundle_fingerprint;
+    UpdateStatus { bundled_version, runtime_version, update_available, bundle_changed }
+}
+
+/// Files that change without the source changing: bytecode, build output, OS metadata
+fn is_volatile(name: &str) -> bool {
+    name.starts_with('.')
+        || matches!(name, "__pycache__" | "build" | "dist" | "Thumbs.db")
+        || name.ends_with(".pyc")
+        || name.ends_with(".pyo")
+        || name.ends_with(".egg-info")
+}
+
+/// Adds `(relative path, sha256 of contents)` for every non-volatile file under `dir`
+fn hash_tree(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if is_volatile(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            hash_tree(root, &path, files)?;
+        } else {
+            let digest = Sha256::digest(std::fs::read(&path)?);
+            // `/` separators, so the digest is the same on every platform
+            let relative: Vec<String> = path.strip_prefix(root).unwrap_or(&path).components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+            files.push((relative.join("/"), hex(&digest)));
+        }
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Digest of the source under `dir` that copy_televoodoo_source would copy. Files are
+/// sorted by path before hashing, so directory listing order doesn't matter.
+pub fn fingerprint_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>, dir: &Path) -> std::io::Result<String> {
+    let cfg = config::get(app);
+    let mut files = Vec::new();
+    if cfg.copy_full_source {
+        hash_tree(dir, dir, &mut files)?;
+    } else {
+        for name in cfg.source_include.iter().filter(|n| !n.is_empty() && !n.contains(['/', '\\']) && *n != "..") {
+            let entry = dir.join(name);
+            if entry.is_dir() {
+                hash_tree(dir, &entry, &mut files)?;
+            } else if entry.is_file() {
+                files.push((name.clone(), hex(&Sha256::digest(std::fs::read(&entry)?))));
+            }
+        }
+    }
+    files.sort();
+    let mut hasher = Sha256::new();
+    for (path, digest) in &files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(digest.as_bytes());
+        hasher.update([b'\n']);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// fingerprint_dir of the bundled televoodoo package, if there is one
+pub fn bundle_fingerprint<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<String> {
+    bundled_televoodoo_dir(app).filter(|d| d.exists()).and_then(|d| fingerprint_dir(app, &d).ok())
+}
+
+/// Remembers the current bundle as installed, after a bootstrap or reinstall from it
+pub fn record_bundle_fingerprint<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(fingerprint) = bundle_fingerprint(app) {
+        let _ = config::update(app, |c| c.last_applied_bundle_fingerprint = Some(fingerprint));
+    }
+}
+
+/// Replaces the runtime televoodoo copy with the bundled one and reinstalls it into the
+/// existing runtime venv (requirements first, then the package itself).
+///
+/// The new source is staged next to the current copy and only swapped in once pip
+/// succeeds, so a failed reinstall keeps the previously working version in place.
+pub fn apply<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    reinstall(app, true, &mut |_| {})
+}
+
+/// Step of refresh_from_bundle, reported as `python-refresh-progress`
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStep {
+    Copying,
+    Installing,
+    Swapping,
+}
+
+/// Payload of `python-refresh-progress`
+#[derive(Clone, serde::Serialize)]
+struct RefreshProgress {
+    step: RefreshStep,
+    pct: u8,
+}
+
+/// Payload of `python-refresh-done`
+#[derive(Clone, serde::Serialize)]
+struct RefreshDone {
+    version: Option<String>,
+}
+
+/// Re-copies the bundled source and reinstalls only the package (`--no-deps`), whatever
+/// the versions: a quick code sync for iterating on the bundled source in a packaged
+/// build. Emits `python-refresh-progress` per step and `python-refresh-done` at the end.
+pub fn refresh_from_bundle<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    reinstall(app, false, &mut |step| {
+        let pct = match step {
+            RefreshStep::Copying => 0,
+            RefreshStep::Installing => 30,
+            RefreshStep::Swapping => 90,
+        };
+        let _ = app.emit("python-refresh-progress", RefreshProgress { step, pct });
+    })?;
+    let version = runtime_televoodoo_dir(app).and_then(|d| read_package_version(&d));
+    let _ = app.emit("python-refresh-done", RefreshDone { version });
+    Ok(())
+}
+
+fn reinstall<R: tauri::Runtime>(app: &tauri::AppHandle<R>, with_requirements: bool, on_step: &mut dyn FnMut(RefreshStep)) -> Result<(), String> {
+    let bundled = bundled_televoodoo_dir(app)
+        .filter(|d| d.join("pyproject.toml").exists())
+        .ok_or_else(|| "No bundled televoodoo package found".to_string())?;
+    let runtime_py_dir = bootstrap::runtime_python_dir(app)
+        .ok_or_else(|| "Could not determine app data directory".to_string())?;
+    let runtime_python = venv_python(&runtime_py_dir);
+    if !runtime_python.exists() {
+        return Err("Runtime venv not bootstrapped yet; start the backend once first".to_string());
+    }
+
+    let runtime_televoodoo = runtime_py_dir.join("televoodoo");
+    let staged = runtime_py_dir.join("televoodoo.staged");
+    let _ = std::fs::remove_dir_all(&staged);
+    on_step(RefreshStep::Copying);
+    bootstrap::copy_televoodoo_source(app, &bundled, &staged).map_err(|e| format!("Copying televoodoo failed: {}", e))?;
+
+    on_step(RefreshStep::Installing);
+    let install = || -> Result<(), String> {
+        let req = staged.join("requirements.txt");
+        if with_requirements && req.exists() {
+            pip_install(app, &runtime_python, &["-r"], &req)?;
+        }
+        pip_install(app, &runtime_python, &["--force-reinstall", "--no-deps"], &staged)
+    };
+    if let Err(e) = install() {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Err(e);
+    }
+
+    on_step(RefreshStep::Swapping);
+    let _ = std::fs::remove_dir_all(&runtime_televoodoo);
+    std::fs::rename(&staged, &runtime_televoodoo).map_err(|e| format!("Swapping in updated televoodoo failed: {}", e))?;
+    record_bundle_fingerprint(app);
+    Ok(())
+}
+
+/// Payload of `python-package-updated`
+#[derive(Clone, serde::Serialize)]
+struct PackageUpdated {
+    from: Option<String>,
+    to: String,
+}
+
+/// Called on packaged start: if the app bundle carries a newer televoodoo than the runtime
+/// venv, or source that differs from what was installed (bundle_fingerprint), reinstall
+/// just the package (no venv rebuild). Failures are logged and the old version keeps
+/// running; a successful update is remembered so it isn't re-applied.
+pub fn auto_update<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    // Nothing installed to update yet (no bootstrap, or the runtime copy is gone)
+    let Some(runtime_py_dir) = bootstrap::runtime_python_dir(app) else { return };
+    if !venv_python(&runtime_py_dir).exists() || !runtime_py_dir.join("televoodoo").is_dir() {
+        return;
+    }
+    let status = check(app);
+    let Some(bundled_version) = status.bundled_version.clone() else { return };
+    if !auto_update_due(app, &status) {
+        return;
+    }
+    match apply(app) {
+        Ok(()) => {
+            let _ = config::update(app, |c| c.last_applied_bundle_version = Some(bundled_version.clone()));
+            let _ = app.emit(
+                "python-package-updated",
+                PackageUpdated { from: status.runtime_version, to: bundled_version },
+            );
+        }
+        Err(e) => {
+            logs::forward_tool_line(app, "update", Stream::Stderr, &format!("televoodoo update failed, keeping current version: {}", e));
+        }
+    }
+}
+
+/// Whether auto_update would reinstall for `status`: a newer bundled version not applied
+/// yet, or a changed bundle
+pub fn auto_update_due<R: tauri::Runtime>(app: &tauri::AppHandle<R>, status: &UpdateStatus) -> bool {
+    let Some(ref bundled_version) = status.bundled_version else { return false };
+    let newer = status.update_available
+        && config::get(app).last_applied_bundle_version.as_deref() != Some(bundled_version.as_str());
+    newer || status.bundle_changed
+}
+
+/// `pip install <network args> <args> <target>`; the network args go first so `args` can end
+/// with an option that takes `target` as its value (`-r`)
+fn pip_install_command(python: &Path, network_args: &[String], args: &[&str], target: &Path) -> Command {
+    let mut cmd = Command::new(python);
+    cmd.args(["-m", "pip", "install"]).args(network_args).args(args).arg(target);
+    cmd
+}
+
+fn pip_install<R: tauri::Runtime>(app: &tauri::AppHandle<R>, python: &Path, args: &[&str], target: &Path) -> Result<(), String> {
+    let mut cmd = pip_install_command(python, &config::get(app).pip_network_args(), args, target);
+    match logs::run_streamed(app, &mut cmd, "pip") {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("pip install {} failed with {}", args.join(" "), status)),
+        Err(e) => Err(format!("Could not run pip: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_args_precede_the_requirements_file() {
+        let network_args = config::ViewerConfig::default().pip_network_args();
+        let cmd = pip_install_command(Path::new("python"), &network_args, &["-r"], Path::new("requirements.txt"));
+        let argv: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(argv, ["-m", "pip", "install", "--timeout", "30", "--retries", "5", "-r", "requirements.txt"]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Export and import of the runtime python dir (venv plus televoodoo copy) as a `.tar.gz`,
+//! for provisioning identical machines from one known-good setup. Uses the system `tar`
+//! (bundled with Windows 10 and later).
+//!
+//! A venv is not relocatable across platforms: scripts and `pyvenv.cfg` reference the base
+//! interpreter by absolute path. The archive records the OS and architecture it was made
+//! on and import refuses a mismatch; the base interpreter must also exist at the same path
+//! on the target machine, which import checks via validate_venv.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::bootstrap::{self, VenvHealth};
+use crate::instance_lock;
+
+// Written next to the python dir at the archive root
+const MANIFEST_NAME: &str = "venv-export.json";
+// Not worth shipping: recreated by Python on demand, or broken venvs kept for post-mortem
+const EXCLUDES: &[&str] = &["__pycache__", "*.pyc", ".pip-cache", crate::quarantine::DIR_NAME, instance_lock::LOCK_FILE];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    os: String,
+    arch: String,
+    /// Name of the python dir inside the archive
+    dir_name: String,
+}
+
+/// Tars the runtime python dir (minus caches) to `dest`
+pub fn export<R: tauri::Runtime>(app: &tauri::AppHandle<R>, dest: &Path) -> Result<(), String> {
+    let runtime_dir = bootstrap::runtime_python_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?;
+    if !runtime_dir.join(".venv").is_dir() {
+        return Err(format!("No runtime venv to export at {}", runtime_dir.display()));
+    }
+    let (Some(parent), Some(dir_name)) = (runtime_dir.parent(), runtime_dir.file_name()) else {
+        return Err(format!("Invalid runtime python dir: {}", runtime_dir.display()));
+    };
+    let staging = staging_dir(parent, "export")?;
+    let manifest = Manifest {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        dir_name: dir_name.to_string_lossy().to_string(),
+    };
+    let result = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(staging.join(MANIFEST_NAME), json).map_err(|e| e.to_string()))
+        .and_then(|()| {
+            let mut cmd = Command::new("tar");
+            cmd.arg("-czf").arg(dest);
+            for pattern in EXCLUDES {
+                cmd.arg(format!("--exclude={}", pattern));
+            }
+            cmd.arg("-C").arg(parent).arg(dir_name).arg("-C").arg(&staging).arg(MANIFEST_NAME);
+            run_tar(cmd)
+        });
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Extracts an archive made by export, checks it matches this machine and its venv is
+/// healthy, then replaces the runtime python dir with it while holding the instance lock
+pub fn import<R: tauri::Runtime>(app: &tauri::AppHandle<R>, src: &Path) -> Result<(), String> {
+    let runtime_dir = bootstrap::runtime_python_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?;
+    let parent = runtime_dir.parent().ok_or_else(|| format!("Invalid runtime python dir: {}", runtime_dir.display()))?;
+    let staging = staging_dir(parent, "import")?;
+    let result = extract_checked(src, &staging).and_then(|extracted| {
+        let held = instance_lock::held();
+        instance_lock::acquire(app).map_err(|e| e.to_string())?;
+        let result = replace_dir(&extracted, &runtime_dir);
+        if !held {
+            instance_lock::release();
+        }
+        result
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn extract_checked(src: &Path, staging: &Path) -> Result<PathBuf, String> {
+    let mut cmd = Command::new("tar");
+    cmd.arg("-xzf").arg(src).arg("-C").arg(staging);
+    run_tar(cmd)?;
+
+    let manifest: Manifest = std::fs::read(staging.join(MANIFEST_NAME))
+        .map_err(|_| format!("{} is not a venv export (no {})", src.display(), MANIFEST_NAME))
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| format!("Invalid {}: {}", MANIFEST_NAME, e)))?;
+    if manifest.os != std::env::consts::OS || manifest.arch != std::env::consts::ARCH {
+        return Err(format!(
+            "Venv was exported on {}-{} and cannot run on {}-{}",
+            manifest.os,
+            manifest.arch,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ));
+    }
+    // The name comes from the archive; never let it point outside the staging dir
+    crate::config::validate_python_dir_name(&manifest.dir_name)?;
+    let extracted = staging.join(&manifest.dir_name);
+    match bootstrap::validate_venv(&extracted.join(".venv")) {
+        VenvHealth::Healthy => Ok(extracted),
+        health => Err(format!("Imported venv is unusable on this machine: {}", health.describe())),
+    }
+}
+
+/// Swaps the entries of `new` in for those of `target`, keeping the old ones until every
+/// rename succeeded. The lock file stays where it is, so the instance lock keeps guarding
+/// the dir through the swap.
+fn replace_dir(new: &Path, target: &Path) -> Result<(), String> {
+    let backup = target.with_extension("replaced");
+    let _ = std::fs::remove_dir_all(&backup);
+    for dir in [target, &backup] {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    let result = match move_entries(target, &backup) {
+        Err(e) => Err(format!("Could not move {} aside: {}", target.display(), e)),
+        Ok(()) => move_entries(new, target).map_err(|e| {
+            // Whatever made it in goes back to staging
+            let _ = move_entries(target, new);
+            format!("Could not install imported venv at {}: {}", target.display(), e)
+        }),
+    };
+    if result.is_err() {
+        let _ = move_entries(&backup, target);
+    }
+    let _ = std::fs::remove_dir_all(&backup);
+    result
+}
+
+/// Renames every entry of `from` except the lock file into `to`, stopping at the first failure
+fn move_entries(from: &Path, to: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let name = entry?.file_name();
+        if name != instance_lock::LOCK_FILE {
+            std::fs::rename(from.join(&name), to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+fn staging_dir(parent: &Path, purpose: &str) -> Result<PathBuf, String> {
+    let dir = parent.join(format!(".venv-{}-{}", purpose, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn run_tar(mut cmd: Command) -> Result<(), String> {
+    let output = cmd.output().map_err(|e| format!("Could not run tar: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_dir_keeps_the_lock_file() {
+        let root = std::env::temp_dir().join(format!("tv-replace-{}", std::process::id()));
+        let (new, target) = (root.join("new"), root.join("python"));
+        for dir in [&new, &target] {
+            std::fs::create_dir_all(dir.join(".venv")).unwrap();
+        }
+        std::fs::write(target.join(".venv").join("old"), "").unwrap();
+        std::fs::write(new.join(".venv").join("new"), "").unwrap();
+        std::fs::write(target.join(instance_lock::LOCK_FILE), "held").unwrap();
+
+        replace_dir(&new, &target).unwrap();
+        assert!(target.join(".venv").join("new").exists());
+        assert!(!target.join(".venv").join("old").exists());
+        assert_eq!(std::fs::read_to_string(target.join(instance_lock::LOCK_FILE)).unwrap(), "held");
+        assert!(!target.with_extension("replaced").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,77 @@
+//! The runtime python dirs under the app data dir. Each one (`python` by default) holds a
+//! `.venv` plus its televoodoo copy; `python_dir_name` selects the one used for the next
+//! start.
+
+use std::path::{Path, PathBuf};
+
+use crate::bootstrap::{self, VenvHealth};
+use crate::{config, update};
+
+/// One runtime python dir, returned by list_venvs
+#[derive(Clone, serde::Serialize)]
+pub struct VenvInfo {
+    /// Directory name, as accepted by use_venv
+    pub name: String,
+    pub path: PathBuf,
+    /// From the venv's `pyvenv.cfg`
+    pub python_version: Option<String>,
+    pub televoodoo_version: Option<String>,
+    pub health: VenvHealth,
+    /// Selected for the next start
+    pub active: bool,
+}
+
+/// All dirs under the app data dir that contain a `.venv`, sorted by name
+pub fn list<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<VenvInfo> {
+    let Some(data_dir) = bootstrap::data_dir(app) else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(&data_dir) else { return Vec::new() };
+    let active = config::get(app).python_dir_name;
+    let mut venvs: Vec<VenvInfo> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join(".venv").is_dir())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let venv_root = path.join(".venv");
+            Some(VenvInfo {
+                active: name == active,
+                python_version: python_version(&venv_root),
+                televoodoo_version: update::read_package_version(&path.join("televoodoo")),
+                health: bootstrap::validate_venv(&venv_root),
+                name,
+                path,
+            })
+        })
+        .collect();
+    venvs.sort_by(|a, b| a.name.cmp(&b.name));
+    venvs
+}
+
+/// Selects `name` for the next start after checking its venv is healthy
+pub fn select<R: tauri::Runtime>(app: &tauri::AppHandle<R>, name: &str) -> Result<(), String> {
+    config::validate_python_dir_name(name)?;
+    let data_dir = bootstrap::data_dir(app).ok_or_else(|| "Could not determine app data directory".to_string())?;
+    let venv_root = data_dir.join(name).join(".venv");
+    if !venv_root.is_dir() {
+        return Err(format!("No venv named {:?} in {}", name, data_dir.display()));
+    }
+    match bootstrap::validate_venv(&venv_root) {
+        VenvHealth::Healthy => config::update(app, |c| c.python_dir_name = name.to_string()),
+        health => Err(format!("Venv {:?} is not usable: {}", name, health.describe())),
+    }
+}
+
+/// `version` (or `version_info`) from `pyvenv.cfg`
+fn python_version(venv_root: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(venv_root.join("pyvenv.cfg")).ok()?;
+    let mut version_info = None;
+    for (key, value) in text.lines().filter_map(|l| l.split_once('=')) {
+        match key.trim() {
+            "version" => return Some(value.trim().to_string()),
+            // e.g. `3.12.1.final.0`
+            "version_info" => version_info = Some(value.trim().splitn(4, '.').take(3).collect::<Vec<_>>().join(".")),
+            _ => {}
+        }
+    }
+    version_info
+}
@@ -145,6 +145,12 @@ function processMessage(msg: any) {
   }
 }
 
+// Lifecycle commands reject with { kind, message } (see PythonError in src-tauri)
+function errorMessage(err: unknown): string {
+  if (err && typeof err === 'object' && 'message' in err) return String((err as any).message);
+  return String(err);
+}
+
 export interface StartConfig {
   connection: ConnectionType;
   name?: string;
@@ -212,7 +218,7 @@ export async function startPythonSidecar(config: StartConfig) {
     log('info', 'Python sidecar started');
     serviceState.set('running');
   } catch (err) {
-    log('error', `Failed to start Python sidecar: ${String(err)}`);
+    log('error', `Failed to start Python sidecar: ${errorMessage(err)}`);
     setStatus('disconnected');
     serviceState.set('stopped');
     throw err;